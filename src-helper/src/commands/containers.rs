@@ -0,0 +1,302 @@
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::process::Command;
+
+// How long to wait on an engine API socket before falling back to the CLI
+#[cfg(unix)]
+const API_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+#[derive(Debug, Serialize)]
+pub struct ContainerPort {
+    /// Port inside the container
+    pub container_port: u16,
+    /// Port published on the host, if any
+    pub host_port: Option<u16>,
+    /// Address the published port is bound to
+    pub host_ip: Option<String>,
+    /// "tcp", "udp" or "sctp"
+    pub protocol: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Container {
+    /// Short (12 character) container id
+    pub id: String,
+    pub name: String,
+    pub image: String,
+    /// "running", "exited", "paused", ...
+    pub state: String,
+    /// Human readable status, e.g. "Up 2 hours"
+    pub status: String,
+    /// CLI to run `exec` and `logs` with: "docker" or "podman"
+    pub runtime: &'static str,
+    pub ports: Vec<ContainerPort>,
+}
+
+/// The container CLI on PATH, preferring docker
+pub fn cli() -> Option<&'static str> {
+    ["docker", "podman"]
+        .into_iter()
+        .find(|c| which::which(c).is_ok())
+}
+
+/// The CLI matching an engine, or whichever one is installed (podman
+/// often serves the Docker socket without a `docker` binary)
+fn cli_for(runtime: &'static str) -> &'static str {
+    if which::which(runtime).is_ok() {
+        runtime
+    } else {
+        cli().unwrap_or(runtime)
+    }
+}
+
+/// Engine API sockets that exist on this host, with the engine behind them
+#[cfg(unix)]
+fn sockets() -> Vec<(std::path::PathBuf, &'static str)> {
+    use std::path::PathBuf;
+
+    let engine = |path: &PathBuf| {
+        if path.to_string_lossy().contains("podman") {
+            "podman"
+        } else {
+            "docker"
+        }
+    };
+    let mut paths: Vec<PathBuf> = Vec::new();
+    if let Ok(host) = std::env::var("DOCKER_HOST") {
+        if let Some(path) = host.strip_prefix("unix://") {
+            paths.push(path.into());
+        }
+    }
+    paths.push("/var/run/docker.sock".into());
+    if let Ok(dir) = std::env::var("XDG_RUNTIME_DIR") {
+        paths.push(PathBuf::from(dir).join("podman/podman.sock"));
+    }
+    paths.push("/run/podman/podman.sock".into());
+    paths
+        .into_iter()
+        .filter(|p| p.exists())
+        .map(|p| {
+            let e = engine(&p);
+            (p, e)
+        })
+        .collect()
+}
+
+/// GET a JSON document from the engine API over its Unix socket
+#[cfg(unix)]
+fn api_get(socket: &std::path::Path, path: &str) -> Result<Value> {
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(socket)?;
+    stream.set_read_timeout(Some(API_TIMEOUT))?;
+    // HTTP/1.0 makes the engine close the connection after a plain body
+    write!(stream, "GET {} HTTP/1.0\r\nHost: localhost\r\n\r\n", path)?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+    let text = String::from_utf8_lossy(&response);
+    let (head, body) = text
+        .split_once("\r\n\r\n")
+        .context("malformed API response")?;
+    let status = head.split_whitespace().nth(1).unwrap_or("");
+    if status != "200" {
+        bail!("{} returned {}", path, status);
+    }
+    Ok(serde_json::from_str(body)?)
+}
+
+fn text(v: &Value, key: &str) -> String {
+    v[key].as_str().unwrap_or_default().to_string()
+}
+
+fn short_id(v: &Value, key: &str) -> String {
+    text(v, key).chars().take(12).collect()
+}
+
+/// "8000" or "8000-8001" as an inclusive range
+fn port_range(s: &str) -> Option<(u16, u16)> {
+    match s.split_once('-') {
+        Some((a, b)) => Some((a.parse().ok()?, b.parse().ok()?)),
+        None => {
+            let p = s.parse().ok()?;
+            Some((p, p))
+        }
+    }
+}
+
+/// Parse the `Ports` column of `docker ps`, e.g.
+/// "0.0.0.0:8080->80/tcp, :::8080->80/tcp, 443/tcp"
+fn parse_port_list(s: &str) -> Vec<ContainerPort> {
+    let mut ports = Vec::new();
+    for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (mapping, protocol) = entry.rsplit_once('/').unwrap_or((entry, "tcp"));
+        let (host, inner) = match mapping.split_once("->") {
+            Some((host, inner)) => (Some(host), inner),
+            None => (None, mapping),
+        };
+        let Some((first, last)) = port_range(inner) else {
+            continue;
+        };
+        let host = host.and_then(|h| {
+            let (ip, range) = h.rsplit_once(':')?;
+            Some((
+                ip.trim_matches(['[', ']']).to_string(),
+                port_range(range)?.0,
+            ))
+        });
+        for (i, container_port) in (first..=last).enumerate() {
+            ports.push(ContainerPort {
+                container_port,
+                host_port: host.as_ref().map(|(_, p)| p + i as u16),
+                host_ip: host.as_ref().map(|(ip, _)| ip.clone()),
+                protocol: protocol.to_string(),
+            });
+        }
+    }
+    ports
+}
+
+/// Docker lists a published port once per address family; keep the first
+fn dedup_ports(mut ports: Vec<ContainerPort>) -> Vec<ContainerPort> {
+    ports.sort_by(|a, b| {
+        (a.container_port, a.host_port, &a.protocol).cmp(&(
+            b.container_port,
+            b.host_port,
+            &b.protocol,
+        ))
+    });
+    ports.dedup_by(|a, b| {
+        a.container_port == b.container_port
+            && a.host_port == b.host_port
+            && a.protocol == b.protocol
+    });
+    ports
+}
+
+/// An entry of the engine API's /containers/json
+fn from_api(v: &Value, runtime: &'static str) -> Container {
+    let ports = v["Ports"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|p| {
+            Some(ContainerPort {
+                container_port: p["PrivatePort"].as_u64()? as u16,
+                host_port: p["PublicPort"].as_u64().map(|p| p as u16),
+                host_ip: p["IP"].as_str().map(String::from),
+                protocol: p["Type"].as_str().unwrap_or("tcp").to_string(),
+            })
+        })
+        .collect();
+    Container {
+        id: short_id(v, "Id"),
+        name: v["Names"][0]
+            .as_str()
+            .unwrap_or_default()
+            .trim_start_matches('/')
+            .to_string(),
+        image: text(v, "Image"),
+        state: text(v, "State"),
+        status: text(v, "Status"),
+        runtime,
+        ports: dedup_ports(ports),
+    }
+}
+
+/// An entry of `podman ps --format json`
+fn from_podman(v: &Value) -> Container {
+    let mut ports = Vec::new();
+    for p in v["Ports"].as_array().into_iter().flatten() {
+        let Some(container_port) = p["container_port"].as_u64() else {
+            continue;
+        };
+        let host_port = p["host_port"].as_u64();
+        for i in 0..p["range"].as_u64().unwrap_or(1).max(1) {
+            ports.push(ContainerPort {
+                container_port: (container_port + i) as u16,
+                host_port: host_port.map(|h| (h + i) as u16),
+                host_ip: p["host_ip"]
+                    .as_str()
+                    .filter(|ip| !ip.is_empty())
+                    .map(String::from),
+                protocol: p["protocol"].as_str().unwrap_or("tcp").to_string(),
+            });
+        }
+    }
+    Container {
+        id: short_id(v, "Id"),
+        name: v["Names"][0].as_str().unwrap_or_default().to_string(),
+        image: text(v, "Image"),
+        state: text(v, "State"),
+        status: text(v, "Status"),
+        runtime: "podman",
+        ports: dedup_ports(ports),
+    }
+}
+
+/// A line of `docker ps --format '{{json .}}'`
+fn from_docker_cli(v: &Value) -> Container {
+    Container {
+        id: short_id(v, "ID"),
+        name: text(v, "Names"),
+        image: text(v, "Image"),
+        state: text(v, "State"),
+        status: text(v, "Status"),
+        runtime: "docker",
+        ports: dedup_ports(parse_port_list(v["Ports"].as_str().unwrap_or_default())),
+    }
+}
+
+/// List running (or with `all`, every) container. Asks the Docker or Podman
+/// engine API first and falls back to `docker ps`/`podman ps` when no socket
+/// is reachable, e.g. without permission on it.
+pub fn list(all: bool) -> Result<Vec<Container>> {
+    #[cfg(unix)]
+    for (socket, engine) in sockets() {
+        let path = if all {
+            "/containers/json?all=1"
+        } else {
+            "/containers/json"
+        };
+        if let Ok(Value::Array(items)) = api_get(&socket, path) {
+            let runtime = cli_for(engine);
+            return Ok(items.iter().map(|c| from_api(c, runtime)).collect());
+        }
+    }
+
+    let Some(cli) = cli() else {
+        bail!("no container runtime found (docker or podman)");
+    };
+    let mut cmd = Command::new(cli);
+    cmd.arg("ps");
+    if all {
+        cmd.arg("--all");
+    }
+    if cli == "podman" {
+        cmd.args(["--format", "json"]);
+    } else {
+        cmd.args(["--no-trunc", "--format", "{{json .}}"]);
+    }
+    let output = cmd.output().with_context(|| format!("running {}", cli))?;
+    if !output.status.success() {
+        bail!(
+            "{} ps failed: {}",
+            cli,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if cli == "podman" {
+        let items: Vec<Value> =
+            serde_json::from_str(stdout.trim()).context("invalid podman ps output")?;
+        Ok(items.iter().map(from_podman).collect())
+    } else {
+        Ok(stdout
+            .lines()
+            .filter_map(|l| serde_json::from_str::<Value>(l).ok())
+            .map(|c| from_docker_cli(&c))
+            .collect())
+    }
+}
@@ -0,0 +1,261 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Text inside the begin/end marker lines of the cwd tracking (OSC 7)
+/// snippet the app adds to shell rc files
+pub const OSC7_MARKER: &str = "jaterm cwd tracking";
+
+const PACKAGE_MANAGERS: &[&str] = &[
+    "apt",
+    "dnf",
+    "yum",
+    "zypper",
+    "pacman",
+    "apk",
+    "emerge",
+    "xbps-install",
+    "nix",
+    "brew",
+    "port",
+    "pkg",
+    "snap",
+    "flatpak",
+];
+
+const TOOLS: &[&str] = &[
+    "git",
+    "docker",
+    "podman",
+    "kubectl",
+    "helm",
+    "python3",
+    "python",
+    "node",
+    "npm",
+    "go",
+    "cargo",
+    "java",
+    "tmux",
+    "screen",
+    "sudo",
+    "systemctl",
+    "journalctl",
+];
+
+#[derive(Debug, Serialize)]
+pub struct Distro {
+    /// os-release ID, e.g. "ubuntu", or "macos"
+    pub id: String,
+    pub name: String,
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShellInfo {
+    pub path: String,
+    /// "bash", "zsh", "fish", ...
+    pub name: String,
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EnvReport {
+    /// "linux", "macos", "freebsd", ...
+    pub os: String,
+    pub distro: Option<Distro>,
+    pub kernel: Option<String>,
+    pub arch: String,
+    pub hostname: Option<String>,
+    pub user: Option<String>,
+    pub home: Option<String>,
+    /// The user's login shell
+    pub shell: Option<ShellInfo>,
+    pub package_managers: Vec<String>,
+    /// Tools found on PATH, by name
+    pub tools: BTreeMap<String, String>,
+    /// Shells whose rc file has the cwd tracking snippet
+    pub osc7_shells: Vec<String>,
+    /// Whether the login shell has it
+    pub osc7_installed: bool,
+}
+
+/// Candidate rc files of a shell, preferred first
+pub fn rc_files(shell: &str) -> Vec<PathBuf> {
+    let Some(home) = dirs::home_dir() else {
+        return Vec::new();
+    };
+    match shell {
+        "zsh" => {
+            let dir = std::env::var("ZDOTDIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| home.clone());
+            vec![dir.join(".zshrc")]
+        }
+        "bash" => vec![home.join(".bashrc"), home.join(".bash_profile")],
+        "fish" => vec![home.join(".config/fish/config.fish")],
+        _ => Vec::new(),
+    }
+}
+
+/// Whether any rc file of the shell has the cwd tracking snippet
+pub fn osc7_installed(shell: &str) -> bool {
+    rc_files(shell).iter().any(|path| {
+        std::fs::read_to_string(path).is_ok_and(|content| content.contains(OSC7_MARKER))
+    })
+}
+
+fn os_release() -> Option<Distro> {
+    let contents = std::fs::read_to_string("/etc/os-release")
+        .or_else(|_| std::fs::read_to_string("/usr/lib/os-release"))
+        .ok()?;
+    let mut fields: BTreeMap<&str, String> = BTreeMap::new();
+    for line in contents.lines() {
+        if let Some((key, value)) = line.split_once('=') {
+            fields.insert(key.trim(), value.trim().trim_matches('"').to_string());
+        }
+    }
+    let id = fields.get("ID").cloned().unwrap_or_else(|| "linux".into());
+    Some(Distro {
+        name: fields
+            .get("PRETTY_NAME")
+            .or_else(|| fields.get("NAME"))
+            .cloned()
+            .unwrap_or_else(|| id.clone()),
+        version: fields.get("VERSION_ID").cloned(),
+        id,
+    })
+}
+
+/// First line of a command's output
+fn first_line(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout.lines().next()?.trim();
+    (!line.is_empty()).then(|| line.to_string())
+}
+
+fn distro() -> Option<Distro> {
+    if cfg!(target_os = "macos") {
+        return Some(Distro {
+            id: "macos".into(),
+            name: first_line("sw_vers", &["-productName"]).unwrap_or_else(|| "macOS".into()),
+            version: first_line("sw_vers", &["-productVersion"]),
+        });
+    }
+    os_release()
+}
+
+/// Host name, kernel release and machine from uname(2)
+#[cfg(unix)]
+fn uname() -> (Option<String>, Option<String>, Option<String>) {
+    let mut buf: libc::utsname = unsafe { std::mem::zeroed() };
+    if unsafe { libc::uname(&mut buf) } != 0 {
+        return (None, None, None);
+    }
+    let field = |f: &[libc::c_char]| {
+        let bytes: Vec<u8> = f
+            .iter()
+            .take_while(|c| **c != 0)
+            .map(|c| *c as u8)
+            .collect();
+        Some(String::from_utf8_lossy(&bytes).into_owned())
+    };
+    (
+        field(&buf.nodename),
+        field(&buf.release),
+        field(&buf.machine),
+    )
+}
+
+#[cfg(not(unix))]
+fn uname() -> (Option<String>, Option<String>, Option<String>) {
+    (None, None, None)
+}
+
+/// User name and login shell from the passwd entry of this process
+#[cfg(unix)]
+fn passwd() -> (Option<String>, Option<String>) {
+    let text = |p: *const libc::c_char| {
+        (!p.is_null()).then(|| {
+            unsafe { std::ffi::CStr::from_ptr(p) }
+                .to_string_lossy()
+                .into_owned()
+        })
+    };
+    let pw = unsafe { libc::getpwuid(libc::getuid()) };
+    if pw.is_null() {
+        return (None, None);
+    }
+    unsafe { (text((*pw).pw_name), text((*pw).pw_shell)) }
+}
+
+#[cfg(not(unix))]
+fn passwd() -> (Option<String>, Option<String>) {
+    (None, None)
+}
+
+fn non_empty_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+/// The version number in `<shell> --version`, e.g. "5.2.15" from
+/// "GNU bash, version 5.2.15(1)-release"
+fn shell_version(path: &str) -> Option<String> {
+    let line = first_line(path, &["--version"])?;
+    let re = regex::Regex::new(r"\d+(\.\d+)+").expect("valid version pattern");
+    re.find(&line).map(|m| m.as_str().to_string())
+}
+
+/// Describe this host for the app: OS, distro, kernel, login shell, package
+/// managers and tools on PATH, and where cwd tracking is installed
+pub fn report() -> EnvReport {
+    let (hostname, kernel, machine) = uname();
+    let (pw_name, pw_shell) = passwd();
+    let shell = non_empty_var("SHELL").or(pw_shell).map(|path| {
+        let name = path.rsplit('/').next().unwrap_or(&path).to_string();
+        ShellInfo {
+            version: shell_version(&path),
+            name,
+            path,
+        }
+    });
+    let osc7_shells: Vec<String> = ["bash", "zsh", "fish"]
+        .into_iter()
+        .filter(|s| osc7_installed(s))
+        .map(String::from)
+        .collect();
+    let osc7_installed = shell
+        .as_ref()
+        .is_some_and(|s| osc7_shells.contains(&s.name));
+    EnvReport {
+        os: std::env::consts::OS.to_string(),
+        distro: distro(),
+        kernel,
+        arch: machine.unwrap_or_else(|| std::env::consts::ARCH.to_string()),
+        hostname,
+        user: non_empty_var("USER")
+            .or_else(|| non_empty_var("LOGNAME"))
+            .or(pw_name),
+        home: dirs::home_dir().map(|h| h.to_string_lossy().into_owned()),
+        shell,
+        package_managers: PACKAGE_MANAGERS
+            .iter()
+            .filter(|pm| which::which(pm).is_ok())
+            .map(|pm| pm.to_string())
+            .collect(),
+        tools: TOOLS
+            .iter()
+            .filter_map(|tool| {
+                let path = which::which(tool).ok()?;
+                Some((tool.to_string(), path.to_string_lossy().into_owned()))
+            })
+            .collect(),
+        osc7_shells,
+        osc7_installed,
+    }
+}
@@ -1,9 +1,20 @@
-use anyhow::{Context, Result};
-use git2::{Repository, StatusOptions};
+use anyhow::{bail, Context, Result};
+use git2::build::CheckoutBuilder;
+use git2::{
+    ApplyLocation, Branch, BranchType, Delta, Diff, DiffFindOptions, DiffFlags, DiffOptions,
+    ErrorCode, Oid, Patch, Repository, Signature, Sort, StashApplyOptions, StashFlags,
+    StatusOptions,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
 use std::process::Command;
 
+// Commits per page when the caller does not say
+const DEFAULT_LOG_LIMIT: usize = 200;
+// Lines of context around each hunk, as `git diff`
+const DEFAULT_CONTEXT: u32 = 3;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GitStatus {
     pub branch: String,
@@ -11,6 +22,15 @@ pub struct GitStatus {
     pub behind: usize,
     pub staged: usize,
     pub unstaged: usize,
+    /// Operation in progress: "merging", "rebasing", "cherry-picking",
+    /// "reverting", "bisecting" or "applying" (git am)
+    pub operation: Option<String>,
+    /// Rebase (or am) progress: the step being applied, of `total`
+    pub step: Option<u32>,
+    pub total: Option<u32>,
+    /// Files with unresolved conflicts; not counted as staged or unstaged
+    pub conflicted: usize,
+    pub conflicted_files: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -22,7 +42,7 @@ pub struct GitChange {
 }
 
 /// Expand tilde in path
-fn expand_tilde(path: &str) -> String {
+pub(crate) fn expand_tilde(path: &str) -> String {
     if path.starts_with("~") {
         if let Some(home) = dirs::home_dir() {
             return path.replacen("~", &home.to_string_lossy(), 1);
@@ -46,6 +66,11 @@ pub fn status(dir: &str) -> Result<GitStatus> {
                 behind: 0,
                 staged: 0,
                 unstaged: 0,
+                operation: None,
+                step: None,
+                total: None,
+                conflicted: 0,
+                conflicted_files: Vec::new(),
             });
         }
     };
@@ -82,12 +107,15 @@ pub fn status(dir: &str) -> Result<GitStatus> {
     
     let mut staged = 0;
     let mut unstaged = 0;
+    let mut conflicted_files = Vec::new();
     
     for entry in statuses.iter() {
         let status = entry.status();
         
-        // Check if file is untracked (counts as unstaged)
-        if status.contains(git2::Status::WT_NEW) {
+        if status.contains(git2::Status::CONFLICTED) {
+            conflicted_files.push(entry.path().unwrap_or("").to_string());
+        } else if status.contains(git2::Status::WT_NEW) {
+            // Untracked files count as unstaged
             unstaged += 1;
         } else {
             // Check if staged
@@ -113,15 +141,63 @@ pub fn status(dir: &str) -> Result<GitStatus> {
         }
     }
     
+    let (operation, step, total) = operation(&repo);
     Ok(GitStatus {
         branch,
         ahead,
         behind,
         staged,
         unstaged,
+        operation: operation.map(String::from),
+        step,
+        total,
+        conflicted: conflicted_files.len(),
+        conflicted_files,
     })
 }
 
+/// The operation a repository is in the middle of, with its progress for
+/// rebases and `git am`
+pub fn operation(repo: &Repository) -> (Option<&'static str>, Option<u32>, Option<u32>) {
+    use git2::RepositoryState as State;
+    let kind = match repo.state() {
+        State::Clean => None,
+        State::Merge => Some("merging"),
+        State::Revert | State::RevertSequence => Some("reverting"),
+        State::CherryPick | State::CherryPickSequence => Some("cherry-picking"),
+        State::Bisect => Some("bisecting"),
+        State::Rebase | State::RebaseInteractive | State::RebaseMerge => Some("rebasing"),
+        State::ApplyMailbox | State::ApplyMailboxOrRebase => Some("applying"),
+    };
+    let number = |dir: &str, file: &str| -> Option<u32> {
+        let text = std::fs::read_to_string(repo.path().join(dir).join(file)).ok()?;
+        text.trim().parse().ok()
+    };
+    let (step, total) = match kind {
+        Some("rebasing") | Some("applying") => match number("rebase-merge", "msgnum") {
+            Some(step) => (Some(step), number("rebase-merge", "end")),
+            None => (number("rebase-apply", "next"), number("rebase-apply", "last")),
+        },
+        _ => (None, None),
+    };
+    (kind, step, total)
+}
+
+/// What a failed pull left behind, so callers hear about a half-done rebase
+/// or merge instead of only git's stderr
+fn left_in_progress(dir: &str) -> Option<String> {
+    let st = status(dir).ok()?;
+    let operation = st.operation?;
+    let progress = match (st.step, st.total) {
+        (Some(step), Some(total)) => format!(" (step {}/{})", step, total),
+        _ => String::new(),
+    };
+    Some(format!(
+        "the repository is {}{} with {} conflicted file(s); resolve them and continue, or abort",
+        operation, progress, st.conflicted
+    ))
+}
+
 pub fn changes(dir: &str) -> Result<Vec<GitChange>> {
     let dir = expand_tilde(dir);
     let path = Path::new(&dir);
@@ -244,6 +320,9 @@ pub fn sync(dir: &str) -> Result<String> {
     
     if !pull_output.status.success() {
         let stderr = String::from_utf8_lossy(&pull_output.stderr);
+        if let Some(note) = left_in_progress(&dir) {
+            bail!("{}\n{}", note, stderr.trim());
+        }
         return Ok(stderr.to_string());
     }
     
@@ -341,6 +420,11 @@ pub fn pull(dir: &str) -> Result<String> {
     
     let stdout = String::from_utf8_lossy(&output.stdout);
     let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        if let Some(note) = left_in_progress(&dir) {
+            bail!("{}\n{}{}", note, stdout, stderr.trim());
+        }
+    }
     Ok(format!("{}{}", stdout, stderr))
 }
 
@@ -354,4 +438,1235 @@ pub fn push(dir: &str) -> Result<String> {
     let stdout = String::from_utf8_lossy(&output.stdout);
     let stderr = String::from_utf8_lossy(&output.stderr);
     Ok(format!("{}{}", stdout, stderr))
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Default)]
+pub struct GitLogOptions {
+    /// Commits to skip, for paging
+    pub skip: usize,
+    /// Page size (200 when 0)
+    pub limit: usize,
+    /// Revision to start from (HEAD when omitted)
+    pub rev: Option<String>,
+    /// Start from every branch, remote branch and tag instead
+    pub all: bool,
+    /// Only commits touching this path, relative to the repository root
+    pub path: Option<String>,
+    /// Only commits whose author name or email contains this
+    pub author: Option<String>,
+    /// Only commits whose message contains this
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GitRef {
+    /// Short name, e.g. "main", "origin/main", "v1.0" or "HEAD"
+    pub name: String,
+    /// "branch", "remote", "tag" or "head" (a detached HEAD)
+    pub kind: String,
+    /// The branch HEAD points at
+    pub is_head: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct GraphEdge {
+    /// Column in this row
+    pub from: usize,
+    /// Column in the next row
+    pub to: usize,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct GraphRow {
+    /// Column of the commit's node
+    pub column: usize,
+    /// Lines from this row down to the next one, passing lanes included
+    pub edges: Vec<GraphEdge>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitCommit {
+    pub id: String,
+    pub short_id: String,
+    pub parents: Vec<String>,
+    pub author_name: String,
+    pub author_email: String,
+    /// Seconds since the epoch
+    pub author_time: i64,
+    pub committer_name: String,
+    pub committer_time: i64,
+    /// First line of the message
+    pub summary: String,
+    pub message: String,
+    /// Branches, tags and HEAD pointing at this commit
+    pub refs: Vec<GitRef>,
+    /// Lane layout; only for unfiltered logs, where every parent is listed
+    pub graph: Option<GraphRow>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitLogPage {
+    pub commits: Vec<GitCommit>,
+    /// More commits follow; ask again with `skip` moved past this page
+    pub has_more: bool,
+    /// Commit HEAD points at
+    pub head: Option<String>,
+}
+
+/// Assigns commits to lanes, one row at a time in log order
+#[derive(Default)]
+struct GraphLayout {
+    /// Commit each lane is waiting for
+    lanes: Vec<Option<String>>,
+}
+
+impl GraphLayout {
+    fn free_lane(&mut self, skip: usize) -> usize {
+        match (0..self.lanes.len()).find(|&i| i != skip && self.lanes[i].is_none()) {
+            Some(i) => i,
+            None => {
+                self.lanes.push(None);
+                self.lanes.len() - 1
+            }
+        }
+    }
+
+    fn row(&mut self, id: &str, parents: &[String]) -> GraphRow {
+        let waiting = |l: &Option<String>| l.as_deref() == Some(id);
+        let column = match self.lanes.iter().position(waiting) {
+            Some(i) => i,
+            None => self.free_lane(usize::MAX),
+        };
+        // Lanes of children that meet at this commit end here
+        for lane in self.lanes.iter_mut().filter(|l| waiting(l)) {
+            *lane = None;
+        }
+        let mut edges: Vec<GraphEdge> = (0..self.lanes.len())
+            .filter(|&i| self.lanes[i].is_some())
+            .map(|i| GraphEdge { from: i, to: i })
+            .collect();
+        for (n, parent) in parents.iter().enumerate() {
+            let to = match self.lanes.iter().position(|l| l.as_ref() == Some(parent)) {
+                Some(i) => i,
+                None => {
+                    let i = if n == 0 {
+                        column
+                    } else {
+                        self.free_lane(column)
+                    };
+                    self.lanes[i] = Some(parent.clone());
+                    i
+                }
+            };
+            edges.push(GraphEdge { from: column, to });
+        }
+        while self.lanes.last().is_some_and(|l| l.is_none()) {
+            self.lanes.pop();
+        }
+        GraphRow { column, edges }
+    }
+}
+
+/// Refs by the commit they point at
+fn ref_map(repo: &Repository) -> HashMap<Oid, Vec<GitRef>> {
+    let mut map: HashMap<Oid, Vec<GitRef>> = HashMap::new();
+    let head = repo.head().ok();
+    let head_name = head
+        .as_ref()
+        .filter(|h| h.is_branch())
+        .and_then(|h| h.name().map(String::from));
+    if let Some(h) = head.as_ref().filter(|h| !h.is_branch()) {
+        if let Some(oid) = h.target() {
+            map.entry(oid).or_default().push(GitRef {
+                name: "HEAD".into(),
+                kind: "head".into(),
+                is_head: true,
+            });
+        }
+    }
+    if let Ok(refs) = repo.references() {
+        for r in refs.flatten() {
+            let Some(full) = r.name() else { continue };
+            let kind = if full.starts_with("refs/heads/") {
+                "branch"
+            } else if full.starts_with("refs/remotes/") {
+                "remote"
+            } else if full.starts_with("refs/tags/") {
+                "tag"
+            } else {
+                continue;
+            };
+            // Skip symbolic refs such as origin/HEAD
+            if r.symbolic_target().is_some() {
+                continue;
+            }
+            let Ok(commit) = r.peel_to_commit() else {
+                continue;
+            };
+            map.entry(commit.id()).or_default().push(GitRef {
+                name: r.shorthand().unwrap_or(full).to_string(),
+                kind: kind.into(),
+                is_head: head_name.as_deref() == Some(full),
+            });
+        }
+    }
+    map
+}
+
+/// Whether a commit changes `path` compared with every parent, like
+/// `git log -- <path>` without history simplification
+fn touches(repo: &Repository, commit: &git2::Commit, path: &str) -> bool {
+    let Ok(tree) = commit.tree() else {
+        return false;
+    };
+    let changed = |parent: Option<git2::Tree>| {
+        let mut opts = DiffOptions::new();
+        opts.pathspec(path);
+        repo.diff_tree_to_tree(parent.as_ref(), Some(&tree), Some(&mut opts))
+            .is_ok_and(|d| d.deltas().len() > 0)
+    };
+    if commit.parent_count() == 0 {
+        return changed(None);
+    }
+    commit
+        .parents()
+        .all(|p| p.tree().is_ok_and(|t| changed(Some(t))))
+}
+
+fn contains_ci(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+/// One page of history, newest first (see `GitLogOptions`)
+pub fn log(dir: &str, opts: &GitLogOptions) -> Result<GitLogPage> {
+    let dir = expand_tilde(dir);
+    let repo = Repository::discover(&dir).context("not a git repository")?;
+    let head = repo.head().ok().and_then(|h| h.target());
+    let mut walk = repo.revwalk()?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+    if opts.all {
+        for glob in ["refs/heads/*", "refs/remotes/*", "refs/tags/*"] {
+            walk.push_glob(glob)?;
+        }
+        if head.is_some() {
+            walk.push_head()?;
+        }
+    } else if let Some(rev) = opts.rev.as_deref().filter(|r| !r.is_empty()) {
+        let obj = repo
+            .revparse_single(rev)
+            .with_context(|| format!("unknown revision '{}'", rev))?;
+        let commit = obj.peel_to_commit()?;
+        walk.push(commit.id())?;
+    } else if head.is_some() {
+        walk.push_head()?;
+    } else {
+        // No commits yet
+        return Ok(GitLogPage {
+            commits: Vec::new(),
+            has_more: false,
+            head: None,
+        });
+    }
+
+    let filtered = opts.path.is_some() || opts.author.is_some() || opts.message.is_some();
+    let limit = if opts.limit == 0 {
+        DEFAULT_LOG_LIMIT
+    } else {
+        opts.limit
+    };
+    let refs = ref_map(&repo);
+    let mut layout = GraphLayout::default();
+    let mut commits = Vec::new();
+    let mut seen = 0usize;
+    let mut has_more = false;
+    for oid in walk {
+        let oid = oid?;
+        let commit = repo.find_commit(oid)?;
+        let author = commit.author();
+        let message = commit.message().unwrap_or_default();
+        if let Some(a) = &opts.author {
+            let who = format!(
+                "{} <{}>",
+                author.name().unwrap_or_default(),
+                author.email().unwrap_or_default()
+            );
+            if !contains_ci(&who, a) {
+                continue;
+            }
+        }
+        if opts
+            .message
+            .as_ref()
+            .is_some_and(|m| !contains_ci(message, m))
+        {
+            continue;
+        }
+        if opts
+            .path
+            .as_ref()
+            .is_some_and(|p| !touches(&repo, &commit, p))
+        {
+            continue;
+        }
+        let parents: Vec<String> = commit.parent_ids().map(|p| p.to_string()).collect();
+        let id = oid.to_string();
+        // Earlier pages still shape the lanes of this one
+        let graph = (!filtered).then(|| layout.row(&id, &parents));
+        seen += 1;
+        if seen <= opts.skip {
+            continue;
+        }
+        if commits.len() == limit {
+            has_more = true;
+            break;
+        }
+        let committer = commit.committer();
+        commits.push(GitCommit {
+            short_id: id.chars().take(7).collect(),
+            id,
+            parents,
+            author_name: author.name().unwrap_or_default().to_string(),
+            author_email: author.email().unwrap_or_default().to_string(),
+            author_time: author.when().seconds(),
+            committer_name: committer.name().unwrap_or_default().to_string(),
+            committer_time: committer.when().seconds(),
+            summary: commit.summary().unwrap_or_default().to_string(),
+            message: message.to_string(),
+            refs: refs.get(&oid).cloned().unwrap_or_default(),
+            graph,
+        });
+    }
+    Ok(GitLogPage {
+        commits,
+        has_more,
+        head: head.map(|h| h.to_string()),
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BranchCommit {
+    pub id: String,
+    pub short_id: String,
+    pub summary: String,
+    pub author_name: String,
+    /// Seconds since the epoch
+    pub time: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitBranch {
+    /// "main", or "origin/main" for remote branches
+    pub name: String,
+    /// "local" or "remote"
+    pub kind: String,
+    pub is_head: bool,
+    /// Upstream of a local branch, e.g. "origin/main"
+    pub upstream: Option<String>,
+    /// The upstream is configured but its remote branch is gone
+    pub upstream_gone: bool,
+    pub ahead: u32,
+    pub behind: u32,
+    pub last_commit: Option<BranchCommit>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitCheckoutResult {
+    /// Local branch checked out (or that would have been)
+    pub branch: String,
+    /// False when local changes are in the way; nothing was touched then
+    pub checked_out: bool,
+    /// Files whose local changes the checkout would overwrite
+    pub conflicts: Vec<String>,
+}
+
+fn open_repo(dir: &str) -> Result<Repository> {
+    Repository::discover(expand_tilde(dir)).context("not a git repository")
+}
+
+fn branch_info(repo: &Repository, branch: &Branch, kind: BranchType) -> Option<GitBranch> {
+    let reference = branch.get();
+    // origin/HEAD and the like
+    if reference.symbolic_target().is_some() {
+        return None;
+    }
+    let name = branch.name().ok()??.to_string();
+    let tip = reference.target();
+    let (mut upstream, mut upstream_gone, mut ahead, mut behind) = (None, false, 0, 0);
+    if kind == BranchType::Local {
+        match branch.upstream() {
+            Ok(up) => {
+                upstream = up.name().ok().flatten().map(String::from);
+                if let (Some(lo), Some(up)) = (tip, up.get().target()) {
+                    if let Ok((a, b)) = repo.graph_ahead_behind(lo, up) {
+                        ahead = a as u32;
+                        behind = b as u32;
+                    }
+                }
+            }
+            Err(_) => {
+                upstream_gone = reference
+                    .name()
+                    .is_some_and(|r| repo.branch_upstream_name(r).is_ok());
+            }
+        }
+    }
+    let last_commit = reference.peel_to_commit().ok().map(|c| {
+        let id = c.id().to_string();
+        BranchCommit {
+            short_id: id.chars().take(7).collect(),
+            id,
+            summary: c.summary().unwrap_or_default().to_string(),
+            author_name: c.author().name().unwrap_or_default().to_string(),
+            time: c.time().seconds(),
+        }
+    });
+    Some(GitBranch {
+        name,
+        kind: if kind == BranchType::Local {
+            "local".into()
+        } else {
+            "remote".into()
+        },
+        is_head: branch.is_head(),
+        upstream,
+        upstream_gone,
+        ahead,
+        behind,
+        last_commit,
+    })
+}
+
+/// Local branches, then remote ones with `remotes`, each sorted by name
+pub fn branches(dir: &str, remotes: bool) -> Result<Vec<GitBranch>> {
+    let repo = open_repo(dir)?;
+    let mut list = Vec::new();
+    let kinds: &[BranchType] = if remotes {
+        &[BranchType::Local, BranchType::Remote]
+    } else {
+        &[BranchType::Local]
+    };
+    for &kind in kinds {
+        let mut found: Vec<GitBranch> = repo
+            .branches(Some(kind))?
+            .flatten()
+            .filter_map(|(b, _)| branch_info(&repo, &b, kind))
+            .collect();
+        found.sort_by(|a, b| a.name.cmp(&b.name));
+        list.extend(found);
+    }
+    Ok(list)
+}
+
+pub fn branch_create(dir: &str, name: &str, start: Option<&str>) -> Result<()> {
+    let repo = open_repo(dir)?;
+    let commit = match start.filter(|s| !s.is_empty()) {
+        Some(rev) => repo.revparse_single(rev).and_then(|o| o.peel_to_commit()),
+        None => repo.head().and_then(|h| h.peel_to_commit()),
+    }?;
+    let mut branch = repo.branch(name, &commit, false)?;
+    // Starting from a remote branch tracks it, like `git branch <name> origin/<name>`
+    if let Some(start) = start {
+        if repo.find_branch(start, BranchType::Remote).is_ok() {
+            branch.set_upstream(Some(start))?;
+        }
+    }
+    Ok(())
+}
+
+/// Switch to a local branch. A remote branch name ("origin/x"), or a name
+/// only one remote has, gets a local branch tracking it first. Refuses to
+/// overwrite local changes and reports the files in the way instead.
+pub fn checkout(dir: &str, name: &str) -> Result<GitCheckoutResult> {
+    let repo = open_repo(dir)?;
+    let (local, remote) = if repo.find_branch(name, BranchType::Local).is_ok() {
+        (name.to_string(), None)
+    } else if repo.find_branch(name, BranchType::Remote).is_ok() {
+        let (_, short) = name.split_once('/').context("invalid remote branch name")?;
+        (short.to_string(), Some(name.to_string()))
+    } else {
+        let suffix = format!("/{}", name);
+        let candidates: Vec<String> = repo
+            .branches(Some(BranchType::Remote))?
+            .flatten()
+            .filter_map(|(b, _)| b.name().ok().flatten().map(String::from))
+            .filter(|n| n.ends_with(&suffix))
+            .collect();
+        match candidates.as_slice() {
+            [only] => (name.to_string(), Some(only.clone())),
+            [] => bail!("branch '{}' not found", name),
+            _ => bail!("'{}' matches several remote branches", name),
+        }
+    };
+    if remote.is_some() && repo.find_branch(&local, BranchType::Local).is_ok() {
+        bail!("a local branch '{}' already exists", local);
+    }
+    let source = remote.as_deref().unwrap_or(&local);
+    let kind = if remote.is_some() {
+        BranchType::Remote
+    } else {
+        BranchType::Local
+    };
+    let commit = repo
+        .find_branch(source, kind)
+        .and_then(|b| b.get().peel_to_commit())?;
+
+    let mut conflicts = Vec::new();
+    let outcome = {
+        let mut opts = CheckoutBuilder::new();
+        opts.safe()
+            .notify_on(git2::CheckoutNotificationType::CONFLICT)
+            .notify(|_, path, _, _, _| {
+                if let Some(p) = path {
+                    conflicts.push(p.to_string_lossy().into_owned());
+                }
+                true
+            });
+        repo.checkout_tree(commit.as_object(), Some(&mut opts))
+    };
+    match outcome {
+        Err(e) if e.code() == ErrorCode::Conflict => {
+            return Ok(GitCheckoutResult {
+                branch: local,
+                checked_out: false,
+                conflicts,
+            })
+        }
+        other => other?,
+    }
+    if let Some(remote) = &remote {
+        let mut branch = repo.branch(&local, &commit, false)?;
+        branch.set_upstream(Some(remote))?;
+    }
+    repo.set_head(&format!("refs/heads/{}", local))?;
+    Ok(GitCheckoutResult {
+        branch: local,
+        checked_out: true,
+        conflicts: Vec::new(),
+    })
+}
+
+/// Delete a local branch; without `force` only when HEAD contains it
+pub fn branch_delete(dir: &str, name: &str, force: bool) -> Result<()> {
+    let repo = open_repo(dir)?;
+    let mut branch = repo.find_branch(name, BranchType::Local)?;
+    if branch.is_head() {
+        bail!("cannot delete the checked out branch '{}'", name);
+    }
+    if !force {
+        let tip = branch.get().target();
+        let head = repo.head().ok().and_then(|h| h.target());
+        let merged = match (tip, head) {
+            (Some(t), Some(h)) => t == h || repo.graph_descendant_of(h, t).unwrap_or(false),
+            _ => false,
+        };
+        if !merged {
+            bail!("branch '{}' is not fully merged", name);
+        }
+    }
+    Ok(branch.delete()?)
+}
+
+pub fn branch_rename(dir: &str, name: &str, new_name: &str, force: bool) -> Result<()> {
+    let repo = open_repo(dir)?;
+    let mut branch = repo.find_branch(name, BranchType::Local)?;
+    branch.rename(new_name, force)?;
+    Ok(())
+}
+
+/// Track `upstream` (e.g. "origin/main"), or stop tracking when None
+pub fn branch_set_upstream(dir: &str, name: &str, upstream: Option<&str>) -> Result<()> {
+    let repo = open_repo(dir)?;
+    let mut branch = repo.find_branch(name, BranchType::Local)?;
+    branch.set_upstream(upstream.filter(|u| !u.is_empty()))?;
+    Ok(())
+}
+
+#[derive(Debug, Default)]
+pub struct GitDiffOptions {
+    /// HEAD against the index instead of the index against the working tree
+    pub staged: bool,
+    /// Only these paths, relative to the repository root
+    pub paths: Vec<String>,
+    /// Lines of context around hunks (3 when omitted)
+    pub context: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitDiffLine {
+    /// "+", "-" or " "
+    pub origin: String,
+    pub old_lineno: Option<u32>,
+    pub new_lineno: Option<u32>,
+    /// Without the line break
+    pub content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitDiffHunk {
+    /// The "@@ -a,b +c,d @@ ..." line
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<GitDiffLine>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitDiffFile {
+    /// None for added and untracked files
+    pub old_path: Option<String>,
+    /// None for deleted files
+    pub new_path: Option<String>,
+    /// "added", "deleted", "modified", "renamed", "copied", "typechange",
+    /// "untracked" or "conflicted"
+    pub status: String,
+    /// Binary files have no hunks
+    pub binary: bool,
+    pub additions: usize,
+    pub deletions: usize,
+    pub hunks: Vec<GitDiffHunk>,
+}
+
+fn make_diff<'r>(
+    repo: &'r Repository,
+    staged: bool,
+    paths: &[String],
+    context: u32,
+) -> Result<Diff<'r>, git2::Error> {
+    let mut opts = DiffOptions::new();
+    opts.context_lines(context).disable_pathspec_match(true);
+    for path in paths {
+        opts.pathspec(path);
+    }
+    if staged {
+        let head = repo.head().ok().and_then(|h| h.peel_to_tree().ok());
+        repo.diff_tree_to_index(head.as_ref(), None, Some(&mut opts))
+    } else {
+        opts.include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .show_untracked_content(true);
+        repo.diff_index_to_workdir(None, Some(&mut opts))
+    }
+}
+
+fn delta_status(status: Delta) -> &'static str {
+    match status {
+        Delta::Added => "added",
+        Delta::Deleted => "deleted",
+        Delta::Renamed => "renamed",
+        Delta::Copied => "copied",
+        Delta::Typechange => "typechange",
+        Delta::Untracked => "untracked",
+        Delta::Conflicted => "conflicted",
+        _ => "modified",
+    }
+}
+
+// "\ No newline at end of file" markers; the line before lacks its break
+fn is_eof_marker(origin: char) -> bool {
+    matches!(origin, '=' | '>' | '<')
+}
+
+/// Changes as files, hunks and lines, with renames detected
+pub fn diff_files(dir: &str, opts: &GitDiffOptions) -> Result<Vec<GitDiffFile>> {
+    let repo = open_repo(dir)?;
+    let context = opts.context.unwrap_or(DEFAULT_CONTEXT);
+    let mut diff = make_diff(&repo, opts.staged, &opts.paths, context)?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+    diff_to_files(&diff)
+}
+
+/// Files, hunks and lines of a diff
+fn diff_to_files(diff: &Diff) -> Result<Vec<GitDiffFile>> {
+    let mut files = Vec::new();
+    for (idx, delta) in diff.deltas().enumerate() {
+        let path = |f: git2::DiffFile| f.path().map(|p| p.to_string_lossy().into_owned());
+        let status = delta.status();
+        let mut file = GitDiffFile {
+            old_path: (!matches!(status, Delta::Added | Delta::Untracked))
+                .then(|| path(delta.old_file()))
+                .flatten(),
+            new_path: (status != Delta::Deleted)
+                .then(|| path(delta.new_file()))
+                .flatten(),
+            status: delta_status(status).into(),
+            binary: delta.flags().contains(DiffFlags::BINARY),
+            additions: 0,
+            deletions: 0,
+            hunks: Vec::new(),
+        };
+        let patch = Patch::from_diff(diff, idx)?;
+        if let Some(patch) = patch {
+            file.binary |= patch.delta().flags().contains(DiffFlags::BINARY);
+            if let Ok((_, additions, deletions)) = patch.line_stats() {
+                file.additions = additions;
+                file.deletions = deletions;
+            }
+            for h in 0..patch.num_hunks() {
+                let (hunk, count) = patch.hunk(h)?;
+                let mut lines = Vec::with_capacity(count);
+                for l in 0..count {
+                    let line = patch.line_in_hunk(h, l)?;
+                    if is_eof_marker(line.origin()) {
+                        continue;
+                    }
+                    let content = String::from_utf8_lossy(line.content());
+                    lines.push(GitDiffLine {
+                        origin: line.origin().to_string(),
+                        old_lineno: line.old_lineno(),
+                        new_lineno: line.new_lineno(),
+                        content: content.trim_end_matches(['\n', '\r']).to_string(),
+                    });
+                }
+                file.hunks.push(GitDiffHunk {
+                    header: String::from_utf8_lossy(hunk.header())
+                        .trim_end()
+                        .to_string(),
+                    old_start: hunk.old_start(),
+                    old_lines: hunk.old_lines(),
+                    new_start: hunk.new_start(),
+                    new_lines: hunk.new_lines(),
+                    lines,
+                });
+            }
+        }
+        files.push(file);
+    }
+    Ok(files)
+}
+
+/// What to do with the selected lines of a hunk
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineAction {
+    /// Working tree change into the index, like `git add -p`
+    Stage,
+    /// Index change back out, like `git reset -p`
+    Unstage,
+    /// Working tree change dropped, like `git checkout -p`
+    Discard,
+}
+
+impl LineAction {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s {
+            "stage" => Ok(Self::Stage),
+            "unstage" => Ok(Self::Unstage),
+            "discard" => Ok(Self::Discard),
+            _ => bail!("unknown action '{}' (stage, unstage or discard)", s),
+        }
+    }
+}
+
+/// A one-hunk patch with only the selected changes of `lines`, which hold
+/// (origin, content) pairs without EOF markers. Forward patches apply the
+/// selection on top of the old side; reverse ones take it back out of the
+/// new side. Unselected changes stay as they are on the side patched.
+fn selection_patch(
+    path: &str,
+    (old_start, new_start): (u32, u32),
+    lines: &[(char, Vec<u8>)],
+    selected: Option<&[usize]>,
+    reverse: bool,
+    (src_absent, dst_absent, mode): (bool, bool, u32),
+) -> Option<Vec<u8>> {
+    let picked = |i: usize| selected.is_none_or(|s| s.contains(&i));
+    let mut body: Vec<(char, &[u8])> = Vec::new();
+    let mut changes = 0;
+    let mut complete = true;
+    for (i, (origin, content)) in lines.iter().enumerate() {
+        let origin = match (origin, reverse) {
+            (' ', _) => ' ',
+            ('+', false) | ('-', true) if picked(i) => '+',
+            ('-', false) | ('+', true) if picked(i) => '-',
+            // Unselected lines that exist on the patched side become context
+            ('-', false) | ('+', true) => ' ',
+            _ => {
+                complete = false;
+                continue;
+            }
+        };
+        if origin != ' ' {
+            changes += 1;
+        } else if lines[i].0 != ' ' {
+            complete = false;
+        }
+        body.push((origin, content));
+    }
+    if changes == 0 {
+        return None;
+    }
+    let old_count = body.iter().filter(|(o, _)| *o != '+').count() as u32;
+    let new_count = body.iter().filter(|(o, _)| *o != '-').count() as u32;
+    let start = if reverse { new_start } else { old_start };
+    let new_at = (start + u32::from(old_count == 0)).saturating_sub(u32::from(new_count == 0));
+
+    let mut patch = format!("diff --git a/{0} b/{0}\n", path);
+    if src_absent {
+        patch.push_str(&format!("new file mode {:o}\n--- /dev/null\n", mode));
+    } else if dst_absent && complete {
+        patch.push_str(&format!("deleted file mode {:o}\n--- a/{}\n", mode, path));
+    } else {
+        patch.push_str(&format!("--- a/{}\n", path));
+    }
+    if dst_absent && complete {
+        patch.push_str("+++ /dev/null\n");
+    } else {
+        patch.push_str(&format!("+++ b/{}\n", path));
+    }
+    patch.push_str(&format!(
+        "@@ -{},{} +{},{} @@\n",
+        start, old_count, new_at, new_count
+    ));
+    let mut bytes = patch.into_bytes();
+    for (origin, content) in body {
+        bytes.push(origin as u8);
+        bytes.extend_from_slice(content);
+        if !content.ends_with(b"\n") {
+            bytes.extend_from_slice(b"\n\\ No newline at end of file\n");
+        }
+    }
+    Some(bytes)
+}
+
+/// Stage, unstage or discard one hunk of `file`, or only the given lines of
+/// it (indexes into the hunk's `lines` from `diff` with the same `context`)
+pub fn apply_lines(
+    dir: &str,
+    action: LineAction,
+    file: &str,
+    hunk: usize,
+    lines: Option<&[usize]>,
+    context: Option<u32>,
+) -> Result<()> {
+    let repo = open_repo(dir)?;
+    let staged = action == LineAction::Unstage;
+    let diff = make_diff(
+        &repo,
+        staged,
+        &[file.to_string()],
+        context.unwrap_or(DEFAULT_CONTEXT),
+    )?;
+    let patch = Patch::from_diff(&diff, 0)?.with_context(|| format!("no changes in {}", file))?;
+    let delta = patch.delta();
+    if delta.flags().contains(DiffFlags::BINARY) {
+        bail!("{} is binary; use the whole file", file);
+    }
+    if hunk >= patch.num_hunks() {
+        bail!("the diff changed; refresh and try again");
+    }
+    let (header, count) = patch.hunk(hunk)?;
+    let starts = (header.old_start(), header.new_start());
+    let mut raw = Vec::with_capacity(count);
+    for l in 0..count {
+        let line = patch.line_in_hunk(hunk, l)?;
+        if !is_eof_marker(line.origin()) {
+            raw.push((line.origin(), line.content().to_vec()));
+        }
+    }
+    if lines.is_some_and(|ls| ls.iter().any(|&i| i >= raw.len())) {
+        bail!("the diff changed; refresh and try again");
+    }
+
+    let added = matches!(delta.status(), Delta::Added | Delta::Untracked);
+    let deleted = delta.status() == Delta::Deleted;
+    let reverse = action != LineAction::Stage;
+    let mode = u32::from(if deleted {
+        delta.old_file().mode()
+    } else {
+        delta.new_file().mode()
+    });
+    let sides = if reverse {
+        (deleted, added, mode)
+    } else {
+        (added, deleted, mode)
+    };
+    let bytes = selection_patch(file, starts, &raw, lines, reverse, sides)
+        .context("no changed lines selected")?;
+    let location = if action == LineAction::Discard {
+        ApplyLocation::WorkDir
+    } else {
+        ApplyLocation::Index
+    };
+    let patch = Diff::from_buffer(&bytes)?;
+    repo.apply(&patch, location, None)?;
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitStash {
+    /// Position in the stash list; 0 is the newest
+    pub index: usize,
+    pub id: String,
+    /// Branch the changes were stashed from
+    pub branch: Option<String>,
+    pub message: String,
+    /// Seconds since the epoch
+    pub time: i64,
+    /// Tracked files changed
+    pub files: usize,
+    /// Untracked files included with --include-untracked
+    pub untracked: usize,
+}
+
+/// Split "WIP on main: 1a2b3c4 subject" or "On main: message"
+fn parse_stash_message(raw: &str) -> (Option<String>, String) {
+    let rest = raw
+        .strip_prefix("WIP on ")
+        .or_else(|| raw.strip_prefix("On "));
+    match rest.and_then(|r| r.split_once(": ")) {
+        Some((branch, message)) => (Some(branch.to_string()), message.to_string()),
+        None => (None, raw.to_string()),
+    }
+}
+
+/// Changes recorded in a stash commit: its tracked files against the
+/// commit it was made on, then the untracked ones it carries, if any
+fn stash_diffs<'r>(
+    repo: &'r Repository,
+    commit: &git2::Commit,
+    opts: Option<&mut DiffOptions>,
+) -> Result<(Diff<'r>, Option<Diff<'r>>), git2::Error> {
+    let tree = commit.tree()?;
+    let base = commit.parent(0)?.tree()?;
+    let mut opts = opts;
+    let tracked = repo.diff_tree_to_tree(Some(&base), Some(&tree), opts.as_deref_mut())?;
+    let untracked = match commit.parent(2) {
+        Ok(parent) => Some(repo.diff_tree_to_tree(None, Some(&parent.tree()?), opts)?),
+        Err(_) => None,
+    };
+    Ok((tracked, untracked))
+}
+
+pub fn stash_list(dir: &str) -> Result<Vec<GitStash>> {
+    let mut repo = open_repo(dir)?;
+    let mut entries = Vec::new();
+    repo.stash_foreach(|index, message, id| {
+        entries.push((index, message.to_string(), *id));
+        true
+    })?;
+    let mut list = Vec::with_capacity(entries.len());
+    for (index, raw, id) in entries {
+        let commit = repo.find_commit(id)?;
+        let (files, untracked) = match stash_diffs(&repo, &commit, None) {
+            Ok((tracked, untracked)) => (
+                tracked.deltas().len(),
+                untracked.map_or(0, |d| d.deltas().len()),
+            ),
+            Err(_) => (0, 0),
+        };
+        let (branch, message) = parse_stash_message(&raw);
+        list.push(GitStash {
+            index,
+            id: id.to_string(),
+            branch,
+            message,
+            time: commit.time().seconds(),
+            files,
+            untracked,
+        });
+    }
+    Ok(list)
+}
+
+/// Stash local changes; the message defaults to git's "WIP on <branch>"
+pub fn stash_push(
+    dir: &str,
+    message: Option<&str>,
+    include_untracked: bool,
+    keep_index: bool,
+) -> Result<()> {
+    let mut repo = open_repo(dir)?;
+    // Stashing works without a configured identity, like `git stash`
+    let signature = repo
+        .signature()
+        .or_else(|_| Signature::now("jaterm", "jaterm@localhost"))?;
+    let mut flags = StashFlags::DEFAULT;
+    if include_untracked {
+        flags |= StashFlags::INCLUDE_UNTRACKED;
+    }
+    if keep_index {
+        flags |= StashFlags::KEEP_INDEX;
+    }
+    repo.stash_save2(&signature, message.filter(|m| !m.is_empty()), Some(flags))?;
+    Ok(())
+}
+
+/// Apply stash `index`, restoring what was staged, and with `pop` drop it
+/// once applied
+pub fn stash_apply(dir: &str, index: usize, pop: bool) -> Result<()> {
+    let mut repo = open_repo(dir)?;
+    let mut opts = StashApplyOptions::new();
+    opts.reinstantiate_index();
+    let res = if pop {
+        repo.stash_pop(index, Some(&mut opts))
+    } else {
+        repo.stash_apply(index, Some(&mut opts))
+    };
+    match res {
+        Err(e) if e.code() == ErrorCode::Conflict => {
+            bail!("local changes would be overwritten; commit or stash them first")
+        }
+        other => Ok(other?),
+    }
+}
+
+pub fn stash_drop(dir: &str, index: usize) -> Result<()> {
+    let mut repo = open_repo(dir)?;
+    Ok(repo.stash_drop(index)?)
+}
+
+/// The changes of stash `index`, untracked files included, as files, hunks
+/// and lines
+pub fn stash_show(dir: &str, index: usize, context: Option<u32>) -> Result<Vec<GitDiffFile>> {
+    let mut repo = open_repo(dir)?;
+    let mut id = None;
+    repo.stash_foreach(|i, _, oid| {
+        if i == index {
+            id = Some(*oid);
+        }
+        id.is_none()
+    })?;
+    let id = id.with_context(|| format!("no stash entry {}", index))?;
+    let commit = repo.find_commit(id)?;
+    let mut opts = DiffOptions::new();
+    opts.context_lines(context.unwrap_or(DEFAULT_CONTEXT));
+    let (tracked, untracked) = stash_diffs(&repo, &commit, Some(&mut opts))?;
+    let mut files = diff_to_files(&tracked)?;
+    if let Some(untracked) = untracked {
+        for mut file in diff_to_files(&untracked)? {
+            file.status = "untracked".into();
+            files.push(file);
+        }
+    }
+    Ok(files)
+}
+
+/// Run git in `dir` without an editor, failing with its output
+fn git_cli(dir: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .current_dir(dir)
+        .env("GIT_EDITOR", "true")
+        .env("LC_ALL", "C")
+        .args(args)
+        .output()
+        .context("running git")?;
+    let text = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    if !output.status.success() {
+        bail!("{}", text.trim());
+    }
+    Ok(text)
+}
+
+/// Continue, abort or skip the merge, rebase, cherry-pick, revert, bisect
+/// or `git am` in progress
+pub fn operation_action(dir: &str, action: &str) -> Result<String> {
+    let dir = expand_tilde(dir);
+    let st = status(&dir)?;
+    let Some(kind) = st.operation.as_deref() else {
+        bail!("no merge, rebase, cherry-pick, revert or bisect in progress");
+    };
+    if !matches!(action, "continue" | "abort" | "skip") {
+        bail!("unknown action '{}' (continue, abort or skip)", action);
+    }
+    if action == "continue" && st.conflicted > 0 {
+        bail!(
+            "{} file(s) still conflicted: {}",
+            st.conflicted,
+            st.conflicted_files.join(", ")
+        );
+    }
+    let flag = format!("--{}", action);
+    let args: Vec<&str> = match (kind, action) {
+        ("bisecting", "abort") => vec!["bisect", "reset"],
+        ("bisecting", "skip") => vec!["bisect", "skip"],
+        ("bisecting", _) => bail!("mark commits good or bad to go on bisecting"),
+        ("merging", "skip") => bail!("a merge cannot be skipped"),
+        ("merging", _) => vec!["merge", &flag],
+        ("rebasing", _) => vec!["rebase", &flag],
+        ("cherry-picking", _) => vec!["cherry-pick", &flag],
+        ("reverting", _) => vec!["revert", &flag],
+        _ => vec!["am", &flag],
+    };
+    git_cli(&dir, &args)
+}
+
+/// Resolve a conflicted file with one side's version, "ours" or "theirs"
+/// as git means them (during a rebase "ours" is the branch rebased onto).
+/// `file` is relative to the repository root, as in `GitStatus`. A side
+/// that deleted the file resolves by deleting it.
+pub fn resolve(dir: &str, file: &str, side: &str) -> Result<()> {
+    let flag = match side {
+        "ours" => "--ours",
+        "theirs" => "--theirs",
+        _ => bail!("unknown side '{}' (ours or theirs)", side),
+    };
+    let repo = open_repo(dir)?;
+    let root = repo
+        .workdir()
+        .context("bare repository")?
+        .to_string_lossy()
+        .to_string();
+    let index = repo.index()?;
+    let conflict = index
+        .conflicts()?
+        .filter_map(Result::ok)
+        .find(|c| {
+            [&c.ancestor, &c.our, &c.their]
+                .iter()
+                .any(|e| e.as_ref().is_some_and(|e| e.path == file.as_bytes()))
+        })
+        .with_context(|| format!("{} is not conflicted", file))?;
+    let kept = if side == "ours" {
+        conflict.our
+    } else {
+        conflict.their
+    };
+    if kept.is_some() {
+        git_cli(&root, &["checkout", flag, "--", file])?;
+        git_cli(&root, &["add", "--", file])?;
+    } else {
+        git_cli(&root, &["rm", "--quiet", "--", file])?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(history: &[(&str, &[&str])]) -> Vec<GraphRow> {
+        let mut layout = GraphLayout::default();
+        history
+            .iter()
+            .map(|(id, parents)| {
+                let parents: Vec<String> = parents.iter().map(|p| p.to_string()).collect();
+                layout.row(id, &parents)
+            })
+            .collect()
+    }
+
+    fn edges(pairs: &[(usize, usize)]) -> Vec<GraphEdge> {
+        pairs
+            .iter()
+            .map(|&(from, to)| GraphEdge { from, to })
+            .collect()
+    }
+
+    #[test]
+    fn linear_history_stays_in_one_lane() {
+        let rows = rows(&[("c", &["b"]), ("b", &["a"]), ("a", &[])]);
+        assert!(rows.iter().all(|r| r.column == 0));
+        assert_eq!(rows[0].edges, edges(&[(0, 0)]));
+        assert!(rows[2].edges.is_empty());
+    }
+
+    #[test]
+    fn merge_opens_and_closes_a_lane() {
+        // m merges f into main; f and b both come from a
+        let rows = rows(&[("m", &["b", "f"]), ("f", &["a"]), ("b", &["a"]), ("a", &[])]);
+        assert_eq!(rows[0].column, 0);
+        assert_eq!(rows[0].edges, edges(&[(0, 0), (0, 1)]));
+        assert_eq!(rows[1].column, 1);
+        assert_eq!(rows[1].edges, edges(&[(0, 0), (1, 1)]));
+        // b's parent is already waited for in lane 1, so lane 0 joins it
+        assert_eq!(rows[2].column, 0);
+        assert_eq!(rows[2].edges, edges(&[(1, 1), (0, 1)]));
+        assert_eq!(rows[3].column, 1);
+    }
+
+    fn commit_file(repo: &Repository, file: &str, text: &str, message: &str) {
+        let root = repo.workdir().unwrap();
+        std::fs::write(root.join(file), text).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(file)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("t", "t@example.com").unwrap();
+        let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+            .unwrap();
+    }
+
+    #[test]
+    fn checkout_reports_local_changes_in_the_way() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        commit_file(&repo, "a.txt", "one\n", "first");
+        let path = dir.path().to_str().unwrap();
+        let main = repo.head().unwrap().shorthand().unwrap().to_string();
+        branch_create(path, "feature", None).unwrap();
+        assert!(checkout(path, "feature").unwrap().checked_out);
+        commit_file(&repo, "a.txt", "two\n", "second");
+        assert!(checkout(path, &main).unwrap().checked_out);
+
+        std::fs::write(dir.path().join("a.txt"), "dirty\n").unwrap();
+        let res = checkout(path, "feature").unwrap();
+        assert!(!res.checked_out);
+        assert_eq!(res.conflicts, vec!["a.txt".to_string()]);
+        assert_eq!(repo.head().unwrap().shorthand(), Some(main.as_str()));
+        assert!(branch_delete(path, "feature", false).is_err());
+        branch_delete(path, "feature", true).unwrap();
+    }
+
+    fn index_text(repo: &Repository, file: &str) -> String {
+        let mut index = repo.index().unwrap();
+        index.read(true).unwrap();
+        let entry = index.get_path(Path::new(file), 0).unwrap();
+        let blob = repo.find_blob(entry.id).unwrap();
+        String::from_utf8(blob.content().to_vec()).unwrap()
+    }
+
+    #[test]
+    fn stages_unstages_and_discards_single_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        commit_file(&repo, "a.txt", "one\ntwo\nthree\n", "first");
+        let path = dir.path().to_str().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "one\n2\nthree\nfour").unwrap();
+
+        let files = diff_files(path, &GitDiffOptions::default()).unwrap();
+        assert_eq!(files.len(), 1);
+        let lines = &files[0].hunks[0].lines;
+        let origins: Vec<&str> = lines.iter().map(|l| l.origin.as_str()).collect();
+        assert_eq!(origins, vec![" ", "-", "+", " ", "+"]);
+        assert_eq!(lines[4].new_lineno, Some(4));
+
+        // Only the added last line, which has no line break
+        apply_lines(path, LineAction::Stage, "a.txt", 0, Some(&[4]), None).unwrap();
+        assert_eq!(index_text(&repo, "a.txt"), "one\ntwo\nthree\nfour");
+        // Then "two" -> "2" too, and take the last line back out
+        apply_lines(path, LineAction::Stage, "a.txt", 0, None, None).unwrap();
+        assert_eq!(index_text(&repo, "a.txt"), "one\n2\nthree\nfour");
+        let staged = GitDiffOptions {
+            staged: true,
+            ..Default::default()
+        };
+        let lines = &diff_files(path, &staged).unwrap()[0].hunks[0].lines;
+        let last = lines.iter().position(|l| l.content == "four").unwrap();
+        apply_lines(path, LineAction::Unstage, "a.txt", 0, Some(&[last]), None).unwrap();
+        assert_eq!(index_text(&repo, "a.txt"), "one\n2\nthree\n");
+
+        apply_lines(path, LineAction::Discard, "a.txt", 0, None, None).unwrap();
+        let text = std::fs::read_to_string(dir.path().join("a.txt")).unwrap();
+        assert_eq!(text, "one\n2\nthree\n");
+    }
+
+    #[test]
+    fn stash_message_names_the_branch() {
+        assert_eq!(
+            parse_stash_message("WIP on main: 1a2b3c4 fix it"),
+            (Some("main".to_string()), "1a2b3c4 fix it".to_string())
+        );
+        assert_eq!(
+            parse_stash_message("On feature/x: half done"),
+            (Some("feature/x".to_string()), "half done".to_string())
+        );
+        assert_eq!(parse_stash_message("odd"), (None, "odd".to_string()));
+    }
+}
@@ -0,0 +1,604 @@
+use anyhow::{bail, Context, Result};
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::git::expand_tilde;
+use super::output::Output;
+
+// How often a followed file is checked for new data or rotation
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+// Longest line reported; longer lines are cut
+const MAX_LINE: usize = 16 * 1024;
+
+pub struct LogsOptions {
+    pub files: Vec<String>,
+    pub units: Vec<String>,
+    /// Container names or ids, read with `docker logs` / `podman logs`
+    pub containers: Vec<String>,
+    /// Lines of history to show per source before following
+    pub lines: usize,
+    pub follow: bool,
+    pub grep: Option<String>,
+    pub ignore_case: bool,
+    /// Minimum level: "error", "warning", "info" or "debug"
+    pub level: Option<String>,
+}
+
+/// A line as read from its source, before filtering
+struct RawLine {
+    source: String,
+    line: String,
+    /// Journal entries carry their own time and priority
+    ts: Option<u64>,
+    level: Option<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LogLine {
+    /// File path, "unit:<name>" for journal entries or "container:<name>"
+    pub source: String,
+    /// Milliseconds since the Unix epoch: from the journal or a leading ISO
+    /// 8601 timestamp in the line, otherwise when the line was read
+    pub ts: u64,
+    /// "error", "warning", "info" or "debug" when known
+    pub level: Option<&'static str>,
+    pub line: String,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn severity(level: &str) -> u8 {
+    match level {
+        "error" => 3,
+        "warning" => 2,
+        "info" => 1,
+        _ => 0,
+    }
+}
+
+/// journald PRIORITY (syslog levels 0-7) as a level name
+fn priority_level(priority: u8) -> &'static str {
+    match priority {
+        0..=3 => "error",
+        4 => "warning",
+        5 | 6 => "info",
+        _ => "debug",
+    }
+}
+
+/// Days since the Unix epoch for a proleptic Gregorian date
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let mp = (m + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+struct LineParser {
+    level: Regex,
+    iso: Regex,
+}
+
+impl LineParser {
+    fn new() -> Self {
+        LineParser {
+            level: RegexBuilder::new(
+                r"\b(fatal|panic|crit(?:ical)?|err(?:or)?|warn(?:ing)?|info|notice|debug|trace)\b",
+            )
+            .case_insensitive(true)
+            .build()
+            .expect("valid level pattern"),
+            iso: Regex::new(
+                r"^\[?(\d{4})-(\d{2})-(\d{2})[T ](\d{2}):(\d{2}):(\d{2})(?:[.,](\d{1,9}))?(Z|[+-]\d{2}:?\d{2})?",
+            )
+            .expect("valid timestamp pattern"),
+        }
+    }
+
+    fn level(&self, line: &str) -> Option<&'static str> {
+        let word = self.level.find(line)?.as_str().to_ascii_lowercase();
+        Some(match word.as_str() {
+            "fatal" | "panic" | "crit" | "critical" | "err" | "error" => "error",
+            "warn" | "warning" => "warning",
+            "info" | "notice" => "info",
+            _ => "debug",
+        })
+    }
+
+    /// Epoch ms for a leading ISO 8601 timestamp; local times are taken as UTC
+    fn timestamp(&self, line: &str) -> Option<u64> {
+        let c = self.iso.captures(line)?;
+        let n = |i: usize| c.get(i).and_then(|m| m.as_str().parse::<i64>().ok());
+        let days = days_from_civil(n(1)?, n(2)?, n(3)?);
+        let mut secs = days * 86400 + n(4)? * 3600 + n(5)? * 60 + n(6)?;
+        if let Some(offset) = c.get(8).map(|m| m.as_str()).filter(|o| *o != "Z") {
+            let digits: String = offset[1..].chars().filter(|c| *c != ':').collect();
+            let minutes =
+                digits[..2].parse::<i64>().ok()? * 60 + digits[2..].parse::<i64>().ok()?;
+            let sign = if offset.starts_with('-') { -1 } else { 1 };
+            secs -= sign * minutes * 60;
+        }
+        let millis = c
+            .get(7)
+            .map(|m| format!("{:0<3}", &m.as_str()[..m.as_str().len().min(3)]))
+            .and_then(|m| m.parse::<i64>().ok())
+            .unwrap_or(0);
+        u64::try_from(secs * 1000 + millis).ok()
+    }
+}
+
+/// Up to `n` complete lines from the end of the file, and the offset after them
+fn read_tail(file: &mut File, n: usize) -> std::io::Result<(Vec<String>, u64)> {
+    let len = file.seek(SeekFrom::End(0))?;
+    if n == 0 {
+        return Ok((Vec::new(), len));
+    }
+    let mut start = len;
+    let mut buf = Vec::new();
+    // Read backwards in blocks until enough newlines have been seen
+    while start > 0 && buf.iter().filter(|b| **b == b'\n').count() <= n {
+        let block = u64::min(64 * 1024, start);
+        start -= block;
+        let mut chunk = vec![0u8; block as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut chunk)?;
+        chunk.extend_from_slice(&buf);
+        buf = chunk;
+    }
+    let text = String::from_utf8_lossy(&buf);
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+    // A partial first line when we did not reach the start of the file
+    if start > 0 && !lines.is_empty() {
+        lines.remove(0);
+    }
+    let skip = lines.len().saturating_sub(n);
+    file.seek(SeekFrom::Start(len))?;
+    Ok((lines.split_off(skip), len))
+}
+
+#[cfg(unix)]
+fn inode(meta: &std::fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta.ino()
+}
+
+#[cfg(not(unix))]
+fn inode(_meta: &std::fs::Metadata) -> u64 {
+    0
+}
+
+/// Send the last lines of `path`, then keep sending new lines. Reopens the
+/// path when it is replaced (rename rotation) or truncated (copytruncate).
+/// Idle followers notice `stop` on their next poll.
+fn follow_file(
+    path: String,
+    lines: usize,
+    follow: bool,
+    tx: mpsc::Sender<RawLine>,
+    stop: Arc<AtomicBool>,
+) {
+    let send = |line: String| {
+        tx.send(RawLine {
+            source: path.clone(),
+            line,
+            ts: None,
+            level: None,
+        })
+        .is_ok()
+    };
+    let mut file = match File::open(&path) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            return;
+        }
+    };
+    let (history, mut pos) = match read_tail(&mut file, lines) {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            return;
+        }
+    };
+    for line in history {
+        if !send(line) {
+            return;
+        }
+    }
+    if !follow {
+        return;
+    }
+    let mut ino = file.metadata().map(|m| inode(&m)).unwrap_or(0);
+    let mut reader = BufReader::new(file);
+    let mut pending: Vec<u8> = Vec::new();
+    loop {
+        let mut chunk = Vec::new();
+        match reader.read_until(b'\n', &mut chunk) {
+            Ok(0) => {
+                thread::sleep(POLL_INTERVAL);
+                if stop.load(Ordering::Relaxed) {
+                    return;
+                }
+                let meta = std::fs::metadata(&path);
+                let rotated = meta.as_ref().is_ok_and(|m| inode(m) != ino);
+                let truncated = meta.as_ref().is_ok_and(|m| m.len() < pos);
+                if rotated || truncated {
+                    if rotated {
+                        // Lines written to the old file just before the switch
+                        let mut rest = Vec::new();
+                        let _ = reader.read_to_end(&mut rest);
+                        pending.extend_from_slice(&rest);
+                        for line in String::from_utf8_lossy(&pending).lines() {
+                            if !send(line.trim_end_matches('\r').to_string()) {
+                                return;
+                            }
+                        }
+                    }
+                    if let Ok(f) = File::open(&path) {
+                        ino = f.metadata().map(|m| inode(&m)).unwrap_or(0);
+                        reader = BufReader::new(f);
+                        pos = 0;
+                        pending.clear();
+                    }
+                }
+                continue;
+            }
+            Ok(n) => pos += n as u64,
+            Err(_) => {
+                thread::sleep(POLL_INTERVAL);
+                continue;
+            }
+        }
+        pending.extend_from_slice(&chunk);
+        if pending.ends_with(b"\n") {
+            let line = String::from_utf8_lossy(&pending)
+                .trim_end_matches(['\n', '\r'])
+                .to_string();
+            pending.clear();
+            if !send(line) {
+                return;
+            }
+        }
+    }
+}
+
+/// Run journalctl for a unit in JSON mode and send its messages
+fn follow_unit(
+    unit: String,
+    lines: usize,
+    follow: bool,
+    tx: mpsc::Sender<RawLine>,
+    children: mpsc::Sender<Child>,
+) {
+    let source = format!("unit:{}", unit);
+    let mut cmd = Command::new("journalctl");
+    cmd.args([
+        "-u",
+        &unit,
+        "-o",
+        "json",
+        "--no-pager",
+        "-n",
+        &lines.to_string(),
+    ]);
+    if follow {
+        cmd.arg("-f");
+    }
+    let mut child = match cmd.stdout(Stdio::piped()).stderr(Stdio::null()).spawn() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("journalctl: {}", e);
+            return;
+        }
+    };
+    let Some(stdout) = child.stdout.take() else {
+        return;
+    };
+    let _ = children.send(child);
+    for line in BufReader::new(stdout).lines() {
+        let Ok(line) = line else { break };
+        let Ok(entry) = serde_json::from_str::<serde_json::Value>(&line) else {
+            continue;
+        };
+        let message = match &entry["MESSAGE"] {
+            serde_json::Value::String(s) => s.clone(),
+            // Non-UTF-8 messages come as byte arrays
+            serde_json::Value::Array(bytes) => String::from_utf8_lossy(
+                &bytes
+                    .iter()
+                    .filter_map(|b| b.as_u64().map(|b| b as u8))
+                    .collect::<Vec<_>>(),
+            )
+            .to_string(),
+            _ => continue,
+        };
+        let ts = entry["__REALTIME_TIMESTAMP"]
+            .as_str()
+            .and_then(|t| t.parse::<u64>().ok())
+            .map(|us| us / 1000);
+        let priority = entry["PRIORITY"]
+            .as_str()
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(6);
+        let sent = tx.send(RawLine {
+            source: source.clone(),
+            line: message,
+            ts,
+            level: Some(priority_level(priority)),
+        });
+        if sent.is_err() {
+            break;
+        }
+    }
+}
+
+/// Run `docker logs` (or podman) for a container and send its output. Both
+/// streams carry log lines; each starts with the runtime's own timestamp.
+fn follow_container(
+    container: String,
+    lines: usize,
+    follow: bool,
+    tx: mpsc::Sender<RawLine>,
+    children: mpsc::Sender<Child>,
+) {
+    let Some(cli) = super::containers::cli() else {
+        eprintln!("{}: no container runtime found", container);
+        return;
+    };
+    let source = format!("container:{}", container);
+    let mut cmd = Command::new(cli);
+    cmd.args(["logs", "--timestamps", "--tail", &lines.to_string()]);
+    if follow {
+        cmd.arg("--follow");
+    }
+    cmd.arg(&container);
+    let mut child = match cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{} logs: {}", cli, e);
+            return;
+        }
+    };
+    let (Some(stdout), Some(stderr)) = (child.stdout.take(), child.stderr.take()) else {
+        return;
+    };
+    let _ = children.send(child);
+    let read = move |stream: Box<dyn Read + Send>, tx: mpsc::Sender<RawLine>, source: String| {
+        let parser = LineParser::new();
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else { break };
+            let (ts, line) = match line.split_once(' ') {
+                Some((stamp, rest)) if parser.timestamp(stamp).is_some() => {
+                    (parser.timestamp(stamp), rest.to_string())
+                }
+                _ => (None, line),
+            };
+            let sent = tx.send(RawLine {
+                source: source.clone(),
+                line,
+                ts,
+                level: None,
+            });
+            if sent.is_err() {
+                break;
+            }
+        }
+    };
+    let stderr_reader = {
+        let (tx, source) = (tx.clone(), source.clone());
+        thread::spawn(move || read(Box::new(stderr), tx, source))
+    };
+    read(Box::new(stdout), tx, source);
+    let _ = stderr_reader.join();
+}
+
+/// Print log lines from files, journal units and containers as JSON, one per line.
+/// With `follow` this runs until the output is closed.
+pub fn run(opts: LogsOptions, out: &mut dyn Output) -> Result<()> {
+    if opts.files.is_empty() && opts.units.is_empty() && opts.containers.is_empty() {
+        bail!("give at least one --file, --unit or --container");
+    }
+    let grep = opts
+        .grep
+        .as_deref()
+        .map(|p| {
+            RegexBuilder::new(p)
+                .case_insensitive(opts.ignore_case)
+                .build()
+                .with_context(|| format!("invalid pattern '{}'", p))
+        })
+        .transpose()?;
+    let min_level = match opts.level.as_deref() {
+        None => None,
+        Some(l @ ("error" | "warning" | "info" | "debug")) => Some(severity(l)),
+        Some(l) => bail!("unknown level '{}'", l),
+    };
+
+    let (tx, rx) = mpsc::channel();
+    let (child_tx, child_rx) = mpsc::channel::<Child>();
+    let stop = Arc::new(AtomicBool::new(false));
+    for file in &opts.files {
+        let (path, tx, stop) = (expand_tilde(file), tx.clone(), stop.clone());
+        let (lines, follow) = (opts.lines, opts.follow);
+        thread::spawn(move || follow_file(path, lines, follow, tx, stop));
+    }
+    for unit in &opts.units {
+        let (unit, tx, children) = (unit.clone(), tx.clone(), child_tx.clone());
+        let (lines, follow) = (opts.lines, opts.follow);
+        thread::spawn(move || follow_unit(unit, lines, follow, tx, children));
+    }
+    for container in &opts.containers {
+        let (container, tx, children) = (container.clone(), tx.clone(), child_tx.clone());
+        let (lines, follow) = (opts.lines, opts.follow);
+        thread::spawn(move || follow_container(container, lines, follow, tx, children));
+    }
+    drop(tx);
+    drop(child_tx);
+
+    let parser = LineParser::new();
+    // Lines without a level of their own (stack traces, wrapped output)
+    // take the level of the line before them from the same source
+    let mut last_level: std::collections::HashMap<String, &'static str> = Default::default();
+    loop {
+        let raw = match rx.recv_timeout(POLL_INTERVAL) {
+            Ok(raw) => raw,
+            Err(mpsc::RecvTimeoutError::Timeout) if out.closed() => break,
+            Err(mpsc::RecvTimeoutError::Timeout) => continue,
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                for mut child in child_rx.try_iter() {
+                    let _ = child.wait();
+                }
+                return Ok(());
+            }
+        };
+        let RawLine {
+            source,
+            mut line,
+            ts,
+            level,
+        } = raw;
+        let level = level
+            .or_else(|| parser.level(&line))
+            .or_else(|| last_level.get(&source).copied());
+        if let Some(level) = level {
+            last_level.insert(source.clone(), level);
+        }
+        if let Some(min) = min_level {
+            if level.is_none_or(|l| severity(l) < min) {
+                continue;
+            }
+        }
+        if grep.as_ref().is_some_and(|re| !re.is_match(&line)) {
+            continue;
+        }
+        if line.len() > MAX_LINE {
+            let mut cut = MAX_LINE;
+            while !line.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            line.truncate(cut);
+        }
+        let entry = LogLine {
+            ts: ts
+                .or_else(|| parser.timestamp(&line))
+                .unwrap_or_else(now_ms),
+            source,
+            level,
+            line,
+        };
+        let written = serde_json::to_string(&entry)
+            .map_err(std::io::Error::from)
+            .and_then(|json| out.line(&json));
+        if written.is_err() {
+            break;
+        }
+    }
+    // Reader went away; stop the followers and journalctl/logs children
+    stop.store(true, Ordering::Relaxed);
+    for mut child in child_rx.try_iter() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn parses_iso_timestamps() {
+        let p = LineParser::new();
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(
+            p.timestamp("2024-03-01T12:34:56Z GET /"),
+            Some(1709296496000)
+        );
+        assert_eq!(
+            p.timestamp("[2024-03-01 12:34:56.123456+02:00] started"),
+            Some(1709289296123)
+        );
+        assert_eq!(
+            p.timestamp("2024-03-01T12:34:56,5-0530 slow"),
+            Some(1709316296500)
+        );
+        // No offset is taken as UTC
+        assert_eq!(p.timestamp("2000-02-29T00:00:00 leap"), Some(951782400000));
+        assert_eq!(p.timestamp("Mar  1 12:34:56 host sshd[1]: ok"), None);
+    }
+
+    #[test]
+    fn tail_crosses_block_boundaries() {
+        let mut tmp = tempfile::tempfile().unwrap();
+        // 11 bytes per line, so the 64 KiB blocks start mid-line
+        for i in 0..10000 {
+            writeln!(tmp, "line {:05}", i).unwrap();
+        }
+        let (lines, pos) = read_tail(&mut tmp, 5000).unwrap();
+        assert_eq!(pos, 110000);
+        assert_eq!(lines.len(), 5000);
+        assert_eq!(lines[0], "line 05000");
+        assert_eq!(lines[4999], "line 09999");
+
+        // Reading back to the start keeps the first line
+        let (lines, _) = read_tail(&mut tmp, 20000).unwrap();
+        assert_eq!(lines.len(), 10000);
+        assert_eq!(lines[0], "line 00000");
+        assert!(read_tail(&mut tmp, 0).unwrap().0.is_empty());
+    }
+
+    #[test]
+    fn follow_survives_rotation_and_truncation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        std::fs::write(&path, "a\n").unwrap();
+        let (tx, rx) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let follower = {
+            let (path, stop) = (path.to_string_lossy().to_string(), stop.clone());
+            thread::spawn(move || follow_file(path, 10, true, tx, stop))
+        };
+        let next = || {
+            rx.recv_timeout(Duration::from_secs(5))
+                .map(|l| l.line)
+                .unwrap()
+        };
+        let append = |text: &str| {
+            let mut f = File::options().append(true).open(&path).unwrap();
+            f.write_all(text.as_bytes()).unwrap();
+        };
+        assert_eq!(next(), "a");
+        append("b\n");
+        assert_eq!(next(), "b");
+
+        // Rename rotation, with a last line written to the old file
+        append("c\n");
+        std::fs::rename(&path, dir.path().join("app.log.1")).unwrap();
+        std::fs::write(&path, "d line\n").unwrap();
+        assert_eq!(next(), "c");
+        assert_eq!(next(), "d line");
+
+        // copytruncate: the file shrinks below what was already read
+        std::fs::write(&path, "e\n").unwrap();
+        assert_eq!(next(), "e");
+
+        stop.store(true, Ordering::Relaxed);
+        follower.join().unwrap();
+    }
+}
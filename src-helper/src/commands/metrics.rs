@@ -0,0 +1,437 @@
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use super::output::Output;
+
+// Pseudo and virtual filesystems left out of disk usage
+const SKIP_FS: &[&str] = &[
+    "proc",
+    "sysfs",
+    "devtmpfs",
+    "devpts",
+    "tmpfs",
+    "cgroup",
+    "cgroup2",
+    "securityfs",
+    "pstore",
+    "debugfs",
+    "tracefs",
+    "configfs",
+    "fusectl",
+    "mqueue",
+    "hugetlbfs",
+    "bpf",
+    "autofs",
+    "binfmt_misc",
+    "rpc_pipefs",
+    "nsfs",
+    "ramfs",
+    "squashfs",
+    "efivarfs",
+    "fuse.portal",
+];
+
+#[derive(Debug, Serialize)]
+pub struct Metrics {
+    /// Milliseconds since the Unix epoch
+    pub timestamp: u64,
+    pub uptime_secs: f64,
+    /// 1, 5 and 15 minute load averages
+    pub load: [f64; 3],
+    pub cpu: CpuUsage,
+    pub memory: Memory,
+    pub disks: Vec<DiskUsage>,
+    pub network: Vec<NetworkUsage>,
+    pub processes: Vec<TopProcess>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CpuUsage {
+    /// Busy percentage over the sample window, across all cores
+    pub usage: f64,
+    pub cores: Vec<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Memory {
+    pub total: u64,
+    pub available: u64,
+    pub used: u64,
+    pub swap_total: u64,
+    pub swap_used: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiskUsage {
+    pub mount: String,
+    pub device: String,
+    pub fs_type: String,
+    pub total: u64,
+    pub used: u64,
+    pub available: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NetworkUsage {
+    pub interface: String,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    /// Bytes per second over the sample window
+    pub rx_rate: f64,
+    pub tx_rate: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TopProcess {
+    pub pid: u32,
+    pub name: String,
+    /// Percentage of one core over the sample window
+    pub cpu: f64,
+    /// Resident memory in bytes
+    pub rss: u64,
+}
+
+/// Fields of /proc/<pid>/stat
+pub(crate) struct ProcStat {
+    pub pid: u32,
+    pub ppid: u32,
+    pub name: String,
+    /// R, S, D, Z, T, ...
+    pub state: char,
+    /// utime + stime in clock ticks
+    pub cpu_ticks: u64,
+    /// Start time in clock ticks after boot
+    pub start_ticks: u64,
+    pub rss_pages: u64,
+}
+
+pub(crate) fn read_proc_stat(pid: u32) -> Option<ProcStat> {
+    parse_proc_stat(&fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?)
+}
+
+/// Parse one /proc/<pid>/stat line
+fn parse_proc_stat(raw: &str) -> Option<ProcStat> {
+    // The name may itself contain spaces and parentheses
+    let open = raw.find('(')?;
+    let close = raw.rfind(')')?;
+    let pid = raw[..open].trim().parse().ok()?;
+    let name = raw[open + 1..close].to_string();
+    let rest: Vec<&str> = raw[close + 1..].split_whitespace().collect();
+    let num = |i: usize| rest.get(i).and_then(|v| v.parse::<u64>().ok());
+    // Fields after the name, starting at `state`
+    Some(ProcStat {
+        pid,
+        ppid: num(1)? as u32,
+        name,
+        state: rest.first()?.chars().next()?,
+        cpu_ticks: num(11)? + num(12)?,
+        start_ticks: num(19)?,
+        rss_pages: num(21)?,
+    })
+}
+
+/// Numeric entries of /proc
+pub(crate) fn pids() -> Vec<u32> {
+    fs::read_dir("/proc")
+        .map(|dir| {
+            dir.flatten()
+                .filter_map(|e| e.file_name().to_str()?.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(unix)]
+pub(crate) fn clock_ticks() -> f64 {
+    // SAFETY: sysconf only reads a configuration value
+    let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if ticks > 0 {
+        ticks as f64
+    } else {
+        100.0
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn clock_ticks() -> f64 {
+    100.0
+}
+
+#[cfg(unix)]
+pub(crate) fn page_size() -> u64 {
+    // SAFETY: sysconf only reads a configuration value
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if size > 0 {
+        size as u64
+    } else {
+        4096
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn page_size() -> u64 {
+    4096
+}
+
+/// Counters that only mean something as a difference between two samples
+struct Sample {
+    at: Instant,
+    /// (busy, total) jiffies: overall first, then per core
+    cpu: Vec<(u64, u64)>,
+    net: HashMap<String, (u64, u64)>,
+    procs: HashMap<u32, (String, u64, u64)>,
+}
+
+fn read_cpu() -> Vec<(u64, u64)> {
+    let stat = fs::read_to_string("/proc/stat").unwrap_or_default();
+    stat.lines()
+        .filter(|l| l.starts_with("cpu"))
+        .map(|l| {
+            let v: Vec<u64> = l
+                .split_whitespace()
+                .skip(1)
+                .filter_map(|x| x.parse().ok())
+                .collect();
+            let total: u64 = v.iter().take(8).sum();
+            // idle + iowait
+            let idle = v.get(3).copied().unwrap_or(0) + v.get(4).copied().unwrap_or(0);
+            (total.saturating_sub(idle), total)
+        })
+        .collect()
+}
+
+fn read_net() -> HashMap<String, (u64, u64)> {
+    let dev = fs::read_to_string("/proc/net/dev").unwrap_or_default();
+    dev.lines()
+        .skip(2)
+        .filter_map(|l| {
+            let (name, rest) = l.split_once(':')?;
+            let v: Vec<u64> = rest
+                .split_whitespace()
+                .filter_map(|x| x.parse().ok())
+                .collect();
+            Some((name.trim().to_string(), (*v.first()?, *v.get(8)?)))
+        })
+        .filter(|(name, _)| name != "lo")
+        .collect()
+}
+
+fn take_sample() -> Sample {
+    let procs = pids()
+        .into_iter()
+        .filter_map(read_proc_stat)
+        .map(|p| (p.pid, (p.name, p.cpu_ticks, p.rss_pages)))
+        .collect();
+    Sample {
+        at: Instant::now(),
+        cpu: read_cpu(),
+        net: read_net(),
+        procs,
+    }
+}
+
+fn percent(part: u64, whole: u64) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        (part as f64 * 1000.0 / whole as f64).round() / 10.0
+    }
+}
+
+pub(crate) fn read_memory() -> Memory {
+    let info = fs::read_to_string("/proc/meminfo").unwrap_or_default();
+    let kb = |key: &str| -> u64 {
+        info.lines()
+            .find_map(|l| l.strip_prefix(key)?.strip_prefix(':'))
+            .and_then(|v| v.split_whitespace().next()?.parse::<u64>().ok())
+            .unwrap_or(0)
+            * 1024
+    };
+    let total = kb("MemTotal");
+    let available = kb("MemAvailable");
+    let swap_total = kb("SwapTotal");
+    Memory {
+        total,
+        available,
+        used: total.saturating_sub(available),
+        swap_total,
+        swap_used: swap_total.saturating_sub(kb("SwapFree")),
+    }
+}
+
+#[cfg(unix)]
+fn statvfs(path: &str) -> Option<(u64, u64, u64)> {
+    let c_path = std::ffi::CString::new(path).ok()?;
+    // SAFETY: statvfs fills the zeroed struct and we only read it on success
+    let mut st: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut st) } != 0 {
+        return None;
+    }
+    let frsize = st.f_frsize as u64;
+    let total = st.f_blocks as u64 * frsize;
+    let free = st.f_bfree as u64 * frsize;
+    let available = st.f_bavail as u64 * frsize;
+    Some((total, total.saturating_sub(free), available))
+}
+
+#[cfg(not(unix))]
+fn statvfs(_path: &str) -> Option<(u64, u64, u64)> {
+    None
+}
+
+fn read_disks() -> Vec<DiskUsage> {
+    let mounts = fs::read_to_string("/proc/mounts").unwrap_or_default();
+    let mut seen = std::collections::HashSet::new();
+    mounts
+        .lines()
+        .filter_map(|l| {
+            let cols: Vec<&str> = l.split_whitespace().collect();
+            let (device, mount, fs_type) = (*cols.first()?, *cols.get(1)?, *cols.get(2)?);
+            if SKIP_FS.contains(&fs_type) || !seen.insert(device.to_string()) {
+                return None;
+            }
+            // Mount points escape spaces as \040
+            let mount = mount.replace("\\040", " ");
+            let (total, used, available) = statvfs(&mount)?;
+            (total > 0).then(|| DiskUsage {
+                mount,
+                device: device.to_string(),
+                fs_type: fs_type.to_string(),
+                total,
+                used,
+                available,
+            })
+        })
+        .collect()
+}
+
+fn read_uptime() -> f64 {
+    fs::read_to_string("/proc/uptime")
+        .ok()
+        .and_then(|u| u.split_whitespace().next()?.parse().ok())
+        .unwrap_or(0.0)
+}
+
+fn read_load() -> [f64; 3] {
+    let raw = fs::read_to_string("/proc/loadavg").unwrap_or_default();
+    let v: Vec<f64> = raw
+        .split_whitespace()
+        .take(3)
+        .filter_map(|x| x.parse().ok())
+        .collect();
+    [
+        v.first().copied().unwrap_or(0.0),
+        v.get(1).copied().unwrap_or(0.0),
+        v.get(2).copied().unwrap_or(0.0),
+    ]
+}
+
+fn collect(prev: &Sample, cur: &Sample, top: usize) -> Metrics {
+    let secs = cur.at.duration_since(prev.at).as_secs_f64().max(0.001);
+    let mut cpu = cur
+        .cpu
+        .iter()
+        .zip(prev.cpu.iter())
+        .map(|(c, p)| percent(c.0.saturating_sub(p.0), c.1.saturating_sub(p.1)));
+    let usage = cpu.next().unwrap_or(0.0);
+    let cores = cpu.collect();
+
+    let mut network: Vec<NetworkUsage> = cur
+        .net
+        .iter()
+        .map(|(name, &(rx, tx))| {
+            let (prx, ptx) = prev.net.get(name).copied().unwrap_or((rx, tx));
+            NetworkUsage {
+                interface: name.clone(),
+                rx_bytes: rx,
+                tx_bytes: tx,
+                rx_rate: rx.saturating_sub(prx) as f64 / secs,
+                tx_rate: tx.saturating_sub(ptx) as f64 / secs,
+            }
+        })
+        .collect();
+    network.sort_by(|a, b| a.interface.cmp(&b.interface));
+
+    let ticks = clock_ticks();
+    let page = page_size();
+    let mut processes: Vec<TopProcess> = cur
+        .procs
+        .iter()
+        .map(|(&pid, (name, cpu_ticks, rss_pages))| {
+            // Processes started during the window count from zero
+            let before = prev.procs.get(&pid).map(|p| p.1).unwrap_or(0);
+            let used = cpu_ticks.saturating_sub(before) as f64 / ticks;
+            TopProcess {
+                pid,
+                name: name.clone(),
+                cpu: (used / secs * 1000.0).round() / 10.0,
+                rss: rss_pages * page,
+            }
+        })
+        .collect();
+    processes.sort_by(|a, b| b.cpu.total_cmp(&a.cpu).then(b.rss.cmp(&a.rss)));
+    processes.truncate(top);
+
+    Metrics {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+        uptime_secs: read_uptime(),
+        load: read_load(),
+        cpu: CpuUsage { usage, cores },
+        memory: read_memory(),
+        disks: read_disks(),
+        network,
+        processes,
+    }
+}
+
+/// Print host metrics as JSON. Rates and CPU usage are measured over
+/// `interval`; with `watch` a new line is printed every interval until
+/// stdout is closed.
+pub fn run(interval: Duration, watch: bool, top: usize, out: &mut dyn Output) -> Result<()> {
+    if !cfg!(target_os = "linux") {
+        bail!("metrics are only available on Linux");
+    }
+    let mut prev = take_sample();
+    loop {
+        thread::sleep(interval);
+        let cur = take_sample();
+        let line = serde_json::to_string(&collect(&prev, &cur, top))?;
+        if !watch {
+            out.line(&line)?;
+            return Ok(());
+        }
+        if out.line(&line).is_err() {
+            return Ok(());
+        }
+        prev = cur;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_proc_stat_lines() {
+        let line = "4242 (my (odd) proc) S 17 4242 4242 0 -1 4194560 900 0 3 0 \
+                    150 25 0 0 20 0 4 0 123456 987654321 2048 18446744073709551615 1 1 0";
+        let p = parse_proc_stat(line).unwrap();
+        assert_eq!((p.pid, p.ppid), (4242, 17));
+        assert_eq!(p.name, "my (odd) proc");
+        assert_eq!(p.state, 'S');
+        assert_eq!(p.cpu_ticks, 175);
+        assert_eq!(p.start_ticks, 123456);
+        assert_eq!(p.rss_pages, 2048);
+
+        // Truncated after the name, or no name at all
+        assert!(parse_proc_stat("4242 (sh) S 1 4242").is_none());
+        assert!(parse_proc_stat("4242 sh S 1").is_none());
+    }
+}
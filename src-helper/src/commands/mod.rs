@@ -1,2 +1,12 @@
+pub mod containers;
+pub mod env;
 pub mod git;
-pub mod ports;
\ No newline at end of file
+pub mod logs;
+pub mod metrics;
+pub mod output;
+pub mod ports;
+pub mod ps;
+pub mod search;
+pub mod serve;
+pub mod shell_integration;
+pub mod watch;
@@ -0,0 +1,25 @@
+use std::io::Write;
+
+/// Where streaming subcommands write their JSON lines: stdout on the command
+/// line, or a request's notifications in `serve` mode. Writing fails once the
+/// reader has gone away or the request was cancelled, which is how the
+/// long-running loops learn to stop.
+pub trait Output {
+    fn line(&mut self, line: &str) -> std::io::Result<()>;
+
+    /// Whether writing would fail, for loops that can go a long time
+    /// without anything to write
+    fn closed(&self) -> bool {
+        false
+    }
+}
+
+pub struct Stdout;
+
+impl Output for Stdout {
+    fn line(&mut self, line: &str) -> std::io::Result<()> {
+        let mut out = std::io::stdout().lock();
+        writeln!(out, "{}", line)?;
+        out.flush()
+    }
+}
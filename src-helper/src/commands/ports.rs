@@ -1,37 +1,362 @@
 use anyhow::Result;
+use clap::Args;
+use serde::Serialize;
+use std::net::IpAddr;
 use std::process::Command;
 
-pub fn detect() -> Result<Vec<u16>> {
-    let mut ports;
-    
-    // Try different methods based on OS
+// Longest command line reported for a socket owner
+const MAX_CMDLINE: usize = 512;
+
+/// Which listening sockets to report
+#[derive(Debug, Clone, Args)]
+pub struct PortFilter {
+    /// Lowest port to report
+    #[arg(long, default_value_t = 1025)]
+    pub min_port: u16,
+    /// Highest port to report
+    #[arg(long, default_value_t = 65535)]
+    pub max_port: u16,
+    /// Also report bound UDP sockets
+    #[arg(long)]
+    pub udp: bool,
+    /// Skip sockets that only listen on loopback
+    #[arg(long)]
+    pub exclude_loopback: bool,
+    /// Only sockets owned by a process whose name or command line contains this
+    #[arg(long)]
+    pub process: Option<String>,
+    /// Report at most this many ports (0 for no limit)
+    #[arg(long, default_value_t = 20)]
+    pub limit: usize,
+}
+
+impl Default for PortFilter {
+    fn default() -> Self {
+        PortFilter {
+            min_port: 1025,
+            max_port: 65535,
+            udp: false,
+            exclude_loopback: false,
+            process: None,
+            limit: 20,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ListeningSocket {
+    pub port: u16,
+    /// "tcp", "tcp6", "udp" or "udp6"
+    pub proto: &'static str,
+    /// Bind address, e.g. "0.0.0.0", "127.0.0.1" or "::"
+    pub address: String,
+    pub pid: Option<u32>,
+    pub process: Option<String>,
+    pub cmdline: Option<String>,
+}
+
+impl ListeningSocket {
+    fn port_only(port: u16) -> Self {
+        ListeningSocket {
+            port,
+            proto: "tcp",
+            address: String::new(),
+            pid: None,
+            process: None,
+            cmdline: None,
+        }
+    }
+
+    fn is_loopback(&self) -> bool {
+        match self.address.parse::<IpAddr>() {
+            Ok(IpAddr::V4(ip)) => ip.is_loopback(),
+            Ok(IpAddr::V6(ip)) => {
+                ip.is_loopback() || ip.to_ipv4_mapped().is_some_and(|v4| v4.is_loopback())
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+impl PortFilter {
+    fn accepts(&self, s: &ListeningSocket) -> bool {
+        if s.port < self.min_port || s.port > self.max_port {
+            return false;
+        }
+        if !self.udp && s.proto.starts_with("udp") {
+            return false;
+        }
+        if self.exclude_loopback && s.is_loopback() {
+            return false;
+        }
+        match &self.process {
+            Some(needle) => [&s.process, &s.cmdline]
+                .iter()
+                .any(|f| f.as_deref().is_some_and(|v| v.contains(needle.as_str()))),
+            None => true,
+        }
+    }
+}
+
+/// Listening ports, without details
+pub fn detect(filter: &PortFilter) -> Result<Vec<u16>> {
+    let mut ports: Vec<u16> = all_listening(filter)?.iter().map(|s| s.port).collect();
+    ports.dedup();
+    if filter.limit > 0 {
+        ports.truncate(filter.limit);
+    }
+    Ok(ports)
+}
+
+/// Listening sockets with their bind address and owning process, where
+/// known. The limit counts ports, so a port bound on several addresses
+/// keeps all of its sockets.
+pub fn listening(filter: &PortFilter) -> Result<Vec<ListeningSocket>> {
+    let mut sockets = all_listening(filter)?;
+    if filter.limit > 0 {
+        keep_ports(&mut sockets, filter.limit);
+    }
+    Ok(sockets)
+}
+
+/// Keep the sockets of the first `limit` ports of a port-sorted list
+fn keep_ports(sockets: &mut Vec<ListeningSocket>, limit: usize) {
+    let mut ports = 0;
+    let mut last = None;
+    sockets.retain(|s| {
+        if last != Some(s.port) {
+            last = Some(s.port);
+            ports += 1;
+        }
+        ports <= limit
+    });
+}
+
+/// Every listening socket that passes `filter`, sorted by port
+fn all_listening(filter: &PortFilter) -> Result<Vec<ListeningSocket>> {
+    let mut sockets;
+
     #[cfg(target_os = "linux")]
     {
-        ports = detect_linux()?;
+        sockets = procfs::listening().unwrap_or_default();
+        if sockets.is_empty() {
+            // /proc/net may be hidden in some containers
+            sockets = detect_linux()?
+                .into_iter()
+                .map(ListeningSocket::port_only)
+                .collect();
+        }
     }
-    
+
     #[cfg(target_os = "macos")]
     {
-        ports = detect_macos()?;
+        sockets = detect_macos()?
+            .into_iter()
+            .map(ListeningSocket::port_only)
+            .collect();
     }
-    
+
     #[cfg(target_os = "windows")]
     {
-        ports = detect_windows()?;
+        sockets = detect_windows()?
+            .into_iter()
+            .map(ListeningSocket::port_only)
+            .collect();
     }
-    
+
     #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
     {
-        ports = vec![];
+        sockets = vec![];
+    }
+
+    sockets.retain(|s| filter.accepts(s));
+    sockets.sort_by(|a, b| {
+        (a.port, a.proto, &a.address).cmp(&(b.port, b.proto, &b.address))
+    });
+    sockets.dedup_by(|a, b| a.port == b.port && a.proto == b.proto && a.address == b.address);
+    Ok(sockets)
+}
+
+/// Socket tables and owners read straight from /proc, so no `ss` or
+/// `netstat` binary is needed
+#[cfg(target_os = "linux")]
+mod procfs {
+    use super::{ListeningSocket, MAX_CMDLINE};
+    use std::collections::HashMap;
+    use std::fs;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    // st column values: TCP_LISTEN, and TCP_CLOSE for unconnected UDP sockets
+    const TCP_LISTEN: &str = "0A";
+    const UDP_UNCONNECTED: &str = "07";
+
+    /// Decode "0100007F:1F90"; the kernel prints each 32-bit word in host order
+    fn parse_addr(field: &str) -> Option<(IpAddr, u16)> {
+        let (addr, port) = field.split_once(':')?;
+        let port = u16::from_str_radix(port, 16).ok()?;
+        if !addr.is_ascii() || addr.len() % 8 != 0 {
+            return None;
+        }
+        let words: Vec<[u8; 4]> = (0..addr.len() / 8)
+            .map(|i| u32::from_str_radix(&addr[i * 8..i * 8 + 8], 16).map(u32::to_ne_bytes))
+            .collect::<Result<_, _>>()
+            .ok()?;
+        let ip = match words.len() {
+            1 => IpAddr::V4(Ipv4Addr::from(words[0])),
+            4 => {
+                let mut bytes = [0u8; 16];
+                for (i, w) in words.iter().enumerate() {
+                    bytes[i * 4..i * 4 + 4].copy_from_slice(w);
+                }
+                IpAddr::V6(Ipv6Addr::from(bytes))
+            }
+            _ => return None,
+        };
+        Some((ip, port))
+    }
+
+    /// Listening entries of one /proc/net table as (socket, inode)
+    fn read_table(proto: &'static str, out: &mut Vec<(ListeningSocket, u64)>) {
+        let Ok(table) = fs::read_to_string(format!("/proc/net/{}", proto)) else {
+            return;
+        };
+        let wanted = if proto.starts_with("tcp") {
+            TCP_LISTEN
+        } else {
+            UDP_UNCONNECTED
+        };
+        for line in table.lines().skip(1) {
+            let cols: Vec<&str> = line.split_whitespace().collect();
+            if cols.len() < 10 || cols[3] != wanted {
+                continue;
+            }
+            let (Some((ip, port)), Ok(inode)) = (parse_addr(cols[1]), cols[9].parse::<u64>())
+            else {
+                continue;
+            };
+            out.push((
+                ListeningSocket {
+                    port,
+                    proto,
+                    address: ip.to_string(),
+                    pid: None,
+                    process: None,
+                    cmdline: None,
+                },
+                inode,
+            ));
+        }
+    }
+
+    /// Map socket inodes to the pid holding them. Only processes we may
+    /// inspect (our own, or all when root) are found.
+    fn socket_owners() -> HashMap<u64, u32> {
+        let mut owners = HashMap::new();
+        let Ok(procs) = fs::read_dir("/proc") else {
+            return owners;
+        };
+        for entry in procs.flatten() {
+            let Some(pid) = entry.file_name().to_str().and_then(|n| n.parse::<u32>().ok())
+            else {
+                continue;
+            };
+            let Ok(fds) = fs::read_dir(entry.path().join("fd")) else {
+                continue;
+            };
+            for fd in fds.flatten() {
+                let Ok(target) = fs::read_link(fd.path()) else {
+                    continue;
+                };
+                let target = target.to_string_lossy();
+                if let Some(inode) = target
+                    .strip_prefix("socket:[")
+                    .and_then(|t| t.strip_suffix(']'))
+                    .and_then(|t| t.parse::<u64>().ok())
+                {
+                    owners.entry(inode).or_insert(pid);
+                }
+            }
+        }
+        owners
+    }
+
+    fn process_info(pid: u32) -> (Option<String>, Option<String>) {
+        let name = fs::read_to_string(format!("/proc/{}/comm", pid))
+            .ok()
+            .map(|n| n.trim().to_string());
+        let cmdline = fs::read(format!("/proc/{}/cmdline", pid))
+            .ok()
+            .map(|raw| {
+                let joined: String = String::from_utf8_lossy(&raw)
+                    .split('\0')
+                    .filter(|a| !a.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                joined.chars().take(MAX_CMDLINE).collect::<String>()
+            })
+            .filter(|c| !c.is_empty());
+        (name, cmdline)
+    }
+
+    pub fn listening() -> Option<Vec<ListeningSocket>> {
+        let mut found = Vec::new();
+        for proto in ["tcp", "tcp6", "udp", "udp6"] {
+            read_table(proto, &mut found);
+        }
+        if found.is_empty() {
+            return None;
+        }
+        let owners = socket_owners();
+        let mut info: HashMap<u32, (Option<String>, Option<String>)> = HashMap::new();
+        Some(
+            found
+                .into_iter()
+                .map(|(mut socket, inode)| {
+                    if let Some(&pid) = owners.get(&inode) {
+                        let (name, cmdline) =
+                            info.entry(pid).or_insert_with(|| process_info(pid)).clone();
+                        socket.pid = Some(pid);
+                        socket.process = name;
+                        socket.cmdline = cmdline;
+                    }
+                    socket
+                })
+                .collect(),
+        )
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        // The kernel prints each word in host order; these fixtures are little-endian
+        #[test]
+        #[cfg(target_endian = "little")]
+        fn parses_proc_net_addresses() {
+            assert_eq!(
+                parse_addr("0100007F:1F90"),
+                Some((IpAddr::V4(Ipv4Addr::LOCALHOST), 8080))
+            );
+            assert_eq!(
+                parse_addr("00000000000000000000000001000000:0050"),
+                Some((IpAddr::V6(Ipv6Addr::LOCALHOST), 80))
+            );
+            assert_eq!(
+                parse_addr("0000000000000000FFFF00000100007F:0016"),
+                Some((IpAddr::V6(Ipv4Addr::LOCALHOST.to_ipv6_mapped()), 22))
+            );
+        }
+
+        #[test]
+        fn rejects_malformed_addresses() {
+            assert_eq!(parse_addr("0100007F"), None);
+            assert_eq!(parse_addr("0100007F:XYZ"), None);
+            assert_eq!(parse_addr("0100ZZ7F:1F90"), None);
+            assert_eq!(parse_addr("0100007F00:1F90"), None);
+            assert_eq!(parse_addr(":1F90"), None);
+            assert_eq!(parse_addr("0100007\u{e9}:1F90"), None);
+        }
     }
-    
-    // Filter to common dev ports range and limit
-    ports.retain(|&p| p > 1024);
-    ports.sort_unstable();
-    ports.dedup();
-    ports.truncate(20);
-    
-    Ok(ports)
 }
 
 #[cfg(target_os = "linux")]
@@ -240,4 +565,30 @@ fn extract_port_from_macos_address(addr: &str) -> Option<u16> {
     }
     
     None
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn socket(port: u16, address: &str) -> ListeningSocket {
+        ListeningSocket {
+            address: address.to_string(),
+            ..ListeningSocket::port_only(port)
+        }
+    }
+
+    #[test]
+    fn limit_counts_ports_not_sockets() {
+        let mut sockets = vec![
+            socket(3000, "0.0.0.0"),
+            socket(3000, "::"),
+            socket(5432, "127.0.0.1"),
+            socket(5432, "10.0.0.2"),
+            socket(8080, "::"),
+        ];
+        keep_ports(&mut sockets, 2);
+        let kept: Vec<u16> = sockets.iter().map(|s| s.port).collect();
+        assert_eq!(kept, vec![3000, 3000, 5432, 5432]);
+    }
+}
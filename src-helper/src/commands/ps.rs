@@ -0,0 +1,318 @@
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::thread;
+use std::time::Duration;
+
+use super::metrics::{clock_ticks, page_size, pids, read_memory, read_proc_stat, ProcStat};
+
+// Longest command line reported per process
+const MAX_CMDLINE: usize = 1024;
+
+#[derive(Debug, Serialize)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub ppid: u32,
+    pub user: String,
+    pub name: String,
+    pub state: char,
+    /// Percentage of one core over the sample window
+    pub cpu: f64,
+    /// Percentage of physical memory
+    pub mem: f64,
+    /// Resident memory in bytes
+    pub rss: u64,
+    /// Seconds since the Unix epoch
+    pub start_time: u64,
+    pub cmdline: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ProcessInfo>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KillResult {
+    pub pid: u32,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// uid -> user name from /etc/passwd
+fn user_names() -> HashMap<u32, String> {
+    fs::read_to_string("/etc/passwd")
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| {
+            let mut cols = l.split(':');
+            let name = cols.next()?;
+            let uid = cols.nth(1)?.parse().ok()?;
+            Some((uid, name.to_string()))
+        })
+        .collect()
+}
+
+fn process_uid(pid: u32) -> Option<u32> {
+    let status = fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    status
+        .lines()
+        .find_map(|l| l.strip_prefix("Uid:"))
+        .and_then(|v| v.split_whitespace().next()?.parse().ok())
+}
+
+fn cmdline(pid: u32) -> String {
+    let raw = fs::read(format!("/proc/{}/cmdline", pid)).unwrap_or_default();
+    let joined = String::from_utf8_lossy(&raw)
+        .split('\0')
+        .filter(|a| !a.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    joined.chars().take(MAX_CMDLINE).collect()
+}
+
+fn boot_time() -> u64 {
+    fs::read_to_string("/proc/stat")
+        .unwrap_or_default()
+        .lines()
+        .find_map(|l| l.strip_prefix("btime "))
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+fn snapshot() -> HashMap<u32, ProcStat> {
+    pids()
+        .into_iter()
+        .filter_map(read_proc_stat)
+        .map(|p| (p.pid, p))
+        .collect()
+}
+
+/// Nest each process under its parent. Processes whose parent is not in
+/// `procs` (pid 1, kernel threads, or filtered out) become roots.
+fn build_tree(procs: Vec<ProcessInfo>) -> Vec<ProcessInfo> {
+    let present: HashSet<u32> = procs.iter().map(|p| p.pid).collect();
+    let mut by_parent: HashMap<u32, Vec<ProcessInfo>> = HashMap::new();
+    let mut roots = Vec::new();
+    for p in procs {
+        if p.ppid != p.pid && present.contains(&p.ppid) {
+            by_parent.entry(p.ppid).or_default().push(p);
+        } else {
+            roots.push(p);
+        }
+    }
+    fn attach(node: &mut ProcessInfo, by_parent: &mut HashMap<u32, Vec<ProcessInfo>>) {
+        if let Some(mut children) = by_parent.remove(&node.pid) {
+            for child in &mut children {
+                attach(child, by_parent);
+            }
+            node.children = children;
+        }
+    }
+    for root in &mut roots {
+        attach(root, &mut by_parent);
+    }
+    roots
+}
+
+/// Keep processes whose name or command line contains `needle`, and with
+/// `ancestors` their parents up to the root
+fn retain_matching(procs: &mut Vec<ProcessInfo>, needle: &str, ancestors: bool) {
+    let parents: HashMap<u32, u32> = procs.iter().map(|p| (p.pid, p.ppid)).collect();
+    let mut keep = HashSet::new();
+    for p in procs
+        .iter()
+        .filter(|p| p.name.contains(needle) || p.cmdline.contains(needle))
+    {
+        keep.insert(p.pid);
+        if ancestors {
+            let mut cur = p.ppid;
+            while cur != 0 && keep.insert(cur) {
+                cur = parents.get(&cur).copied().unwrap_or(0);
+            }
+        }
+    }
+    procs.retain(|p| keep.contains(&p.pid));
+}
+
+/// List processes: a tree by default, or a flat list sorted by pid.
+/// `filter` keeps processes whose name or command line contains it, plus
+/// their ancestors so the tree stays connected.
+pub fn list(filter: Option<&str>, flat: bool, sample: Duration) -> Result<Vec<ProcessInfo>> {
+    if !cfg!(target_os = "linux") {
+        bail!("process listing is only available on Linux");
+    }
+    let before = snapshot();
+    let elapsed = if sample.is_zero() {
+        None
+    } else {
+        thread::sleep(sample);
+        Some(sample.as_secs_f64())
+    };
+    let after = snapshot();
+
+    let ticks = clock_ticks();
+    let page = page_size();
+    let btime = boot_time();
+    let uptime = fs::read_to_string("/proc/uptime")
+        .ok()
+        .and_then(|u| u.split_whitespace().next()?.parse::<f64>().ok())
+        .unwrap_or(0.0);
+    let mem_total = read_memory().total.max(1);
+    let users = user_names();
+
+    let mut procs: Vec<ProcessInfo> = after
+        .values()
+        .map(|p| {
+            let cpu = match elapsed {
+                Some(secs) => {
+                    let prev = before.get(&p.pid).map(|b| b.cpu_ticks).unwrap_or(0);
+                    p.cpu_ticks.saturating_sub(prev) as f64 / ticks / secs
+                }
+                // Average over the process lifetime, like `ps`
+                None => {
+                    let age = uptime - p.start_ticks as f64 / ticks;
+                    if age > 0.0 {
+                        p.cpu_ticks as f64 / ticks / age
+                    } else {
+                        0.0
+                    }
+                }
+            };
+            let rss = p.rss_pages * page;
+            let uid = process_uid(p.pid);
+            ProcessInfo {
+                pid: p.pid,
+                ppid: p.ppid,
+                user: uid
+                    .and_then(|u| users.get(&u).cloned())
+                    .or_else(|| uid.map(|u| u.to_string()))
+                    .unwrap_or_default(),
+                name: p.name.clone(),
+                state: p.state,
+                cpu: (cpu * 1000.0).round() / 10.0,
+                mem: (rss as f64 * 1000.0 / mem_total as f64).round() / 10.0,
+                rss,
+                start_time: btime + (p.start_ticks as f64 / ticks) as u64,
+                cmdline: cmdline(p.pid),
+                children: Vec::new(),
+            }
+        })
+        .collect();
+    procs.sort_by_key(|p| p.pid);
+
+    if let Some(needle) = filter.filter(|f| !f.is_empty()) {
+        retain_matching(&mut procs, needle, !flat);
+    }
+
+    Ok(if flat { procs } else { build_tree(procs) })
+}
+
+#[cfg(unix)]
+fn parse_signal(signal: &str) -> Result<libc::c_int> {
+    if let Ok(n) = signal.parse::<libc::c_int>() {
+        return Ok(n);
+    }
+    let name = signal.to_ascii_uppercase();
+    Ok(match name.strip_prefix("SIG").unwrap_or(&name) {
+        "HUP" => libc::SIGHUP,
+        "INT" => libc::SIGINT,
+        "QUIT" => libc::SIGQUIT,
+        "KILL" => libc::SIGKILL,
+        "TERM" => libc::SIGTERM,
+        "USR1" => libc::SIGUSR1,
+        "USR2" => libc::SIGUSR2,
+        "STOP" => libc::SIGSTOP,
+        "CONT" => libc::SIGCONT,
+        _ => bail!("unknown signal '{}'", signal),
+    })
+}
+
+/// Send `signal` to each pid and report the outcome per pid
+#[cfg(unix)]
+pub fn kill(pids: &[u32], signal: &str) -> Result<Vec<KillResult>> {
+    let sig = parse_signal(signal)?;
+    Ok(pids
+        .iter()
+        .map(|&pid| {
+            // 0 and negative pids address whole process groups
+            if pid == 0 || pid > libc::pid_t::MAX as u32 {
+                return KillResult {
+                    pid,
+                    ok: false,
+                    error: Some("invalid pid".into()),
+                };
+            }
+            // SAFETY: kill only sends a signal; a bad pid is reported through errno
+            let rc = unsafe { libc::kill(pid as libc::pid_t, sig) };
+            KillResult {
+                pid,
+                ok: rc == 0,
+                error: (rc != 0).then(|| std::io::Error::last_os_error().to_string()),
+            }
+        })
+        .collect())
+}
+
+#[cfg(not(unix))]
+pub fn kill(_pids: &[u32], _signal: &str) -> Result<Vec<KillResult>> {
+    bail!("sending signals is not supported on this platform");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, ppid: u32, name: &str) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            ppid,
+            user: "root".into(),
+            name: name.into(),
+            state: 'S',
+            cpu: 0.0,
+            mem: 0.0,
+            rss: 0,
+            start_time: 0,
+            cmdline: name.into(),
+            children: Vec::new(),
+        }
+    }
+
+    fn shape(nodes: &[ProcessInfo]) -> Vec<(u32, Vec<u32>)> {
+        nodes
+            .iter()
+            .map(|n| (n.pid, n.children.iter().map(|c| c.pid).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn tree_with_a_missing_middle_node() {
+        // init -> sshd -> bash -> vim, with sshd filtered out
+        let procs = vec![
+            process(1, 0, "init"),
+            process(30, 20, "bash"),
+            process(40, 30, "vim"),
+            process(50, 1, "cron"),
+        ];
+        let tree = build_tree(procs);
+        assert_eq!(shape(&tree), vec![(1, vec![50]), (30, vec![40])]);
+
+        // Filtering for vim keeps its ancestors, or only vim when flat
+        let all = || {
+            vec![
+                process(1, 0, "init"),
+                process(20, 1, "sshd"),
+                process(30, 20, "bash"),
+                process(40, 30, "vim"),
+                process(50, 1, "cron"),
+            ]
+        };
+        let mut procs = all();
+        retain_matching(&mut procs, "vim", true);
+        let pids: Vec<u32> = procs.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![1, 20, 30, 40]);
+        let mut procs = all();
+        retain_matching(&mut procs, "vim", false);
+        assert_eq!(procs.len(), 1);
+    }
+}
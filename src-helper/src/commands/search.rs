@@ -0,0 +1,235 @@
+use anyhow::{bail, Context, Result};
+use globset::{GlobBuilder, GlobMatcher};
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use super::git::expand_tilde;
+use super::output::Output;
+
+// Pseudo filesystems that are slow or endless to walk
+const SKIP_DIRS: &[&str] = &["/proc", "/sys", "/dev"];
+// Longest line text reported for a content match
+const MAX_TEXT: usize = 300;
+
+pub struct SearchOptions {
+    pub name: Option<String>,
+    pub content: Option<String>,
+    pub regex: bool,
+    pub ignore_case: bool,
+    pub hidden: bool,
+    pub max_results: usize,
+    pub max_file_size: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchMatch {
+    pub path: String,
+    #[serde(rename = "type")]
+    pub file_type: &'static str,
+    pub size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+struct Searcher<'a> {
+    out: &'a mut dyn Output,
+    // Set once the reader has gone away
+    stopped: bool,
+    name: Option<GlobMatcher>,
+    // Name patterns containing `/` are matched against the path below the root
+    name_is_path: bool,
+    content: Option<Regex>,
+    hidden: bool,
+    max_results: usize,
+    max_file_size: u64,
+    found: usize,
+    scanned: u64,
+}
+
+impl Searcher<'_> {
+    fn full(&self) -> bool {
+        self.found >= self.max_results
+    }
+
+    fn done(&self) -> bool {
+        self.stopped || self.full()
+    }
+
+    fn report(&mut self, m: SearchMatch) {
+        if self.done() {
+            return;
+        }
+        if let Ok(line) = serde_json::to_string(&m) {
+            self.stopped = self.out.line(&line).is_err();
+        }
+        self.found += 1;
+    }
+
+    fn name_matches(&self, root: &Path, path: &Path) -> bool {
+        match &self.name {
+            None => true,
+            Some(glob) if self.name_is_path => {
+                glob.is_match(path.strip_prefix(root).unwrap_or(path))
+            }
+            Some(glob) => path.file_name().is_some_and(|n| glob.is_match(n)),
+        }
+    }
+
+    fn grep(&mut self, path: &Path, size: u64) {
+        let Some(re) = self.content.clone() else {
+            return;
+        };
+        if size > self.max_file_size {
+            return;
+        }
+        let mut data = Vec::new();
+        if fs::File::open(path)
+            .and_then(|mut f| f.read_to_end(&mut data))
+            .is_err()
+        {
+            return;
+        }
+        // Skip binary files
+        if data.iter().take(8192).any(|b| *b == 0) {
+            return;
+        }
+        let text = String::from_utf8_lossy(&data);
+        for (idx, line) in text.lines().enumerate() {
+            if self.done() {
+                return;
+            }
+            if re.is_match(line) {
+                let mut shown: String = line.chars().take(MAX_TEXT).collect();
+                if shown.len() < line.len() {
+                    shown.push('…');
+                }
+                self.report(SearchMatch {
+                    path: path.to_string_lossy().to_string(),
+                    file_type: "file",
+                    size,
+                    line: Some(idx as u64 + 1),
+                    text: Some(shown),
+                });
+            }
+        }
+    }
+
+    fn walk(&mut self, root: &Path, dir: &Path) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        let mut entries: Vec<_> = entries.filter_map(|e| e.ok()).collect();
+        entries.sort_by_key(|e| e.file_name());
+        for entry in entries {
+            if self.done() {
+                return;
+            }
+            let path = entry.path();
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            if hidden && !self.hidden {
+                continue;
+            }
+            let Ok(ft) = entry.file_type() else { continue };
+            self.scanned += 1;
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            let matched = self.name_matches(root, &path);
+
+            if ft.is_dir() {
+                if matched && self.content.is_none() && self.name.is_some() {
+                    self.report(SearchMatch {
+                        path: path.to_string_lossy().to_string(),
+                        file_type: "dir",
+                        size: 0,
+                        line: None,
+                        text: None,
+                    });
+                }
+                if !SKIP_DIRS.iter().any(|s| path == Path::new(s)) {
+                    self.walk(root, &path);
+                }
+            } else if matched && (ft.is_file() || ft.is_symlink()) {
+                if self.content.is_some() {
+                    if ft.is_file() {
+                        self.grep(&path, size);
+                    }
+                } else {
+                    self.report(SearchMatch {
+                        path: path.to_string_lossy().to_string(),
+                        file_type: if ft.is_symlink() { "symlink" } else { "file" },
+                        size,
+                        line: None,
+                        text: None,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Walk `root` and print one JSON match per line, then a final
+/// `{"done":true,...}` line so callers can stream results as they arrive.
+pub fn run(root: &str, opts: SearchOptions, out: &mut dyn Output) -> Result<()> {
+    if opts.name.is_none() && opts.content.is_none() {
+        bail!("either a name pattern or a content pattern is required");
+    }
+    let root = expand_tilde(root);
+    let root_path = Path::new(&root);
+    if !root_path.is_dir() {
+        bail!("{} is not a directory", root);
+    }
+
+    let name = opts
+        .name
+        .as_deref()
+        .map(|p| {
+            GlobBuilder::new(p)
+                .case_insensitive(opts.ignore_case)
+                .literal_separator(p.contains('/'))
+                .build()
+                .map(|g| g.compile_matcher())
+                .with_context(|| format!("invalid name pattern '{}'", p))
+        })
+        .transpose()?;
+    let content = opts
+        .content
+        .as_deref()
+        .map(|p| {
+            let pattern = if opts.regex {
+                p.to_string()
+            } else {
+                regex::escape(p)
+            };
+            RegexBuilder::new(&pattern)
+                .case_insensitive(opts.ignore_case)
+                .build()
+                .with_context(|| format!("invalid content pattern '{}'", p))
+        })
+        .transpose()?;
+
+    let mut searcher = Searcher {
+        out,
+        stopped: false,
+        name_is_path: opts.name.as_deref().is_some_and(|p| p.contains('/')),
+        name,
+        content,
+        hidden: opts.hidden,
+        max_results: opts.max_results,
+        max_file_size: opts.max_file_size,
+        found: 0,
+        scanned: 0,
+    };
+    searcher.walk(root_path, root_path);
+    let summary = serde_json::json!({
+        "done": true,
+        "truncated": searcher.full(),
+        "scanned": searcher.scanned
+    });
+    // Nobody is left to read the summary when the reader went away
+    let _ = searcher.out.line(&summary.to_string());
+    Ok(())
+}
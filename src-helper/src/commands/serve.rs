@@ -0,0 +1,202 @@
+use anyhow::Result;
+use clap::Parser;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use super::output::Output;
+use crate::version::{HELPER_VERSION, PROTOCOL_VERSION};
+use crate::{execute, Cli, Reply};
+
+// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const REQUEST_FAILED: i64 = -32000;
+const REQUEST_CANCELLED: i64 = -32800;
+
+/// Cancel flags of the requests still running, by request id
+type Running = Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>;
+
+/// Write one message per line. Holding the stdout lock for the whole line
+/// keeps messages from concurrent requests from interleaving.
+fn send(message: &Value) -> std::io::Result<()> {
+    let mut out = std::io::stdout().lock();
+    writeln!(out, "{}", message)?;
+    out.flush()
+}
+
+fn response(id: &Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn error(id: &Value, code: i64, message: impl Into<String>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message.into() }
+    })
+}
+
+/// Sends each line a subcommand streams as a `stream` notification tagged
+/// with the request id
+struct RequestOutput {
+    id: Value,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Output for RequestOutput {
+    fn line(&mut self, line: &str) -> std::io::Result<()> {
+        if self.closed() {
+            return Err(std::io::ErrorKind::Interrupted.into());
+        }
+        let data = serde_json::from_str(line).unwrap_or_else(|_| Value::String(line.into()));
+        send(&json!({
+            "jsonrpc": "2.0",
+            "method": "stream",
+            "params": { "id": self.id, "data": data }
+        }))
+    }
+
+    fn closed(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Positional params are the subcommand's command-line arguments
+fn params_to_args(params: &Value) -> Option<Vec<String>> {
+    match params {
+        Value::Null => Some(Vec::new()),
+        Value::Array(items) => items
+            .iter()
+            .map(|v| match v {
+                Value::String(s) => Some(s.clone()),
+                Value::Number(n) => Some(n.to_string()),
+                Value::Bool(b) => Some(b.to_string()),
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+/// Run the subcommand named by `method` and build its response
+fn handle(id: &Value, method: &str, params: &Value, cancelled: Arc<AtomicBool>) -> Value {
+    let Some(args) = params_to_args(params) else {
+        return error(id, INVALID_PARAMS, "params must be an array of arguments");
+    };
+    if method == "serve" {
+        return error(id, METHOD_NOT_FOUND, "unknown method 'serve'");
+    }
+    let argv = ["jaterm-agent", method]
+        .into_iter()
+        .map(String::from)
+        .chain(args);
+    let cli = match Cli::try_parse_from(argv) {
+        Ok(cli) => cli,
+        Err(e) => {
+            let code = match e.kind() {
+                clap::error::ErrorKind::InvalidSubcommand => METHOD_NOT_FOUND,
+                _ => INVALID_PARAMS,
+            };
+            // clap appends usage hints meant for a terminal
+            let message = e.to_string();
+            let summary: Vec<&str> = message
+                .lines()
+                .take_while(|l| !l.starts_with("Usage:"))
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .collect();
+            return error(id, code, summary.join(" ").trim_start_matches("error: "));
+        }
+    };
+
+    let mut out = RequestOutput {
+        id: id.clone(),
+        cancelled,
+    };
+    match execute(cli.command, &mut out) {
+        _ if out.closed() => error(id, REQUEST_CANCELLED, "cancelled"),
+        Ok(Reply::Json(value)) => response(id, value),
+        Ok(Reply::Text(text)) => response(id, Value::String(text)),
+        Ok(Reply::Nothing) => response(id, Value::Null),
+        Err(e) => error(id, REQUEST_FAILED, format!("{:#}", e)),
+    }
+}
+
+/// Answer JSON-RPC 2.0 requests, one JSON object per line on stdin, until
+/// stdin closes. Methods are the subcommand names and positional params
+/// their arguments; every request runs on its own thread. Streaming
+/// subcommands send their lines as `stream` notifications before the final
+/// response, and a `$/cancel` notification with `{"id": ...}` stops one.
+/// The first line out is a `ready` notification carrying the protocol
+/// version.
+pub fn run() -> Result<()> {
+    let running: Running = Default::default();
+    send(&json!({
+        "jsonrpc": "2.0",
+        "method": "ready",
+        "params": { "protocol": PROTOCOL_VERSION, "version": HELPER_VERSION }
+    }))?;
+
+    for line in std::io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let message: Value = match serde_json::from_str(&line) {
+            Ok(m) => m,
+            Err(e) => {
+                send(&error(&Value::Null, PARSE_ERROR, e.to_string()))?;
+                continue;
+            }
+        };
+        let method = message["method"].as_str().map(String::from);
+        match (method, message.get("id").cloned()) {
+            (Some(method), None) if method == "$/cancel" => {
+                let target = message["params"]["id"].to_string();
+                if let Some(flag) = running.lock().unwrap().get(&target) {
+                    flag.store(true, Ordering::Relaxed);
+                }
+            }
+            // Other notifications need no answer
+            (Some(_), None) => {}
+            (Some(method), Some(id)) => {
+                let cancelled = Arc::new(AtomicBool::new(false));
+                let key = id.to_string();
+                running
+                    .lock()
+                    .unwrap()
+                    .insert(key.clone(), cancelled.clone());
+                let running = running.clone();
+                thread::spawn(move || {
+                    // Every request gets an answer, or its caller would wait forever
+                    let reply = catch_unwind(AssertUnwindSafe(|| {
+                        handle(&id, &method, &message["params"], cancelled)
+                    }))
+                    .unwrap_or_else(|_| error(&id, REQUEST_FAILED, "helper panicked"));
+                    running.lock().unwrap().remove(&key);
+                    let _ = send(&reply);
+                });
+            }
+            (None, id) => {
+                send(&error(
+                    &id.unwrap_or(Value::Null),
+                    INVALID_REQUEST,
+                    "missing method",
+                ))?;
+            }
+        }
+    }
+
+    // The app went away; stop whatever is still streaming
+    for flag in running.lock().unwrap().values() {
+        flag.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
@@ -0,0 +1,277 @@
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::env::{osc7_installed, rc_files, OSC7_MARKER};
+
+// The same blocks the app writes into local rc files (src-tauri shell.rs)
+const ZSH_SNIPPET: &str = r#"
+# >>> jaterm cwd tracking >>>
+autoload -Uz add-zsh-hook
+function _jaterm_osc7_precmd() { print -Pn '\e]7;file://%m%d\e\\' }
+function _jaterm_osc7_chpwd()  { print -Pn '\e]7;file://%m%d\e\\' }
+add-zsh-hook precmd _jaterm_osc7_precmd
+add-zsh-hook chpwd  _jaterm_osc7_chpwd
+# <<< jaterm cwd tracking <<<
+"#;
+
+const BASH_SNIPPET: &str = r#"
+# >>> jaterm cwd tracking >>>
+__jaterm_osc7() { printf '\033]7;file://%s%s\007' "$(hostname)" "$PWD"; }
+case ":$PROMPT_COMMAND:" in
+  *:"__jaterm_osc7":*) ;;
+  *) PROMPT_COMMAND="__jaterm_osc7;${PROMPT_COMMAND}";;
+esac
+# <<< jaterm cwd tracking <<<
+"#;
+
+const FISH_SNIPPET: &str = r#"
+# >>> jaterm cwd tracking >>>
+function __jaterm_osc7 --on-event fish_prompt
+  printf '\e]7;file://%s%s\e\\' (hostname) $PWD
+end
+# <<< jaterm cwd tracking <<<
+"#;
+
+const SHELLS: &[&str] = &["bash", "zsh", "fish"];
+
+#[derive(Debug, Serialize)]
+pub struct IntegrationResult {
+    pub shell: String,
+    /// The rc file written, or that would have been
+    pub path: String,
+    /// "installed", "updated", "unchanged", "removed" or "absent"
+    pub status: &'static str,
+}
+
+fn begin_marker() -> String {
+    format!("# >>> {} >>>", OSC7_MARKER)
+}
+
+fn end_marker() -> String {
+    format!("# <<< {} <<<", OSC7_MARKER)
+}
+
+fn snippet(shell: &str) -> Option<&'static str> {
+    match shell {
+        "zsh" => Some(ZSH_SNIPPET),
+        "bash" => Some(BASH_SNIPPET),
+        "fish" => Some(FISH_SNIPPET),
+        _ => None,
+    }
+}
+
+/// The shells to act on: those asked for, or else the login shell
+fn target_shells(shells: &[String]) -> Result<Vec<String>> {
+    let shells = if shells.is_empty() {
+        let login = std::env::var("SHELL").unwrap_or_default();
+        let name = login.rsplit('/').next().unwrap_or_default().to_string();
+        if name.is_empty() {
+            bail!("no login shell found; pass --shell");
+        }
+        vec![name]
+    } else {
+        shells.to_vec()
+    };
+    for shell in &shells {
+        if !SHELLS.contains(&shell.as_str()) {
+            bail!("unsupported shell '{}' (bash, zsh or fish)", shell);
+        }
+    }
+    Ok(shells)
+}
+
+/// Byte range of the marker block in `content`, markers included
+fn block_span(content: &str) -> Option<(usize, usize)> {
+    let (begin, end) = (begin_marker(), end_marker());
+    let start = content.find(&begin)?;
+    let stop = content[start..].find(&end)? + start + end.len();
+    Some((start, stop))
+}
+
+/// `content` with the marker block swapped for `block` where it stands, or
+/// None when it has no complete block
+fn replace_block(content: &str, block: &str) -> Option<String> {
+    let (start, stop) = block_span(content)?;
+    Some(format!(
+        "{}{}{}",
+        &content[..start],
+        block.trim(),
+        &content[stop..]
+    ))
+}
+
+/// `content` with `block` added at the end, after a blank line
+fn append_block(content: &str, block: &str) -> String {
+    if content.trim().is_empty() {
+        block.trim_start().to_string()
+    } else {
+        format!("{}\n{}", content.trim_end(), block)
+    }
+}
+
+/// `content` with the marker block cut out, or None when it has no
+/// complete block
+fn remove_block(content: &str) -> Option<String> {
+    let (start, stop) = block_span(content)?;
+    let before = content[..start].trim_end_matches('\n');
+    let after = content[stop..].trim_start_matches('\n');
+    Some(match (before.is_empty(), after.is_empty()) {
+        (true, _) => after.to_string(),
+        (false, true) => format!("{}\n", before),
+        (false, false) => format!("{}\n\n{}", before, after),
+    })
+}
+
+fn display(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+/// Add (or refresh) the cwd tracking block in one shell's rc file
+fn install_one(shell: &str) -> Result<IntegrationResult> {
+    let block = snippet(shell).expect("checked shell");
+    let candidates = rc_files(shell);
+    let Some(path) = candidates
+        .iter()
+        .find(|p| p.exists())
+        .or(candidates.first())
+        .cloned()
+    else {
+        bail!("no home directory");
+    };
+    let existing = fs::read_to_string(&path).unwrap_or_default();
+
+    let (content, status) = if existing.contains(OSC7_MARKER) {
+        let Some(content) = replace_block(&existing, block) else {
+            bail!("{} has an incomplete jaterm block", display(&path));
+        };
+        if content == existing {
+            return Ok(IntegrationResult {
+                shell: shell.to_string(),
+                path: display(&path),
+                status: "unchanged",
+            });
+        }
+        (content, "updated")
+    } else {
+        if path.exists() {
+            let backup = PathBuf::from(format!("{}.jaterm.bak", display(&path)));
+            fs::copy(&path, &backup).with_context(|| format!("backup {}", display(&backup)))?;
+        } else if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        (append_block(&existing, block), "installed")
+    };
+    fs::write(&path, content).with_context(|| format!("write {}", display(&path)))?;
+    Ok(IntegrationResult {
+        shell: shell.to_string(),
+        path: display(&path),
+        status,
+    })
+}
+
+/// Drop the cwd tracking block from every rc file of one shell
+fn uninstall_one(shell: &str) -> Result<IntegrationResult> {
+    let candidates = rc_files(shell);
+    let mut result = IntegrationResult {
+        shell: shell.to_string(),
+        path: candidates.first().map(|p| display(p)).unwrap_or_default(),
+        status: "absent",
+    };
+    for path in candidates {
+        let Ok(existing) = fs::read_to_string(&path) else {
+            continue;
+        };
+        if let Some(content) = remove_block(&existing) {
+            fs::write(&path, content).with_context(|| format!("write {}", display(&path)))?;
+            result.path = display(&path);
+            result.status = "removed";
+        }
+    }
+    Ok(result)
+}
+
+/// Install cwd tracking (OSC 7) for `shells`, or the login shell when empty.
+/// Running it again refreshes the block in place.
+pub fn install(shells: &[String]) -> Result<Vec<IntegrationResult>> {
+    target_shells(shells)?
+        .iter()
+        .map(|s| install_one(s))
+        .collect()
+}
+
+/// Remove cwd tracking from `shells`, or from every shell that has it when
+/// empty
+pub fn uninstall(shells: &[String]) -> Result<Vec<IntegrationResult>> {
+    let shells: Vec<String> = if shells.is_empty() {
+        SHELLS
+            .iter()
+            .filter(|s| osc7_installed(s))
+            .map(|s| s.to_string())
+            .collect()
+    } else {
+        target_shells(shells)?
+    };
+    shells.iter().map(|s| uninstall_one(s)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn old_block() -> String {
+        format!("{}\nold line\n{}", begin_marker(), end_marker())
+    }
+
+    #[test]
+    fn refresh_keeps_the_block_in_place() {
+        let rc = format!("export A=1\n\n{}\n\nalias ll='ls -l'\n", old_block());
+        let updated = replace_block(&rc, BASH_SNIPPET).unwrap();
+        assert_eq!(
+            updated,
+            format!(
+                "export A=1\n\n{}\n\nalias ll='ls -l'\n",
+                BASH_SNIPPET.trim()
+            )
+        );
+    }
+
+    #[test]
+    fn second_install_is_unchanged() {
+        for existing in ["", "export A=1\n"] {
+            let installed = append_block(existing, ZSH_SNIPPET);
+            assert!(installed.starts_with(existing.trim_end()));
+            assert_eq!(replace_block(&installed, ZSH_SNIPPET).unwrap(), installed);
+        }
+    }
+
+    #[test]
+    fn removes_the_block_wherever_it_is() {
+        let block = old_block();
+        assert_eq!(
+            remove_block(&format!("{}\n\nexport A=1\n", block)).unwrap(),
+            "export A=1\n"
+        );
+        assert_eq!(
+            remove_block(&format!("export A=1\n\n{}\n\nexport B=2\n", block)).unwrap(),
+            "export A=1\n\nexport B=2\n"
+        );
+        assert_eq!(
+            remove_block(&format!("export A=1\n\n{}\n", block)).unwrap(),
+            "export A=1\n"
+        );
+        assert_eq!(remove_block(&block).unwrap(), "");
+    }
+
+    #[test]
+    fn incomplete_block_is_left_alone() {
+        let rc = format!("export A=1\n{}\nold line\n", begin_marker());
+        assert_eq!(block_span(&rc), None);
+        assert_eq!(replace_block(&rc, FISH_SNIPPET), None);
+        assert_eq!(remove_block(&rc), None);
+        // An end marker before the begin marker does not close it
+        let reversed = format!("{}\n{}\n", end_marker(), begin_marker());
+        assert_eq!(block_span(&reversed), None);
+    }
+}
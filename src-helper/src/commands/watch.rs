@@ -0,0 +1,101 @@
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::git::{self, expand_tilde};
+use super::output::Output;
+use super::ports;
+use crate::version::HELPER_VERSION;
+
+pub struct WatchOptions {
+    /// Poll interval for git status and cwd
+    pub interval: Duration,
+    /// Poll interval for listening ports (scanning sockets is more expensive)
+    pub ports_interval: Duration,
+    /// Follow the working directory of this process instead of a fixed dir
+    pub pid: Option<u32>,
+    pub ports: ports::PortFilter,
+}
+
+/// Last value sent for each event type, so only changes are emitted
+#[derive(Default)]
+struct Sent {
+    cwd: Option<String>,
+    git: Option<Value>,
+    ports: Option<Value>,
+}
+
+/// Write one event line. Fails once the reader has gone away, which is how
+/// the loop learns that the SSH channel was closed.
+fn emit(out: &mut dyn Output, event: Value) -> std::io::Result<()> {
+    out.line(&event.to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn process_cwd(pid: u32) -> Option<String> {
+    std::fs::read_link(format!("/proc/{}/cwd", pid))
+        .ok()
+        .map(|p| p.to_string_lossy().to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn process_cwd(_pid: u32) -> Option<String> {
+    None
+}
+
+/// Poll git status, listening ports and the working directory, printing a
+/// JSON event per line whenever one of them changes. Runs until stdout is
+/// closed or the followed process exits.
+pub fn run(dir: &str, opts: WatchOptions, out: &mut dyn Output) -> Result<()> {
+    let fixed_dir = expand_tilde(dir);
+    let mut sent = Sent::default();
+    let mut last_ports: Option<Instant> = None;
+
+    emit(out, json!({ "type": "hello", "version": HELPER_VERSION }))?;
+    loop {
+        let cwd = match opts.pid {
+            Some(pid) => match process_cwd(pid) {
+                Some(cwd) => cwd,
+                None => {
+                    emit(out, json!({ "type": "exit", "reason": "process gone", "pid": pid }))?;
+                    return Ok(());
+                }
+            },
+            None => fixed_dir.clone(),
+        };
+        if sent.cwd.as_deref() != Some(cwd.as_str()) {
+            emit(out, json!({ "type": "cwd", "cwd": cwd }))?;
+            sent.cwd = Some(cwd.clone());
+            // Force a fresh git event for the new directory
+            sent.git = None;
+        }
+
+        // Repositories mid-creation (no HEAD yet) fail; report them on a later poll
+        if let Ok(status) = git::status(&cwd) {
+            let status = serde_json::to_value(status)?;
+            if sent.git.as_ref() != Some(&status) {
+                emit(out, json!({ "type": "git", "cwd": cwd, "status": status }))?;
+                sent.git = Some(status);
+            }
+        }
+
+        if last_ports.is_none_or(|t| t.elapsed() >= opts.ports_interval) {
+            last_ports = Some(Instant::now());
+            if let Ok(sockets) = ports::listening(&opts.ports) {
+                let mut numbers: Vec<u16> = sockets.iter().map(|s| s.port).collect();
+                numbers.dedup();
+                let found = serde_json::to_value(&sockets)?;
+                if sent.ports.as_ref() != Some(&found) {
+                    emit(out, json!({ "type": "ports", "ports": numbers, "sockets": found }))?;
+                    sent.ports = Some(found);
+                }
+            }
+        }
+
+        thread::sleep(opts.interval);
+        if out.closed() {
+            return Ok(());
+        }
+    }
+}
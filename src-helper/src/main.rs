@@ -3,7 +3,10 @@ use clap::{Parser, Subcommand};
 
 mod commands;
 mod version;
-use commands::{git, ports};
+use commands::output::{Output, Stdout};
+use commands::{
+    containers, env, git, logs, metrics, ports, ps, search, serve, shell_integration, watch,
+};
 use version::HELPER_VERSION;
 
 /// Detect the operating system
@@ -51,6 +54,9 @@ struct Cli {
 enum Commands {
     /// Check health and version
     Health,
+
+    /// Report OS, distro, kernel, shell, package managers and tools as JSON
+    Env,
     
     /// Get git repository status
     #[command(name = "git-status")]
@@ -161,9 +167,210 @@ enum Commands {
         dir: String,
     },
     
+    /// Print one page of commit history with refs and graph lanes as JSON
+    #[command(name = "git-log")]
+    GitLog {
+        /// Directory inside the repository
+        #[arg(default_value = ".")]
+        dir: String,
+        /// Commits to skip, for paging
+        #[arg(long, default_value_t = 0)]
+        skip: usize,
+        /// Commits per page
+        #[arg(long, default_value_t = 200)]
+        limit: usize,
+        /// Revision to start from (defaults to HEAD)
+        #[arg(long)]
+        rev: Option<String>,
+        /// Start from every branch, remote branch and tag
+        #[arg(long)]
+        all: bool,
+        /// Only commits touching this path (relative to the repository root)
+        #[arg(long)]
+        path: Option<String>,
+        /// Only commits whose author name or email contains this
+        #[arg(long)]
+        author: Option<String>,
+        /// Only commits whose message contains this
+        #[arg(long)]
+        message: Option<String>,
+    },
+
+    /// List branches with upstream, ahead/behind and last commit as JSON
+    #[command(name = "git-branches")]
+    GitBranches {
+        /// Directory inside the repository
+        #[arg(default_value = ".")]
+        dir: String,
+        /// Leave out remote branches
+        #[arg(long)]
+        no_remotes: bool,
+    },
+
+    /// Create a branch without switching to it
+    #[command(name = "git-branch-create")]
+    GitBranchCreate {
+        dir: String,
+        name: String,
+        /// Commit or branch to start from (defaults to HEAD)
+        #[arg(long)]
+        start: Option<String>,
+    },
+
+    /// Switch branches; prints the result, with files in the way, as JSON
+    #[command(name = "git-checkout")]
+    GitCheckout {
+        dir: String,
+        /// Local branch, remote branch, or a branch name one remote has
+        branch: String,
+    },
+
+    /// Delete a local branch
+    #[command(name = "git-branch-delete")]
+    GitBranchDelete {
+        dir: String,
+        name: String,
+        /// Delete even when not merged into HEAD
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Rename a local branch
+    #[command(name = "git-branch-rename")]
+    GitBranchRename {
+        dir: String,
+        name: String,
+        new_name: String,
+        /// Replace an existing branch named NEW_NAME
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Set the upstream of a local branch (unset when UPSTREAM is omitted)
+    #[command(name = "git-branch-set-upstream")]
+    GitBranchSetUpstream {
+        dir: String,
+        name: String,
+        /// Remote branch to track, e.g. origin/main
+        upstream: Option<String>,
+    },
+
+    /// Print changes as files, hunks and lines (JSON)
+    #[command(name = "git-diff-files")]
+    GitDiffFiles {
+        /// Directory inside the repository
+        dir: String,
+        /// Only these paths (relative to the repository root)
+        paths: Vec<String>,
+        /// Compare HEAD with the index instead of the index with the working tree
+        #[arg(long)]
+        staged: bool,
+        /// Lines of context around hunks
+        #[arg(long)]
+        context: Option<u32>,
+    },
+
+    /// Stage, unstage or discard one hunk of a file, or some of its lines
+    #[command(name = "git-apply-lines")]
+    GitApplyLines {
+        dir: String,
+        /// stage, unstage or discard
+        action: String,
+        file: String,
+        /// Hunk index, as listed by git-diff-files
+        hunk: usize,
+        /// Line index within the hunk (whole hunk when omitted); may be repeated
+        #[arg(long = "line")]
+        lines: Vec<usize>,
+        /// Lines of context the hunk was listed with
+        #[arg(long)]
+        context: Option<u32>,
+    },
+
+    /// List stash entries with branch, message and file counts as JSON
+    #[command(name = "git-stash-list")]
+    GitStashList {
+        #[arg(default_value = ".")]
+        dir: String,
+    },
+
+    /// Stash local changes
+    #[command(name = "git-stash-push")]
+    GitStashPush {
+        #[arg(default_value = ".")]
+        dir: String,
+        #[arg(long, short = 'm')]
+        message: Option<String>,
+        /// Stash untracked files too
+        #[arg(long, short = 'u')]
+        include_untracked: bool,
+        /// Leave staged changes in place
+        #[arg(long)]
+        keep_index: bool,
+    },
+
+    /// Apply a stash entry, keeping it
+    #[command(name = "git-stash-apply")]
+    GitStashApply {
+        dir: String,
+        /// Entry to apply (0 is the newest)
+        #[arg(default_value_t = 0)]
+        index: usize,
+    },
+
+    /// Apply a stash entry and drop it
+    #[command(name = "git-stash-pop")]
+    GitStashPop {
+        dir: String,
+        #[arg(default_value_t = 0)]
+        index: usize,
+    },
+
+    /// Drop a stash entry
+    #[command(name = "git-stash-drop")]
+    GitStashDrop {
+        dir: String,
+        #[arg(default_value_t = 0)]
+        index: usize,
+    },
+
+    /// Print the changes of a stash entry as files, hunks and lines (JSON)
+    #[command(name = "git-stash-show")]
+    GitStashShow {
+        dir: String,
+        #[arg(default_value_t = 0)]
+        index: usize,
+        /// Lines of context around hunks
+        #[arg(long)]
+        context: Option<u32>,
+    },
+
+    /// Continue, abort or skip the merge, rebase, cherry-pick, revert or bisect in progress
+    #[command(name = "git-operation")]
+    GitOperation {
+        dir: String,
+        /// continue, abort or skip
+        action: String,
+    },
+
+    /// Resolve a conflicted file with our or their version
+    #[command(name = "git-resolve")]
+    GitResolve {
+        dir: String,
+        file: String,
+        /// ours or theirs
+        side: String,
+    },
+
     /// Detect listening ports
     #[command(name = "detect-ports")]
-    DetectPorts,
+    DetectPorts {
+        /// Print bind address, protocol and owning process for each socket
+        #[arg(long)]
+        detailed: bool,
+        #[command(flatten)]
+        filter: ports::PortFilter,
+    },
     
     /// Combined watchdog (git status + ports)
     Watchdog {
@@ -171,93 +378,519 @@ enum Commands {
         #[arg(default_value = ".")]
         dir: String,
     },
+
+    /// Stream git status, port and cwd changes as JSON lines until stopped
+    Watch {
+        /// Directory to watch (ignored with --pid)
+        #[arg(default_value = ".")]
+        dir: String,
+        /// Follow the working directory of this process
+        #[arg(long)]
+        pid: Option<u32>,
+        /// Git status and cwd poll interval in milliseconds
+        #[arg(long, default_value_t = 2000)]
+        interval_ms: u64,
+        /// Listening port poll interval in milliseconds
+        #[arg(long, default_value_t = 5000)]
+        ports_interval_ms: u64,
+        #[command(flatten)]
+        filter: ports::PortFilter,
+    },
+
+    /// Report CPU, memory, disk, network and top processes as JSON
+    Metrics {
+        /// Keep printing one JSON line per interval
+        #[arg(long)]
+        watch: bool,
+        /// Sample window (and print interval with --watch) in milliseconds
+        #[arg(long, default_value_t = 1000)]
+        interval_ms: u64,
+        /// Number of top processes by CPU to include
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+    },
+
+    /// List processes as a JSON tree (or flat list)
+    Ps {
+        /// Keep processes whose name or command line contains this
+        #[arg(long)]
+        filter: Option<String>,
+        /// Print a flat list instead of a tree
+        #[arg(long)]
+        flat: bool,
+        /// Measure CPU over this many milliseconds (0 for lifetime average)
+        #[arg(long, default_value_t = 250)]
+        sample_ms: u64,
+    },
+
+    /// Send a signal to processes
+    Kill {
+        /// Process ids
+        #[arg(required = true)]
+        pids: Vec<u32>,
+        /// Signal name (TERM, KILL, INT, HUP, ...) or number
+        #[arg(long, short = 's', default_value = "TERM")]
+        signal: String,
+    },
+
+    /// Print (and follow) log files and journal units as JSON lines
+    Logs {
+        /// Log file to read; may be repeated
+        #[arg(long = "file", short = 'f')]
+        files: Vec<String>,
+        /// systemd unit to read from the journal; may be repeated
+        #[arg(long = "unit", short = 'u')]
+        units: Vec<String>,
+        /// Docker/Podman container name or id; may be repeated
+        #[arg(long = "container", short = 'c')]
+        containers: Vec<String>,
+        /// Lines of history per source
+        #[arg(long, short = 'n', default_value_t = 100)]
+        lines: usize,
+        /// Keep following new lines (survives log rotation)
+        #[arg(long)]
+        follow: bool,
+        /// Only lines matching this regular expression
+        #[arg(long)]
+        grep: Option<String>,
+        /// Case-insensitive --grep
+        #[arg(long, short = 'i')]
+        ignore_case: bool,
+        /// Minimum level: error, warning, info or debug
+        #[arg(long)]
+        level: Option<String>,
+    },
+
+    /// List containers with image, status and ports as JSON
+    Containers {
+        /// Include stopped containers
+        #[arg(long, short = 'a')]
+        all: bool,
+    },
+
+    /// Add cwd tracking (OSC 7) to shell rc files; prints what changed as JSON
+    #[command(name = "install-shell-integration")]
+    InstallShellIntegration {
+        /// bash, zsh or fish (defaults to the login shell); may be repeated
+        #[arg(long = "shell")]
+        shells: Vec<String>,
+    },
+
+    /// Remove the cwd tracking added by install-shell-integration
+    #[command(name = "uninstall-shell-integration")]
+    UninstallShellIntegration {
+        /// bash, zsh or fish (defaults to every shell that has it); may be repeated
+        #[arg(long = "shell")]
+        shells: Vec<String>,
+    },
+
+    /// Search files by name glob and/or content (one JSON match per line)
+    Search {
+        /// Directory to search
+        #[arg(default_value = ".")]
+        dir: String,
+        /// File name glob (matched against the path below DIR when it contains '/')
+        #[arg(long)]
+        name: Option<String>,
+        /// Text to look for inside files
+        #[arg(long)]
+        content: Option<String>,
+        /// Treat --content as a regular expression
+        #[arg(long)]
+        regex: bool,
+        /// Case-insensitive matching
+        #[arg(long, short = 'i')]
+        ignore_case: bool,
+        /// Include hidden files and directories
+        #[arg(long)]
+        hidden: bool,
+        /// Stop after this many matches
+        #[arg(long, default_value_t = 1000)]
+        max_results: usize,
+        /// Skip content search in files larger than this (bytes)
+        #[arg(long, default_value_t = 4 * 1024 * 1024)]
+        max_file_size: u64,
+    },
+
+    /// Answer JSON-RPC requests on stdin/stdout until stdin closes
+    Serve,
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
-    
-    match cli.command {
+/// What a subcommand produced. Streaming subcommands write their lines to
+/// the output as they go and reply with `Nothing`.
+enum Reply {
+    Json(serde_json::Value),
+    Text(String),
+    Nothing,
+}
+
+/// Run one subcommand, writing streamed lines to `out`
+fn execute(command: Commands, out: &mut dyn Output) -> Result<Reply> {
+    let reply = match command {
         Commands::Health => {
             let os = detect_os();
-            println!(r#"{{"ok":true,"version":"{}","os":"{}"}}"#, HELPER_VERSION, os);
+            Reply::Json(serde_json::json!({ "ok": true, "version": HELPER_VERSION, "os": os }))
         }
+
+        Commands::Env => Reply::Json(serde_json::to_value(env::report())?),
         
         Commands::GitStatus { dir } => {
             let status = git::status(&dir)?;
-            println!("{}", serde_json::to_string(&status)?);
+            Reply::Json(serde_json::to_value(&status)?)
         }
         
         Commands::GitChanges { dir } => {
             let changes = git::changes(&dir)?;
-            println!("{}", serde_json::to_string(&changes)?);
+            Reply::Json(serde_json::to_value(&changes)?)
         }
         
         Commands::GitDiff { dir, file, mode } => {
             let diff = git::diff(&dir, &file, &mode)?;
-            print!("{}", diff);
+            Reply::Text(diff)
         }
         
         Commands::GitCommit { dir, message } => {
             let msg = message.join(" ");
             let output = git::commit(&dir, &msg)?;
-            print!("{}", output);
+            Reply::Text(output)
         }
         
         Commands::GitSync { dir } => {
             let output = git::sync(&dir)?;
-            print!("{}", output);
+            Reply::Text(output)
         }
         
         Commands::GitStage { dir, file } => {
             let output = git::stage(&dir, &file)?;
-            print!("{}", output);
+            Reply::Text(output)
         }
         
         Commands::GitUnstage { dir, file } => {
             let output = git::unstage(&dir, &file)?;
-            print!("{}", output);
+            Reply::Text(output)
         }
         
         Commands::GitDiscard { dir, file } => {
             let output = git::discard(&dir, &file)?;
-            print!("{}", output);
+            Reply::Text(output)
         }
         
         Commands::GitStageAll { dir } => {
             let output = git::stage_all(&dir)?;
-            print!("{}", output);
+            Reply::Text(output)
         }
         
         Commands::GitUnstageAll { dir } => {
             let output = git::unstage_all(&dir)?;
-            print!("{}", output);
+            Reply::Text(output)
         }
         
         Commands::GitPull { dir } => {
             let output = git::pull(&dir)?;
-            print!("{}", output);
+            Reply::Text(output)
         }
         
         Commands::GitPush { dir } => {
             let output = git::push(&dir)?;
-            print!("{}", output);
+            Reply::Text(output)
         }
         
-        Commands::DetectPorts => {
-            let ports = ports::detect()?;
-            println!("{}", serde_json::to_string(&ports)?);
+        Commands::GitLog {
+            dir,
+            skip,
+            limit,
+            rev,
+            all,
+            path,
+            author,
+            message,
+        } => {
+            let page = git::log(
+                &dir,
+                &git::GitLogOptions {
+                    skip,
+                    limit,
+                    rev,
+                    all,
+                    path,
+                    author,
+                    message,
+                },
+            )?;
+            Reply::Json(serde_json::to_value(&page)?)
+        }
+
+        Commands::GitBranches { dir, no_remotes } => {
+            let branches = git::branches(&dir, !no_remotes)?;
+            Reply::Json(serde_json::to_value(&branches)?)
+        }
+
+        Commands::GitBranchCreate { dir, name, start } => {
+            git::branch_create(&dir, &name, start.as_deref())?;
+            Reply::Nothing
+        }
+
+        Commands::GitCheckout { dir, branch } => {
+            let result = git::checkout(&dir, &branch)?;
+            Reply::Json(serde_json::to_value(&result)?)
+        }
+
+        Commands::GitBranchDelete { dir, name, force } => {
+            git::branch_delete(&dir, &name, force)?;
+            Reply::Nothing
+        }
+
+        Commands::GitBranchRename {
+            dir,
+            name,
+            new_name,
+            force,
+        } => {
+            git::branch_rename(&dir, &name, &new_name, force)?;
+            Reply::Nothing
+        }
+
+        Commands::GitBranchSetUpstream {
+            dir,
+            name,
+            upstream,
+        } => {
+            git::branch_set_upstream(&dir, &name, upstream.as_deref())?;
+            Reply::Nothing
+        }
+
+        Commands::GitDiffFiles {
+            dir,
+            paths,
+            staged,
+            context,
+        } => {
+            let files = git::diff_files(
+                &dir,
+                &git::GitDiffOptions {
+                    staged,
+                    paths,
+                    context,
+                },
+            )?;
+            Reply::Json(serde_json::to_value(&files)?)
+        }
+
+        Commands::GitApplyLines {
+            dir,
+            action,
+            file,
+            hunk,
+            lines,
+            context,
+        } => {
+            let action = git::LineAction::parse(&action)?;
+            let lines = (!lines.is_empty()).then_some(lines.as_slice());
+            git::apply_lines(&dir, action, &file, hunk, lines, context)?;
+            Reply::Nothing
+        }
+
+        Commands::GitStashList { dir } => {
+            let list = git::stash_list(&dir)?;
+            Reply::Json(serde_json::to_value(&list)?)
+        }
+
+        Commands::GitStashPush {
+            dir,
+            message,
+            include_untracked,
+            keep_index,
+        } => {
+            git::stash_push(&dir, message.as_deref(), include_untracked, keep_index)?;
+            Reply::Nothing
+        }
+
+        Commands::GitStashApply { dir, index } => {
+            git::stash_apply(&dir, index, false)?;
+            Reply::Nothing
+        }
+
+        Commands::GitStashPop { dir, index } => {
+            git::stash_apply(&dir, index, true)?;
+            Reply::Nothing
+        }
+
+        Commands::GitStashDrop { dir, index } => {
+            git::stash_drop(&dir, index)?;
+            Reply::Nothing
+        }
+
+        Commands::GitStashShow {
+            dir,
+            index,
+            context,
+        } => {
+            let files = git::stash_show(&dir, index, context)?;
+            Reply::Json(serde_json::to_value(&files)?)
+        }
+
+        Commands::GitOperation { dir, action } => {
+            let output = git::operation_action(&dir, &action)?;
+            Reply::Text(output)
+        }
+
+        Commands::GitResolve { dir, file, side } => {
+            git::resolve(&dir, &file, &side)?;
+            Reply::Nothing
+        }
+
+        Commands::DetectPorts { detailed, filter } => {
+            if detailed {
+                let sockets = ports::listening(&filter)?;
+                Reply::Json(serde_json::to_value(&sockets)?)
+            } else {
+                let ports = ports::detect(&filter)?;
+                Reply::Json(serde_json::to_value(&ports)?)
+            }
         }
         
         Commands::Watchdog { dir } => {
             let git_status = git::status(&dir)?;
-            let ports = ports::detect()?;
-            let result = serde_json::json!({
+            let ports = ports::detect(&ports::PortFilter::default())?;
+            Reply::Json(serde_json::json!({
                 "git": git_status,
                 "ports": ports
-            });
-            println!("{}", serde_json::to_string(&result)?);
+            }))
+        }
+        
+        Commands::Watch {
+            dir,
+            pid,
+            interval_ms,
+            ports_interval_ms,
+            filter,
+        } => {
+            watch::run(
+                &dir,
+                watch::WatchOptions {
+                    interval: std::time::Duration::from_millis(interval_ms.max(100)),
+                    ports_interval: std::time::Duration::from_millis(ports_interval_ms.max(100)),
+                    pid,
+                    ports: filter,
+                },
+                out,
+            )?;
+            Reply::Nothing
+        }
+
+        Commands::Metrics {
+            watch,
+            interval_ms,
+            top,
+        } => {
+            metrics::run(
+                std::time::Duration::from_millis(interval_ms.max(100)),
+                watch,
+                top,
+                out,
+            )?;
+            Reply::Nothing
+        }
+
+        Commands::Ps {
+            filter,
+            flat,
+            sample_ms,
+        } => {
+            let procs = ps::list(
+                filter.as_deref(),
+                flat,
+                std::time::Duration::from_millis(sample_ms),
+            )?;
+            Reply::Json(serde_json::to_value(&procs)?)
+        }
+
+        Commands::Kill { pids, signal } => {
+            let results = ps::kill(&pids, &signal)?;
+            Reply::Json(serde_json::to_value(&results)?)
         }
+
+        Commands::Logs {
+            files,
+            units,
+            containers,
+            lines,
+            follow,
+            grep,
+            ignore_case,
+            level,
+        } => {
+            logs::run(
+                logs::LogsOptions {
+                    files,
+                    units,
+                    containers,
+                    lines,
+                    follow,
+                    grep,
+                    ignore_case,
+                    level,
+                },
+                out,
+            )?;
+            Reply::Nothing
+        }
+
+        Commands::Containers { all } => {
+            let list = containers::list(all)?;
+            Reply::Json(serde_json::to_value(&list)?)
+        }
+
+        Commands::InstallShellIntegration { shells } => {
+            let results = shell_integration::install(&shells)?;
+            Reply::Json(serde_json::to_value(&results)?)
+        }
+
+        Commands::UninstallShellIntegration { shells } => {
+            let results = shell_integration::uninstall(&shells)?;
+            Reply::Json(serde_json::to_value(&results)?)
+        }
+
+        Commands::Search {
+            dir,
+            name,
+            content,
+            regex,
+            ignore_case,
+            hidden,
+            max_results,
+            max_file_size,
+        } => {
+            search::run(
+                &dir,
+                search::SearchOptions {
+                    name,
+                    content,
+                    regex,
+                    ignore_case,
+                    hidden,
+                    max_results,
+                    max_file_size,
+                },
+                out,
+            )?;
+            Reply::Nothing
+        }
+
+        Commands::Serve => anyhow::bail!("already serving"),
+    };
+    Ok(reply)
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    if let Commands::Serve = cli.command {
+        return serve::run();
+    }
+    match execute(cli.command, &mut Stdout)? {
+        Reply::Json(value) => println!("{}", value),
+        Reply::Text(text) => print!("{}", text),
+        Reply::Nothing => {}
     }
-    
     Ok(())
-}
\ No newline at end of file
+}
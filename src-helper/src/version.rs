@@ -1,5 +1,8 @@
 /// Helper version - update this when making changes to the helper
-pub const HELPER_VERSION: &str = "0.2.1";
+pub const HELPER_VERSION: &str = "0.2.21";
+
+/// Version of the `serve` JSON-RPC protocol - bump on incompatible message changes
+pub const PROTOCOL_VERSION: u32 = 1;
 
 /// Helper binary name
 #[allow(dead_code)]
@@ -1,5 +1,24 @@
 use image::{GenericImageView, ImageEncoder};
-use std::{env, fs, fs::File, io::BufWriter, path::Path, process::Command}; // write_image + size helpers
+use sha2::{Digest, Sha256};
+use std::{
+    env, fs,
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+    process::Command,
+}; // write_image + size helpers
+
+/// Cross-compiled helper builds to embed: target triple, `uname -s`
+/// (lowercased) and architecture of the hosts they run on
+const HELPER_TARGETS: &[(&str, &str, &str)] = &[
+    ("x86_64-unknown-linux-musl", "linux", "x86_64"),
+    ("aarch64-unknown-linux-musl", "linux", "aarch64"),
+    ("armv7-unknown-linux-musleabihf", "linux", "armv7l"),
+    ("x86_64-unknown-linux-gnu", "linux", "x86_64"),
+    ("aarch64-unknown-linux-gnu", "linux", "aarch64"),
+    ("x86_64-apple-darwin", "darwin", "x86_64"),
+    ("aarch64-apple-darwin", "darwin", "aarch64"),
+];
 
 fn is_rgba8_png(path: &Path) -> bool {
     match File::open(path) {
@@ -87,30 +106,29 @@ fn build_helper_binary() {
 
     println!("cargo:warning=Built native helper binary successfully");
 
-    // Also build Linux binary if cargo-zigbuild is available and we're on macOS
-    if cfg!(target_os = "macos") {
-        if let Ok(output) = Command::new("which").arg("cargo-zigbuild").output() {
-            if output.status.success() {
-                println!("cargo:warning=Building Linux helper binary with cargo-zigbuild...");
-                let output = Command::new("cargo")
-                    .args([
-                        "zigbuild",
-                        "--release",
-                        "--target",
-                        "x86_64-unknown-linux-musl",
-                    ])
-                    .current_dir("../src-helper")
-                    .output()
-                    .expect("Failed to build Linux helper binary");
-
-                if !output.status.success() {
-                    println!(
-                        "cargo:warning=Failed to build Linux helper binary: {}",
-                        String::from_utf8_lossy(&output.stderr)
-                    );
-                } else {
-                    println!("cargo:warning=Built Linux helper binary successfully");
-                }
+    // Also build static Linux binaries for remote hosts if cargo-zigbuild is available
+    let zigbuild = Command::new("which")
+        .arg("cargo-zigbuild")
+        .output()
+        .is_ok_and(|o| o.status.success());
+    if zigbuild {
+        for (triple, _, _) in HELPER_TARGETS.iter().filter(|t| t.0.contains("-musl")) {
+            println!(
+                "cargo:warning=Building {} helper binary with cargo-zigbuild...",
+                triple
+            );
+            let output = Command::new("cargo")
+                .args(["zigbuild", "--release", "--target", triple])
+                .current_dir("../src-helper")
+                .output()
+                .expect("Failed to run cargo-zigbuild");
+
+            if !output.status.success() {
+                println!(
+                    "cargo:warning=Failed to build {} helper binary: {}",
+                    triple,
+                    String::from_utf8_lossy(&output.stderr)
+                );
             }
         }
     }
@@ -141,26 +159,64 @@ fn generate_helper_module() {
 
     let helper_binary = fs::read(helper_binary_path)
         .expect("Failed to read helper binary - run 'cargo build --release' in src-helper first");
+    for (triple, _, _) in HELPER_TARGETS {
+        println!(
+            "cargo:rerun-if-changed=../src-helper/target/{}/release/jaterm-agent",
+            triple
+        );
+    }
 
-    // Try to read Linux binary if it exists (try musl first, then gnu)
-    let linux_binary_path = if Path::new(
-        "../src-helper/target/x86_64-unknown-linux-musl/release/jaterm-agent",
-    )
-    .exists()
-    {
-        Path::new("../src-helper/target/x86_64-unknown-linux-musl/release/jaterm-agent")
-    } else {
-        Path::new("../src-helper/target/x86_64-unknown-linux-gnu/release/jaterm-agent")
-    };
-    let linux_binary = if linux_binary_path.exists() {
-        fs::read(linux_binary_path).ok()
-    } else {
-        None
-    };
+    // Binaries for remote hosts, keyed by `uname -s` and `uname -m`. Static
+    // musl builds come first so they win over the host's own build.
+    let native = (
+        env::consts::OS.replace("macos", "darwin"),
+        env::consts::ARCH.to_string(),
+    );
+    let mut candidates: Vec<(PathBuf, String, String)> = HELPER_TARGETS
+        .iter()
+        .map(|(triple, os, arch)| {
+            (
+                Path::new("../src-helper/target")
+                    .join(triple)
+                    .join("release/jaterm-agent"),
+                os.to_string(),
+                arch.to_string(),
+            )
+        })
+        .collect();
+    if matches!(native.0.as_str(), "linux" | "darwin") {
+        candidates.push((helper_binary_path.to_path_buf(), native.0, native.1));
+    }
 
-    // Write the helper module with embedded binary/binaries
     let out_dir = env::var("OUT_DIR").unwrap();
+    let mut targets = String::new();
+    let mut embedded: Vec<String> = Vec::new();
+    for (path, os, arch) in candidates {
+        let key = format!("{}-{}", os, arch);
+        if embedded.contains(&key) {
+            continue;
+        }
+        let Ok(binary) = fs::read(&path) else {
+            continue;
+        };
+        // Copy into OUT_DIR so a helper rebuild cannot change it under include_bytes!
+        let copy = Path::new(&out_dir).join(format!("jaterm-agent-{}", key));
+        fs::write(&copy, &binary).expect("Failed to copy helper binary");
+        let sha256: String = Sha256::digest(&binary)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        targets.push_str(&format!(
+            "    HelperTarget {{ os: {:?}, arch: {:?}, sha256: {:?}, binary: include_bytes!({:?}) }},\n",
+            os, arch, sha256, copy
+        ));
+        embedded.push(key);
+    }
+
+    // Write the helper module with embedded binary/binaries
     let dest_path = Path::new(&out_dir).join("helper_generated.rs");
+    let native_copy = Path::new(&out_dir).join("jaterm-agent-native");
+    fs::write(&native_copy, &helper_binary).expect("Failed to copy helper binary");
 
     let rust_code = format!(
         r#"pub const HELPER_VERSION: &str = "{}";
@@ -169,24 +225,32 @@ pub const HELPER_NAME: &str = "jaterm-agent.exe";
 #[cfg(not(windows))]
 pub const HELPER_NAME: &str = "jaterm-agent";
 pub const HELPER_REL_DIR: &str = ".jaterm-helper";
-pub const HELPER_BINARY: &[u8] = &{:?};
-pub const HELPER_BINARY_LINUX: Option<&[u8]> = {};
+pub const HELPER_BINARY: &[u8] = include_bytes!({:?});
+
+/// A helper build for remote hosts of one OS and architecture
+pub struct HelperTarget {{
+    /// `uname -s`, lowercased
+    pub os: &'static str,
+    /// `uname -m`, with `arm64` and `amd64` as `aarch64` and `x86_64`
+    pub arch: &'static str,
+    /// Hex SHA-256 of `binary`
+    pub sha256: &'static str,
+    pub binary: &'static [u8],
+}}
+
+pub const HELPER_TARGETS: &[HelperTarget] = &[
+{}];
 "#,
-        version,
-        helper_binary,
-        if let Some(linux_bin) = linux_binary {
-            format!("Some(&{:?})", linux_bin)
-        } else {
-            "None".to_string()
-        }
+        version, native_copy, targets
     );
 
     fs::write(&dest_path, rust_code).expect("Failed to write generated helper module");
 
     println!("cargo:warning=Embedded helper binary version {}", version);
-    if linux_binary_path.exists() {
-        println!("cargo:warning=Also embedded Linux helper binary");
-    }
+    println!(
+        "cargo:warning=Embedded helper binaries for: {}",
+        embedded.join(", ")
+    );
 }
 
 fn main() {
@@ -0,0 +1,145 @@
+use std::io::{ErrorKind, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crate::commands::agent_rpc;
+use crate::commands::transfer::LockedSession;
+use crate::state::app_state::AppState;
+use crate::utils::shell_quote;
+
+/// Where the helper agent is deployed on remote hosts (expanded by the remote shell)
+pub(crate) const REMOTE_AGENT: &str = "~/.jaterm-helper/jaterm-agent";
+
+// Pause between polls of a streaming channel that has no data yet
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+// Keep at most this much stderr from a streaming command
+const MAX_STDERR: usize = 64 * 1024;
+
+/// Run a helper agent subcommand and return its stdout: the local helper
+/// when `session_id` is None, otherwise the one deployed on that SSH session.
+/// Goes through the session's agent when it can be started, otherwise runs
+/// the subcommand as its own process. Failures carry the agent's message.
+pub(crate) fn agent_exec(
+    state: &AppState,
+    session_id: Option<&str>,
+    args: &[String],
+) -> Result<String, String> {
+    let (method, rest) = args.split_first().ok_or("no helper command")?;
+    match agent_rpc::connect(state, session_id) {
+        Ok(agent) => return agent.call(method, rest).map(agent_rpc::result_text),
+        Err(e) if e == "helper not installed" => return Err(e),
+        Err(e) => eprintln!("[agent] rpc unavailable ({}), running {} directly", e, method),
+    }
+    let res = match session_id {
+        None => {
+            let path = crate::commands::helper::local_helper_path()?;
+            if !path.exists() {
+                return Err("helper not installed".into());
+            }
+            let refs: Vec<&str> = args.iter().map(String::as_str).collect();
+            let res = crate::commands::helper::helper_local_exec_internal(&path, &refs)?;
+            (res.stdout, res.stderr, res.exit_code)
+        }
+        Some(id) => {
+            let ls = LockedSession::for_session(state, id)?;
+            let mut command = REMOTE_AGENT.to_string();
+            for arg in args {
+                command.push(' ');
+                command.push_str(&shell_quote(arg));
+            }
+            let res = ls.run(|| crate::commands::ssh::run_exec(&ls.sess, &command))?;
+            if res.exit_code == 127 {
+                return Err("helper not installed".into());
+            }
+            (res.stdout, res.stderr, res.exit_code)
+        }
+    };
+    let (stdout, stderr, code) = res;
+    if code != 0 {
+        // anyhow prints "Error: <message>" followed by a backtrace when enabled
+        let message = stderr
+            .lines()
+            .find_map(|l| l.strip_prefix("Error: "))
+            .or_else(|| stderr.lines().rev().find(|l| !l.trim().is_empty()))
+            .unwrap_or("helper failed");
+        return Err(message.trim().to_string());
+    }
+    Ok(stdout)
+}
+
+pub(crate) struct StreamExit {
+    /// None when the stream was cancelled before the command finished
+    pub code: Option<i32>,
+    pub stderr: String,
+}
+
+/// Run a remote command and hand stdout to `on_data` as it arrives. The
+/// session lock is only held for each non-blocking read, so other work on the
+/// session keeps going while the command runs. Setting `cancel` closes the
+/// channel; `on_data` returning false does the same.
+pub(crate) fn stream_exec(
+    ls: &LockedSession,
+    command: &str,
+    cancel: &AtomicBool,
+    mut on_data: impl FnMut(&[u8]) -> bool,
+) -> Result<StreamExit, String> {
+    let mut channel = ls
+        .run(|| -> Result<ssh2::Channel, ssh2::Error> {
+            let mut channel = ls.sess.channel_session()?;
+            channel.exec(command)?;
+            Ok(channel)
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut buf = vec![0u8; 32 * 1024];
+    let mut stderr: Vec<u8> = Vec::new();
+    let result = loop {
+        if cancel.load(Ordering::Relaxed) {
+            ls.run(|| {
+                let _ = channel.close();
+            });
+            break Ok(false);
+        }
+        let (out, err, eof) = {
+            let _guard = ls.lock.lock().unwrap();
+            let out = channel.read(&mut buf);
+            let err = match out {
+                Ok(_) => Ok(0),
+                Err(_) => channel.stderr().read(&mut buf),
+            };
+            (out, err, channel.eof())
+        };
+        match out {
+            Ok(0) if eof => break Ok(true),
+            Ok(0) => thread::sleep(POLL_INTERVAL),
+            Ok(n) => {
+                if !on_data(&buf[..n]) {
+                    ls.run(|| {
+                        let _ = channel.close();
+                    });
+                    break Ok(false);
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => match err {
+                Ok(n) if n > 0 => {
+                    if stderr.len() < MAX_STDERR {
+                        stderr.extend_from_slice(&buf[..n]);
+                    }
+                }
+                _ if eof => break Ok(true),
+                _ => thread::sleep(POLL_INTERVAL),
+            },
+            Err(e) => break Err(e.to_string()),
+        }
+    };
+
+    let code = ls.run(move || {
+        let _ = channel.wait_close();
+        channel.exit_status().ok()
+    });
+    result.map(|finished| StreamExit {
+        code: if finished { code } else { None },
+        stderr: String::from_utf8_lossy(&stderr).to_string(),
+    })
+}
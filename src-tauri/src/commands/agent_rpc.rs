@@ -0,0 +1,414 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::commands::agent::REMOTE_AGENT;
+use crate::commands::transfer::LockedSession;
+use crate::state::app_state::AppState;
+
+/// Version of the helper's `serve` protocol this build speaks
+const PROTOCOL_VERSION: u64 = 1;
+// Key of this machine's agent in `Inner::agents`
+const LOCAL: &str = "local";
+// How long a new agent gets to announce itself
+const READY_TIMEOUT: Duration = Duration::from_secs(10);
+// Pause between polls of a remote agent's channel that has no data yet
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+// How often a waiting call checks its cancel flag
+const CANCEL_POLL: Duration = Duration::from_millis(100);
+// JSON-RPC error code the agent answers cancelled requests with
+const REQUEST_CANCELLED: i64 = -32800;
+// Keep at most this much agent stderr for error messages
+const MAX_STDERR: usize = 16 * 1024;
+
+/// A new agent, and where its `ready` notification (or failure) arrives
+type Started = (Arc<Agent>, mpsc::Receiver<Result<Value, String>>);
+
+enum Message {
+    Stream(Value),
+    Done(Result<Value, String>),
+}
+
+enum Transport {
+    Local {
+        child: Mutex<Child>,
+        stdin: Mutex<ChildStdin>,
+    },
+    Remote {
+        ls: LockedSession,
+        channel: Mutex<ssh2::Channel>,
+    },
+}
+
+/// A `jaterm-agent serve` process shared by every helper call for one SSH
+/// session (or this machine). Responses are matched to requests by id, so
+/// any number of calls can be in flight at once.
+pub struct Agent {
+    transport: Transport,
+    next_id: AtomicU64,
+    pending: Mutex<HashMap<u64, mpsc::Sender<Message>>>,
+    closed: AtomicBool,
+    stderr: Mutex<Vec<u8>>,
+}
+
+impl Agent {
+    fn new(transport: Transport) -> Arc<Self> {
+        Arc::new(Agent {
+            transport,
+            next_id: AtomicU64::new(1),
+            pending: Mutex::new(HashMap::new()),
+            closed: AtomicBool::new(false),
+            stderr: Mutex::new(Vec::new()),
+        })
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Relaxed)
+    }
+
+    /// Stop the agent. Calls still waiting fail with "helper agent exited".
+    pub(crate) fn shutdown(&self) {
+        self.closed.store(true, Ordering::Relaxed);
+        // A remote agent's reader closes the channel on its next poll
+        if let Transport::Local { child, .. } = &self.transport {
+            let _ = child.lock().map(|mut c| c.kill());
+        }
+    }
+
+    fn send(&self, message: &Value) -> Result<(), String> {
+        let line = format!("{}\n", message);
+        let res = match &self.transport {
+            Transport::Local { stdin, .. } => {
+                let mut stdin = stdin.lock().map_err(|_| "lock")?;
+                stdin.write_all(line.as_bytes()).and_then(|_| stdin.flush())
+            }
+            Transport::Remote { ls, channel } => ls.run(|| {
+                let mut channel = channel.lock().unwrap();
+                channel
+                    .write_all(line.as_bytes())
+                    .and_then(|_| channel.flush())
+            }),
+        };
+        res.map_err(|e| {
+            self.shutdown();
+            format!("helper agent: {}", e)
+        })
+    }
+
+    fn note_stderr(&self, data: &[u8]) {
+        if let Ok(mut stderr) = self.stderr.lock() {
+            if stderr.len() < MAX_STDERR {
+                stderr.extend_from_slice(data);
+            }
+        }
+    }
+
+    /// Why the agent stopped, from its exit code and last stderr line
+    fn exit_reason(&self, code: Option<i32>) -> String {
+        if code == Some(127) {
+            return "helper not installed".into();
+        }
+        let stderr = self.stderr.lock().map(|s| s.clone()).unwrap_or_default();
+        String::from_utf8_lossy(&stderr)
+            .lines()
+            .rev()
+            .map(str::trim)
+            .find(|l| !l.is_empty())
+            .map(|l| l.trim_start_matches("error: ").to_string())
+            .unwrap_or_else(|| "helper agent exited".into())
+    }
+
+    /// Route one line from the agent to the call waiting for it
+    fn dispatch(&self, line: &str, ready: &mpsc::Sender<Result<Value, String>>) {
+        let Ok(mut message) = serde_json::from_str::<Value>(line) else {
+            eprintln!("[agent] unexpected output: {}", line);
+            return;
+        };
+        if let Some(id) = message.get("id") {
+            let Some(id) = id.as_u64() else {
+                eprintln!("[agent] error: {}", message["error"]["message"]);
+                return;
+            };
+            let result = match message.get("error") {
+                Some(e) if e["code"].as_i64() == Some(REQUEST_CANCELLED) => {
+                    Err("cancelled".to_string())
+                }
+                Some(e) => Err(e["message"].as_str().unwrap_or("helper failed").to_string()),
+                None => Ok(message["result"].take()),
+            };
+            if let Some(tx) = self.pending.lock().unwrap().remove(&id) {
+                let _ = tx.send(Message::Done(result));
+            }
+            return;
+        }
+        match message["method"].as_str() {
+            Some("stream") => {
+                let id = message["params"]["id"].as_u64().unwrap_or(0);
+                if let Some(tx) = self.pending.lock().unwrap().get(&id) {
+                    let _ = tx.send(Message::Stream(message["params"]["data"].take()));
+                }
+            }
+            Some("ready") => {
+                let _ = ready.send(Ok(message["params"].take()));
+            }
+            _ => {}
+        }
+    }
+
+    /// Fail everything still waiting once the agent is gone
+    fn finish(&self, reason: String, ready: &mpsc::Sender<Result<Value, String>>) {
+        self.closed.store(true, Ordering::Relaxed);
+        for (_, tx) in self.pending.lock().unwrap().drain() {
+            let _ = tx.send(Message::Done(Err(reason.clone())));
+        }
+        let _ = ready.send(Err(reason));
+    }
+
+    /// Call a helper subcommand with its command-line `args` and return its
+    /// result: parsed JSON for subcommands that print JSON, otherwise text
+    pub(crate) fn call(&self, method: &str, args: &[String]) -> Result<Value, String> {
+        self.call_streaming(method, args, None, |_| {})
+    }
+
+    /// Like `call`, handing each line a streaming subcommand prints to
+    /// `on_item` as it arrives. Setting `cancel` asks the agent to stop the
+    /// request, which then fails with "cancelled".
+    pub(crate) fn call_streaming(
+        &self,
+        method: &str,
+        args: &[String],
+        cancel: Option<&AtomicBool>,
+        mut on_item: impl FnMut(Value),
+    ) -> Result<Value, String> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = mpsc::channel();
+        self.pending.lock().unwrap().insert(id, tx);
+        // The reader may have drained `pending` just before the insert
+        if self.is_closed() {
+            self.pending.lock().unwrap().remove(&id);
+            return Err("helper agent exited".into());
+        }
+        let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": args });
+        if let Err(e) = self.send(&request) {
+            self.pending.lock().unwrap().remove(&id);
+            return Err(e);
+        }
+
+        let mut cancel_sent = false;
+        loop {
+            if !cancel_sent && cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
+                cancel_sent = true;
+                let _ = self.send(&json!({
+                    "jsonrpc": "2.0",
+                    "method": "$/cancel",
+                    "params": { "id": id }
+                }));
+            }
+            match rx.recv_timeout(CANCEL_POLL) {
+                Ok(Message::Stream(item)) => on_item(item),
+                Ok(Message::Done(result)) => return result,
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    return Err("helper agent exited".into())
+                }
+            }
+        }
+    }
+}
+
+fn start_local() -> Result<Started, String> {
+    let path = crate::commands::helper::local_helper_path()?;
+    if !path.exists() {
+        return Err("helper not installed".into());
+    }
+    let mut child = Command::new(&path)
+        .arg("serve")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    let stdin = child.stdin.take().ok_or("no stdin")?;
+    let stdout = child.stdout.take().ok_or("no stdout")?;
+    let stderr = child.stderr.take();
+    let agent = Agent::new(Transport::Local {
+        child: Mutex::new(child),
+        stdin: Mutex::new(stdin),
+    });
+
+    let (ready_tx, ready_rx) = mpsc::channel();
+    let reader = agent.clone();
+    thread::spawn(move || {
+        let errors = stderr.map(|mut stderr| {
+            let agent = reader.clone();
+            thread::spawn(move || {
+                let mut buf = [0u8; 4096];
+                while let Ok(n @ 1..) = stderr.read(&mut buf) {
+                    agent.note_stderr(&buf[..n]);
+                }
+            })
+        });
+        for line in BufReader::new(stdout).lines() {
+            match line {
+                Ok(line) => reader.dispatch(&line, &ready_tx),
+                Err(_) => break,
+            }
+        }
+        if let Some(errors) = errors {
+            let _ = errors.join();
+        }
+        let code = match &reader.transport {
+            Transport::Local { child, .. } => child
+                .lock()
+                .ok()
+                .and_then(|mut c| c.wait().ok())
+                .and_then(|s| s.code()),
+            Transport::Remote { .. } => None,
+        };
+        reader.finish(reader.exit_reason(code), &ready_tx);
+    });
+    Ok((agent, ready_rx))
+}
+
+fn start_remote(ls: LockedSession) -> Result<Started, String> {
+    // `exec` so closing the channel ends the agent rather than a wrapping shell
+    let command = format!("exec {} serve", REMOTE_AGENT);
+    let channel = ls
+        .run(|| -> Result<ssh2::Channel, ssh2::Error> {
+            let mut channel = ls.sess.channel_session()?;
+            channel.exec(&command)?;
+            Ok(channel)
+        })
+        .map_err(|e| e.to_string())?;
+    let agent = Agent::new(Transport::Remote {
+        ls,
+        channel: Mutex::new(channel),
+    });
+
+    let (ready_tx, ready_rx) = mpsc::channel();
+    let reader = agent.clone();
+    thread::spawn(move || {
+        let Transport::Remote { ls, channel } = &reader.transport else {
+            return;
+        };
+        let mut buf = vec![0u8; 32 * 1024];
+        let mut pending: Vec<u8> = Vec::new();
+        // Same polling as `stream_exec`: the session lock is only held for
+        // each non-blocking read, so requests can be written in between
+        loop {
+            if reader.is_closed() {
+                break;
+            }
+            let (out, err, eof) = {
+                let _guard = ls.lock.lock().unwrap();
+                let mut channel = channel.lock().unwrap();
+                let out = channel.read(&mut buf);
+                let err = match out {
+                    Ok(_) => Ok(0),
+                    Err(_) => channel.stderr().read(&mut buf),
+                };
+                (out, err, channel.eof())
+            };
+            match out {
+                Ok(0) if eof => break,
+                Ok(0) => thread::sleep(POLL_INTERVAL),
+                Ok(n) => {
+                    pending.extend_from_slice(&buf[..n]);
+                    while let Some(pos) = pending.iter().position(|b| *b == b'\n') {
+                        let line: Vec<u8> = pending.drain(..=pos).collect();
+                        reader.dispatch(String::from_utf8_lossy(&line).trim_end(), &ready_tx);
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => match err {
+                    Ok(n) if n > 0 => reader.note_stderr(&buf[..n]),
+                    _ if eof => break,
+                    _ => thread::sleep(POLL_INTERVAL),
+                },
+                Err(e) => {
+                    eprintln!("[agent] read failed: {}", e);
+                    break;
+                }
+            }
+        }
+        let code = ls.run(|| {
+            let mut channel = channel.lock().unwrap();
+            let _ = channel.close();
+            let _ = channel.wait_close();
+            channel.exit_status().ok()
+        });
+        reader.finish(reader.exit_reason(code), &ready_tx);
+    });
+    Ok((agent, ready_rx))
+}
+
+/// The helper agent for an SSH session, or for this machine when
+/// `session_id` is None, started on first use
+pub(crate) fn connect(state: &AppState, session_id: Option<&str>) -> Result<Arc<Agent>, String> {
+    let key = session_id.unwrap_or(LOCAL);
+    {
+        let inner = state.inner.lock().map_err(|_| "lock")?;
+        if let Some(agent) = inner.agents.get(key).filter(|a| !a.is_closed()) {
+            return Ok(agent.clone());
+        }
+    }
+
+    let (agent, ready) = match session_id {
+        None => start_local()?,
+        Some(id) => start_remote(LockedSession::for_session(state, id)?)?,
+    };
+    let hello = match ready.recv_timeout(READY_TIMEOUT) {
+        Ok(Ok(hello)) => hello,
+        Ok(Err(e)) => return Err(e),
+        Err(_) => {
+            agent.shutdown();
+            return Err("helper agent did not start".into());
+        }
+    };
+    if hello["protocol"].as_u64() != Some(PROTOCOL_VERSION) {
+        agent.shutdown();
+        return Err(format!(
+            "helper {} speaks protocol {}, expected {}; redeploy the helper",
+            hello["version"], hello["protocol"], PROTOCOL_VERSION
+        ));
+    }
+
+    let mut inner = state.inner.lock().map_err(|_| "lock")?;
+    // Another call may have started one in the meantime
+    if let Some(existing) = inner.agents.get(key).filter(|a| !a.is_closed()) {
+        let existing = existing.clone();
+        drop(inner);
+        agent.shutdown();
+        return Ok(existing);
+    }
+    eprintln!("[agent] {} serving, helper {}", key, hello["version"]);
+    inner.agents.insert(key.to_string(), agent.clone());
+    Ok(agent)
+}
+
+/// Stop the agent of a session (or this machine's), e.g. before its helper
+/// binary is replaced. The next call starts a fresh one.
+pub(crate) fn disconnect(state: &AppState, session_id: Option<&str>) {
+    let agent = state
+        .inner
+        .lock()
+        .ok()
+        .and_then(|mut inner| inner.agents.remove(session_id.unwrap_or(LOCAL)));
+    if let Some(agent) = agent {
+        agent.shutdown();
+    }
+}
+
+/// Text for callers that expect a subcommand's stdout
+pub(crate) fn result_text(result: Value) -> String {
+    match result {
+        Value::String(text) => text,
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,298 @@
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Instant;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::commands::agent::stream_exec;
+use crate::commands::ssh::run_exec;
+use crate::commands::transfer::{LockedSession, PROGRESS_INTERVAL};
+use crate::utils::shell_quote;
+
+#[derive(Serialize, Default)]
+pub struct DownloadDirResult {
+    /// "tar+zstd", "tar+gzip", "tar" or "sftp"
+    pub method: String,
+    pub files: u64,
+    pub dirs: u64,
+    /// Bytes written locally
+    pub bytes: u64,
+    /// Archive entries that were not extracted (unsafe paths, devices, hard links)
+    pub skipped: Vec<String>,
+}
+
+// Prefix of the line carrying tar's exit status on the zstd path
+const TAR_STATUS_MARKER: &str = "jaterm-tar-exit:";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Codec {
+    Zstd,
+    Gzip,
+    Plain,
+}
+
+/// Read side of the pipe between the SSH channel and the extractor thread
+struct ChunkReader {
+    rx: mpsc::Receiver<Vec<u8>>,
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.pos >= self.chunk.len() {
+            match self.rx.recv() {
+                Ok(chunk) => {
+                    self.chunk = chunk;
+                    self.pos = 0;
+                }
+                Err(_) => return Ok(0),
+            }
+        }
+        let n = usize::min(buf.len(), self.chunk.len() - self.pos);
+        buf[..n].copy_from_slice(&self.chunk[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// A relative path that stays below the extraction root
+fn safe_entry_path(path: &Path) -> bool {
+    path.components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+/// A relative link target that does not climb above the extraction root
+/// from the directory the link lives in
+fn safe_link_target(link: &Path, target: &Path) -> bool {
+    if target.is_absolute() {
+        return false;
+    }
+    let mut depth = link
+        .components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .count() as i64
+        - 1;
+    for c in target.components() {
+        match c {
+            Component::ParentDir => depth -= 1,
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            _ => return false,
+        }
+        if depth < 0 {
+            return false;
+        }
+    }
+    true
+}
+
+fn extract(reader: ChunkReader, codec: Codec, root: &Path) -> Result<DownloadDirResult, String> {
+    let reader: Box<dyn Read> = match codec {
+        Codec::Zstd => {
+            Box::new(zstd::stream::read::Decoder::new(reader).map_err(|e| e.to_string())?)
+        }
+        Codec::Gzip => Box::new(flate2::read::GzDecoder::new(reader)),
+        Codec::Plain => Box::new(reader),
+    };
+    let mut archive = tar::Archive::new(reader);
+    archive.set_preserve_permissions(true);
+    archive.set_preserve_mtime(true);
+    archive.set_overwrite(true);
+
+    let mut result = DownloadDirResult::default();
+    for entry in archive.entries().map_err(|e| e.to_string())? {
+        let mut entry = entry.map_err(|e| e.to_string())?;
+        let path = entry.path().map_err(|e| e.to_string())?.to_path_buf();
+        let name = path.to_string_lossy().to_string();
+        // The "./" entry for the root itself
+        if !path.components().any(|c| matches!(c, Component::Normal(_))) {
+            continue;
+        }
+        if !safe_entry_path(&path) {
+            eprintln!("[archive] skipping unsafe path {}", name);
+            result.skipped.push(name);
+            continue;
+        }
+        let kind = entry.header().entry_type();
+        if kind.is_symlink() {
+            let target = entry
+                .link_name()
+                .map_err(|e| e.to_string())?
+                .map(|t| t.to_path_buf())
+                .unwrap_or_default();
+            if !safe_link_target(&path, &target) {
+                eprintln!(
+                    "[archive] skipping symlink {} -> {}",
+                    name,
+                    target.display()
+                );
+                result.skipped.push(name);
+                continue;
+            }
+        } else if !(kind.is_file() || kind.is_dir()) {
+            result.skipped.push(name);
+            continue;
+        }
+        // unpack_in re-checks that the final location is inside `root`
+        let unpacked = entry
+            .unpack_in(root)
+            .map_err(|e| format!("{}: {}", name, e))?;
+        if !unpacked {
+            result.skipped.push(name);
+        } else if kind.is_dir() {
+            result.dirs += 1;
+        } else {
+            result.files += 1;
+            result.bytes += entry.header().size().unwrap_or(0);
+        }
+    }
+    // Consume the end-of-archive padding so the remote side can finish writing
+    let _ = std::io::copy(&mut archive.into_inner(), &mut std::io::sink());
+    Ok(result)
+}
+
+/// Fail unless the remote tar (and zstd, when piped through it) exited
+/// cleanly. On the zstd path tar's own status comes from the marker line
+/// on stderr. GNU tar exits 1 when files changed while being read; the
+/// archive is still usable.
+fn check_tar_exit(code: Option<i32>, stderr: &str) -> Result<(), String> {
+    let mut statuses = vec![code];
+    let mut messages = Vec::new();
+    for line in stderr.lines().map(str::trim) {
+        match line.strip_prefix(TAR_STATUS_MARKER) {
+            Some(status) => statuses.push(status.parse().ok()),
+            None if !line.is_empty() => messages.push(line),
+            None => {}
+        }
+    }
+    if statuses.iter().all(|c| matches!(c, Some(0) | Some(1))) {
+        return Ok(());
+    }
+    Err(format!(
+        "remote tar failed: {}",
+        messages.last().copied().unwrap_or("unknown error")
+    ))
+}
+
+/// Pack `remote_dir` with tar on the server, stream it down and extract it
+/// into `local_root`. Returns None when the server has no tar, so the caller
+/// can fall back to copying file by file.
+pub(crate) fn download_dir_archive(
+    app: &AppHandle,
+    ls: &LockedSession,
+    remote_dir: &str,
+    local_root: &Path,
+    compression: &str,
+) -> Result<Option<DownloadDirResult>, String> {
+    let probe = ls.run(|| {
+        run_exec(
+            &ls.sess,
+            "for t in tar zstd gzip; do command -v $t >/dev/null 2>&1 && echo $t; done",
+        )
+    })?;
+    let tools: Vec<&str> = probe.stdout.lines().map(str::trim).collect();
+    if !tools.contains(&"tar") {
+        eprintln!("[archive] tar not available on remote");
+        return Ok(None);
+    }
+    let codec = match compression {
+        "zstd" if tools.contains(&"zstd") => Codec::Zstd,
+        "zstd" | "gzip" if tools.contains(&"gzip") => Codec::Gzip,
+        _ => Codec::Plain,
+    };
+    let dir = shell_quote(remote_dir);
+    let (command, method) = match codec {
+        // The pipeline exits with zstd's status, so report tar's on stderr
+        Codec::Zstd => (
+            format!(
+                "{{ tar -cf - -C {} .; echo {}$? >&2; }} | zstd -q -c",
+                dir, TAR_STATUS_MARKER
+            ),
+            "tar+zstd",
+        ),
+        Codec::Gzip => (format!("tar -czf - -C {} .", dir), "tar+gzip"),
+        Codec::Plain => (format!("tar -cf - -C {} .", dir), "tar"),
+    };
+    eprintln!("[archive] downloading {} as {}", remote_dir, method);
+
+    std::fs::create_dir_all(local_root).map_err(|e| e.to_string())?;
+    let (tx, rx) = mpsc::sync_channel::<Vec<u8>>(64);
+    let root: PathBuf = local_root.to_path_buf();
+    let extractor = thread::spawn(move || {
+        let reader = ChunkReader {
+            rx,
+            chunk: Vec::new(),
+            pos: 0,
+        };
+        extract(reader, codec, &root)
+    });
+
+    let mut received = 0u64;
+    let mut last_emit: Option<Instant> = None;
+    let never = AtomicBool::new(false);
+    let exit = stream_exec(ls, &command, &never, |data| {
+        received += data.len() as u64;
+        if last_emit.is_none_or(|t| t.elapsed() >= PROGRESS_INTERVAL) {
+            last_emit = Some(Instant::now());
+            let _ = app.emit(
+                crate::events::SSH_DOWNLOAD_PROGRESS,
+                &serde_json::json!({
+                    "path": remote_dir,
+                    "written": received,
+                    "total": 0
+                }),
+            );
+        }
+        // A failed extractor drops the receiver; stop reading then
+        tx.send(data.to_vec()).is_ok()
+    });
+    drop(tx);
+    let extracted = extractor
+        .join()
+        .map_err(|_| "archive extraction panicked".to_string())?;
+    let exit = exit?;
+
+    // A tar that failed to start sends an empty archive, so check it first
+    check_tar_exit(exit.code, &exit.stderr)?;
+    let mut result = extracted?;
+    result.method = method.to_string();
+    Ok(Some(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_safe_paths() {
+        assert!(safe_entry_path(Path::new("./a/b.txt")));
+        assert!(!safe_entry_path(Path::new("../etc/passwd")));
+        assert!(!safe_entry_path(Path::new("/etc/passwd")));
+        assert!(!safe_entry_path(Path::new("a/../../b")));
+
+        assert!(safe_link_target(Path::new("a/link"), Path::new("../b")));
+        assert!(!safe_link_target(Path::new("link"), Path::new("../b")));
+        assert!(!safe_link_target(Path::new("a/link"), Path::new("/etc")));
+    }
+
+    #[test]
+    fn test_tar_failure_behind_zstd() {
+        let missing = "tar: /nope: Cannot open: No such file or directory\n\
+                       tar: Error is not recoverable: exiting now\n\
+                       jaterm-tar-exit:2\n";
+        assert_eq!(
+            check_tar_exit(Some(0), missing),
+            Err("remote tar failed: tar: Error is not recoverable: exiting now".to_string())
+        );
+        assert!(check_tar_exit(Some(0), "jaterm-tar-exit:0\n").is_ok());
+        // Files changed while being read
+        assert!(check_tar_exit(Some(0), "jaterm-tar-exit:1\n").is_ok());
+        assert!(check_tar_exit(Some(2), "").is_err());
+        assert!(check_tar_exit(Some(1), "").is_ok());
+    }
+}
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::commands::agent::agent_exec;
+use crate::state::app_state::AppState;
+use crate::utils::shell_quote;
+
+// Prefer bash inside the container, falling back to sh on minimal images
+const DEFAULT_SHELL: &str = "sh -c 'command -v bash >/dev/null 2>&1 && exec bash || exec sh'";
+
+#[derive(Serialize, Deserialize)]
+pub struct ContainerPort {
+    pub container_port: u16,
+    /// Port published on the host, if any
+    pub host_port: Option<u16>,
+    pub host_ip: Option<String>,
+    pub protocol: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ContainerInfo {
+    pub id: String,
+    pub name: String,
+    pub image: String,
+    pub state: String,
+    pub status: String,
+    /// "docker" or "podman"
+    pub runtime: String,
+    pub ports: Vec<ContainerPort>,
+}
+
+/// List running containers (every container with `all`) on this machine or
+/// an SSH session's host, from the Docker or Podman engine
+#[tauri::command]
+pub async fn container_list(
+    state: State<'_, AppState>,
+    session_id: Option<String>,
+    all: Option<bool>,
+) -> Result<Vec<ContainerInfo>, String> {
+    let mut args = vec!["containers".to_string()];
+    if all.unwrap_or(false) {
+        args.push("--all".into());
+    }
+    let out = agent_exec(&state, session_id.as_deref(), &args)?;
+    serde_json::from_str(&out).map_err(|e| format!("invalid containers output: {}", e))
+}
+
+/// Open an interactive shell inside a container on an SSH session's host,
+/// as a new terminal channel like `ssh_open_shell`. `shell` defaults to bash
+/// when the image has it, sh otherwise.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn container_shell(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    session_id: String,
+    container: String,
+    runtime: Option<String>,
+    shell: Option<String>,
+    cols: Option<u16>,
+    rows: Option<u16>,
+) -> Result<String, String> {
+    let runtime = runtime.unwrap_or_else(|| "docker".into());
+    if runtime != "docker" && runtime != "podman" {
+        return Err(format!("unknown container runtime '{}'", runtime));
+    }
+    let shell = shell
+        .filter(|s| !s.is_empty())
+        .map(|s| shell_quote(&s))
+        .unwrap_or_else(|| DEFAULT_SHELL.to_string());
+    let command = format!("{} exec -it {} {}", runtime, shell_quote(&container), shell);
+    eprintln!("[containers] shell on {}: {}", session_id, command);
+    crate::commands::ssh::open_shell_channel(&app, &state, &session_id, Some(command), cols, rows)
+}
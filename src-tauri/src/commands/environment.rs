@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::State;
+
+use crate::commands::agent::agent_exec;
+use crate::state::app_state::AppState;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Distro {
+    pub id: String,
+    pub name: String,
+    pub version: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ShellInfo {
+    pub path: String,
+    pub name: String,
+    pub version: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EnvReport {
+    pub os: String,
+    pub distro: Option<Distro>,
+    pub kernel: Option<String>,
+    pub arch: String,
+    pub hostname: Option<String>,
+    pub user: Option<String>,
+    pub home: Option<String>,
+    pub shell: Option<ShellInfo>,
+    #[serde(default)]
+    pub package_managers: Vec<String>,
+    /// Tools found on PATH, by name
+    #[serde(default)]
+    pub tools: BTreeMap<String, String>,
+    /// Shells whose rc file has the cwd tracking (OSC 7) snippet
+    #[serde(default)]
+    pub osc7_shells: Vec<String>,
+    #[serde(default)]
+    pub osc7_installed: bool,
+}
+
+/// The report of this machine or an SSH session's host, from the session's
+/// cache unless `refresh` is set
+pub(crate) fn load(
+    state: &AppState,
+    session_id: Option<&str>,
+    refresh: bool,
+) -> Result<EnvReport, String> {
+    if let (Some(id), false) = (session_id, refresh) {
+        let inner = state.inner.lock().map_err(|_| "lock")?;
+        let s = inner.ssh.get(id).ok_or("ssh session not found")?;
+        if let Some(report) = &s.env {
+            return Ok(report.clone());
+        }
+    }
+    let out = agent_exec(state, session_id, &["env".to_string()])?;
+    let report: EnvReport =
+        serde_json::from_str(&out).map_err(|e| format!("invalid env output: {}", e))?;
+    if let Some(id) = session_id {
+        let mut inner = state.inner.lock().map_err(|_| "lock")?;
+        if let Some(s) = inner.ssh.get_mut(id) {
+            s.env = Some(report.clone());
+        }
+    }
+    Ok(report)
+}
+
+/// Whether the host's login shell could get cwd tracking but has none
+pub(crate) fn wants_shell_integration(report: &EnvReport) -> bool {
+    !report.osc7_installed
+        && report
+            .shell
+            .as_ref()
+            .is_some_and(|s| matches!(s.name.as_str(), "bash" | "zsh" | "fish"))
+}
+
+/// Describe this machine (no `session_id`) or an SSH session's host: distro,
+/// kernel, arch, login shell, package managers, tools on PATH and whether
+/// cwd tracking is installed. Reports of SSH hosts are cached on the session
+/// until `refresh` is set.
+#[tauri::command]
+pub async fn env_report(
+    state: State<'_, AppState>,
+    session_id: Option<String>,
+    refresh: Option<bool>,
+) -> Result<EnvReport, String> {
+    load(&state, session_id.as_deref(), refresh.unwrap_or(false))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ShellIntegrationResult {
+    pub shell: String,
+    pub path: String,
+    /// "installed", "updated", "unchanged", "removed" or "absent"
+    pub status: String,
+}
+
+fn shell_integration(
+    state: &AppState,
+    session_id: &str,
+    method: &str,
+    shells: Option<Vec<String>>,
+) -> Result<Vec<ShellIntegrationResult>, String> {
+    let mut args = vec![method.to_string()];
+    for shell in shells.into_iter().flatten() {
+        args.push("--shell".into());
+        args.push(shell);
+    }
+    eprintln!("[env] {} on {} {:?}", method, session_id, &args[1..]);
+    let out = agent_exec(state, Some(session_id), &args)?;
+    // The cached report says whether cwd tracking is installed
+    if let Ok(mut inner) = state.inner.lock() {
+        if let Some(s) = inner.ssh.get_mut(session_id) {
+            s.env = None;
+        }
+    }
+    serde_json::from_str(&out).map_err(|e| format!("invalid {} output: {}", method, e))
+}
+
+/// Add the cwd tracking (OSC 7) snippet to the rc files of `shells` on an SSH
+/// session's host, or of its login shell when omitted. Safe to repeat; new
+/// shells pick it up on their next start.
+#[tauri::command]
+pub async fn ssh_install_shell_integration(
+    state: State<'_, AppState>,
+    session_id: String,
+    shells: Option<Vec<String>>,
+) -> Result<Vec<ShellIntegrationResult>, String> {
+    shell_integration(&state, &session_id, "install-shell-integration", shells)
+}
+
+/// Remove the cwd tracking snippet from an SSH session's host, from every
+/// shell that has it when `shells` is omitted
+#[tauri::command]
+pub async fn ssh_uninstall_shell_integration(
+    state: State<'_, AppState>,
+    session_id: String,
+    shells: Option<Vec<String>>,
+) -> Result<Vec<ShellIntegrationResult>, String> {
+    shell_integration(&state, &session_id, "uninstall-shell-integration", shells)
+}
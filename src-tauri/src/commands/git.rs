@@ -1,5 +1,9 @@
 use git2::{BranchType, Repository, Status, StatusOptions};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::commands::agent::agent_exec;
+use crate::state::app_state::AppState;
 
 #[derive(Serialize)]
 pub struct GitStatus {
@@ -8,6 +12,15 @@ pub struct GitStatus {
     pub behind: u32,
     pub staged: u32,
     pub unstaged: u32,
+    /// Operation in progress: "merging", "rebasing", "cherry-picking",
+    /// "reverting", "bisecting" or "applying" (git am)
+    pub operation: Option<String>,
+    /// Rebase (or am) progress: the step being applied, of `total`
+    pub step: Option<u32>,
+    pub total: Option<u32>,
+    /// Files with unresolved conflicts; not counted as staged or unstaged
+    pub conflicted: u32,
+    pub conflicted_files: Vec<String>,
 }
 
 #[tauri::command]
@@ -22,6 +35,11 @@ pub async fn git_status(path: String) -> Result<GitStatus, String> {
                 behind: 0,
                 staged: 0,
                 unstaged: 0,
+                operation: None,
+                step: None,
+                total: None,
+                conflicted: 0,
+                conflicted_files: Vec::new(),
             });
         }
     };
@@ -55,6 +73,7 @@ pub async fn git_status(path: String) -> Result<GitStatus, String> {
     // Staged/unstaged counts via status
     let mut staged = 0u32;
     let mut unstaged = 0u32;
+    let mut conflicted_files = Vec::new();
     let mut opts = StatusOptions::new();
     opts.include_untracked(true)
         .recurse_untracked_dirs(true)
@@ -62,6 +81,10 @@ pub async fn git_status(path: String) -> Result<GitStatus, String> {
     if let Ok(statuses) = repo.statuses(Some(&mut opts)) {
         for s in statuses.iter() {
             let st = s.status();
+            if st.contains(Status::CONFLICTED) {
+                conflicted_files.push(s.path().unwrap_or_default().to_string());
+                continue;
+            }
             let is_staged = st.intersects(
                 Status::INDEX_NEW
                     | Status::INDEX_MODIFIED
@@ -85,11 +108,531 @@ pub async fn git_status(path: String) -> Result<GitStatus, String> {
         }
     }
 
+    let (operation, step, total) = operation(&repo);
     Ok(GitStatus {
         branch: branch_name,
         ahead,
         behind,
         staged,
         unstaged,
+        operation: operation.map(String::from),
+        step,
+        total,
+        conflicted: conflicted_files.len() as u32,
+        conflicted_files,
     })
 }
+
+/// The operation a repository is in the middle of, with its progress for
+/// rebases and `git am`
+fn operation(repo: &Repository) -> (Option<&'static str>, Option<u32>, Option<u32>) {
+    use git2::RepositoryState as RepoState;
+    let kind = match repo.state() {
+        RepoState::Clean => None,
+        RepoState::Merge => Some("merging"),
+        RepoState::Revert | RepoState::RevertSequence => Some("reverting"),
+        RepoState::CherryPick | RepoState::CherryPickSequence => Some("cherry-picking"),
+        RepoState::Bisect => Some("bisecting"),
+        RepoState::Rebase | RepoState::RebaseInteractive | RepoState::RebaseMerge => {
+            Some("rebasing")
+        }
+        RepoState::ApplyMailbox | RepoState::ApplyMailboxOrRebase => Some("applying"),
+    };
+    let number = |dir: &str, file: &str| -> Option<u32> {
+        let text = std::fs::read_to_string(repo.path().join(dir).join(file)).ok()?;
+        text.trim().parse().ok()
+    };
+    let (step, total) = match kind {
+        Some("rebasing") | Some("applying") => match number("rebase-merge", "msgnum") {
+            Some(step) => (Some(step), number("rebase-merge", "end")),
+            None => (
+                number("rebase-apply", "next"),
+                number("rebase-apply", "last"),
+            ),
+        },
+        _ => (None, None),
+    };
+    (kind, step, total)
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct GitLogOptions {
+    /// Commits to skip, for paging
+    pub skip: usize,
+    /// Page size (200 when 0)
+    pub limit: usize,
+    /// Revision to start from (HEAD when omitted)
+    pub rev: Option<String>,
+    /// Start from every branch, remote branch and tag instead
+    pub all: bool,
+    /// Only commits touching this path, relative to the repository root
+    pub path: Option<String>,
+    /// Only commits whose author name or email contains this
+    pub author: Option<String>,
+    /// Only commits whose message contains this
+    pub message: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GitRef {
+    /// Short name, e.g. "main", "origin/main", "v1.0" or "HEAD"
+    pub name: String,
+    /// "branch", "remote", "tag" or "head" (a detached HEAD)
+    pub kind: String,
+    /// The branch HEAD points at
+    pub is_head: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct GraphEdge {
+    /// Column in this row
+    pub from: usize,
+    /// Column in the next row
+    pub to: usize,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct GraphRow {
+    /// Column of the commit's node
+    pub column: usize,
+    /// Lines from this row down to the next one, passing lanes included
+    pub edges: Vec<GraphEdge>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GitCommit {
+    pub id: String,
+    pub short_id: String,
+    pub parents: Vec<String>,
+    pub author_name: String,
+    pub author_email: String,
+    /// Seconds since the epoch
+    pub author_time: i64,
+    pub committer_name: String,
+    pub committer_time: i64,
+    /// First line of the message
+    pub summary: String,
+    pub message: String,
+    /// Branches, tags and HEAD pointing at this commit
+    pub refs: Vec<GitRef>,
+    /// Lane layout; only for unfiltered logs, where every parent is listed
+    pub graph: Option<GraphRow>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GitLogPage {
+    pub commits: Vec<GitCommit>,
+    /// More commits follow; ask again with `skip` moved past this page
+    pub has_more: bool,
+    /// Commit HEAD points at
+    pub head: Option<String>,
+}
+
+/// One page of commit history of the repository at `path`, read by the
+/// helper on this machine or (with `session_id`) on an SSH session's host
+#[tauri::command]
+pub async fn git_log(
+    state: State<'_, AppState>,
+    path: String,
+    session_id: Option<String>,
+    options: Option<GitLogOptions>,
+) -> Result<GitLogPage, String> {
+    let opts = options.unwrap_or_default();
+    let mut args = vec!["git-log".to_string(), path];
+    args.push(format!("--skip={}", opts.skip));
+    if opts.limit > 0 {
+        args.push(format!("--limit={}", opts.limit));
+    }
+    if opts.all {
+        args.push("--all".into());
+    }
+    for (flag, value) in [
+        ("--rev", &opts.rev),
+        ("--path", &opts.path),
+        ("--author", &opts.author),
+        ("--message", &opts.message),
+    ] {
+        if let Some(v) = value {
+            args.push(format!("{}={}", flag, v));
+        }
+    }
+    let out = agent_exec(&state, session_id.as_deref(), &args)?;
+    serde_json::from_str(&out).map_err(|e| format!("invalid git-log output: {}", e))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BranchCommit {
+    pub id: String,
+    pub short_id: String,
+    pub summary: String,
+    pub author_name: String,
+    /// Seconds since the epoch
+    pub time: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GitBranch {
+    /// "main", or "origin/main" for remote branches
+    pub name: String,
+    /// "local" or "remote"
+    pub kind: String,
+    pub is_head: bool,
+    /// Upstream of a local branch, e.g. "origin/main"
+    pub upstream: Option<String>,
+    /// The upstream is configured but its remote branch is gone
+    pub upstream_gone: bool,
+    pub ahead: u32,
+    pub behind: u32,
+    pub last_commit: Option<BranchCommit>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GitCheckoutResult {
+    /// Local branch checked out (or that would have been)
+    pub branch: String,
+    /// False when local changes are in the way; nothing was touched then
+    pub checked_out: bool,
+    /// Files whose local changes the checkout would overwrite
+    pub conflicts: Vec<String>,
+}
+
+/// Branches of the repository at `path` with upstream, ahead/behind and last
+/// commit, read by the helper on this machine or (with `session_id`) on an
+/// SSH session's host
+#[tauri::command]
+pub async fn git_branches(
+    state: State<'_, AppState>,
+    path: String,
+    session_id: Option<String>,
+    remotes: Option<bool>,
+) -> Result<Vec<GitBranch>, String> {
+    let remotes = remotes.unwrap_or(true);
+    let mut args = vec!["git-branches".to_string(), path];
+    if !remotes {
+        args.push("--no-remotes".into());
+    }
+    let out = agent_exec(&state, session_id.as_deref(), &args)?;
+    serde_json::from_str(&out).map_err(|e| format!("invalid git-branches output: {}", e))
+}
+
+/// Create a branch at `start` (HEAD when omitted) without switching to it
+#[tauri::command]
+pub async fn git_branch_create(
+    state: State<'_, AppState>,
+    path: String,
+    session_id: Option<String>,
+    name: String,
+    start: Option<String>,
+) -> Result<(), String> {
+    let mut args = vec!["git-branch-create".to_string(), path, name];
+    if let Some(start) = start {
+        args.push(format!("--start={}", start));
+    }
+    agent_exec(&state, session_id.as_deref(), &args).map(|_| ())
+}
+
+/// Switch branches. Local changes in the way come back as `conflicts` with
+/// `checked_out` false rather than as an error.
+#[tauri::command]
+pub async fn git_checkout(
+    state: State<'_, AppState>,
+    path: String,
+    session_id: Option<String>,
+    branch: String,
+) -> Result<GitCheckoutResult, String> {
+    let args = vec!["git-checkout".to_string(), path, branch];
+    let out = agent_exec(&state, session_id.as_deref(), &args)?;
+    serde_json::from_str(&out).map_err(|e| format!("invalid git-checkout output: {}", e))
+}
+
+#[tauri::command]
+pub async fn git_branch_delete(
+    state: State<'_, AppState>,
+    path: String,
+    session_id: Option<String>,
+    name: String,
+    force: Option<bool>,
+) -> Result<(), String> {
+    let force = force.unwrap_or(false);
+    let mut args = vec!["git-branch-delete".to_string(), path, name];
+    if force {
+        args.push("--force".into());
+    }
+    agent_exec(&state, session_id.as_deref(), &args).map(|_| ())
+}
+
+/// Rename a local branch; `force` replaces an existing `new_name`
+#[tauri::command]
+pub async fn git_branch_rename(
+    state: State<'_, AppState>,
+    path: String,
+    session_id: Option<String>,
+    name: String,
+    new_name: String,
+    force: Option<bool>,
+) -> Result<(), String> {
+    let force = force.unwrap_or(false);
+    let mut args = vec!["git-branch-rename".to_string(), path, name, new_name];
+    if force {
+        args.push("--force".into());
+    }
+    agent_exec(&state, session_id.as_deref(), &args).map(|_| ())
+}
+
+/// Set (or with no `upstream`, unset) the branch a local branch tracks
+#[tauri::command]
+pub async fn git_branch_set_upstream(
+    state: State<'_, AppState>,
+    path: String,
+    session_id: Option<String>,
+    name: String,
+    upstream: Option<String>,
+) -> Result<(), String> {
+    let mut args = vec!["git-branch-set-upstream".to_string(), path, name];
+    if let Some(upstream) = upstream {
+        args.push(upstream);
+    }
+    agent_exec(&state, session_id.as_deref(), &args).map(|_| ())
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct GitDiffOptions {
+    /// HEAD against the index instead of the index against the working tree
+    pub staged: bool,
+    /// Only these paths, relative to the repository root
+    pub paths: Vec<String>,
+    /// Lines of context around hunks (3 when omitted)
+    pub context: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GitDiffLine {
+    /// "+", "-" or " "
+    pub origin: String,
+    pub old_lineno: Option<u32>,
+    pub new_lineno: Option<u32>,
+    /// Without the line break
+    pub content: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GitDiffHunk {
+    /// The "@@ -a,b +c,d @@ ..." line
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<GitDiffLine>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GitDiffFile {
+    /// None for added and untracked files
+    pub old_path: Option<String>,
+    /// None for deleted files
+    pub new_path: Option<String>,
+    /// "added", "deleted", "modified", "renamed", "copied", "typechange",
+    /// "untracked" or "conflicted"
+    pub status: String,
+    /// Binary files have no hunks
+    pub binary: bool,
+    pub additions: usize,
+    pub deletions: usize,
+    pub hunks: Vec<GitDiffHunk>,
+}
+
+/// Changes in the repository at `path` as files, hunks and lines: the
+/// working tree against the index, or with `staged` the index against HEAD
+#[tauri::command]
+pub async fn git_diff(
+    state: State<'_, AppState>,
+    path: String,
+    session_id: Option<String>,
+    options: Option<GitDiffOptions>,
+) -> Result<Vec<GitDiffFile>, String> {
+    let opts = options.unwrap_or_default();
+    let mut args = vec!["git-diff-files".to_string(), path];
+    if opts.staged {
+        args.push("--staged".into());
+    }
+    if let Some(context) = opts.context {
+        args.push(format!("--context={}", context));
+    }
+    args.extend(opts.paths);
+    let out = agent_exec(&state, session_id.as_deref(), &args)?;
+    serde_json::from_str(&out).map_err(|e| format!("invalid git-diff-files output: {}", e))
+}
+
+/// Stage, unstage or discard (`action`) hunk `hunk` of `file`, or only the
+/// `lines` of it, as listed by `git_diff` with the same `context`
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn git_apply_lines(
+    state: State<'_, AppState>,
+    path: String,
+    session_id: Option<String>,
+    action: String,
+    file: String,
+    hunk: usize,
+    lines: Option<Vec<usize>>,
+    context: Option<u32>,
+) -> Result<(), String> {
+    let mut args = vec![
+        "git-apply-lines".to_string(),
+        path,
+        action,
+        file,
+        hunk.to_string(),
+    ];
+    for line in lines.into_iter().flatten() {
+        args.push(format!("--line={}", line));
+    }
+    if let Some(context) = context {
+        args.push(format!("--context={}", context));
+    }
+    agent_exec(&state, session_id.as_deref(), &args).map(|_| ())
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GitStash {
+    /// Position in the stash list; 0 is the newest
+    pub index: usize,
+    pub id: String,
+    /// Branch the changes were stashed from
+    pub branch: Option<String>,
+    pub message: String,
+    /// Seconds since the epoch
+    pub time: i64,
+    /// Tracked files changed
+    pub files: usize,
+    /// Untracked files included with --include-untracked
+    pub untracked: usize,
+}
+
+/// Stash entries of the repository at `path` with branch, message and file
+/// counts, read by the helper on this machine or (with `session_id`) on an
+/// SSH session's host
+#[tauri::command]
+pub async fn git_stash_list(
+    state: State<'_, AppState>,
+    path: String,
+    session_id: Option<String>,
+) -> Result<Vec<GitStash>, String> {
+    let args = vec!["git-stash-list".to_string(), path];
+    let out = agent_exec(&state, session_id.as_deref(), &args)?;
+    serde_json::from_str(&out).map_err(|e| format!("invalid git-stash-list output: {}", e))
+}
+
+#[tauri::command]
+pub async fn git_stash_push(
+    state: State<'_, AppState>,
+    path: String,
+    session_id: Option<String>,
+    message: Option<String>,
+    include_untracked: Option<bool>,
+    keep_index: Option<bool>,
+) -> Result<(), String> {
+    let include_untracked = include_untracked.unwrap_or(false);
+    let keep_index = keep_index.unwrap_or(false);
+    let mut args = vec!["git-stash-push".to_string(), path];
+    if let Some(message) = message {
+        args.push(format!("--message={}", message));
+    }
+    if include_untracked {
+        args.push("--include-untracked".into());
+    }
+    if keep_index {
+        args.push("--keep-index".into());
+    }
+    agent_exec(&state, session_id.as_deref(), &args).map(|_| ())
+}
+
+fn stash_entry_command(
+    state: &AppState,
+    method: &str,
+    path: String,
+    session_id: Option<&str>,
+    index: Option<usize>,
+) -> Result<String, String> {
+    let args = vec![method.to_string(), path, index.unwrap_or(0).to_string()];
+    agent_exec(state, session_id, &args)
+}
+
+#[tauri::command]
+pub async fn git_stash_apply(
+    state: State<'_, AppState>,
+    path: String,
+    session_id: Option<String>,
+    index: Option<usize>,
+) -> Result<(), String> {
+    stash_entry_command(
+        &state,
+        "git-stash-apply",
+        path,
+        session_id.as_deref(),
+        index,
+    )
+    .map(|_| ())
+}
+
+#[tauri::command]
+pub async fn git_stash_pop(
+    state: State<'_, AppState>,
+    path: String,
+    session_id: Option<String>,
+    index: Option<usize>,
+) -> Result<(), String> {
+    stash_entry_command(&state, "git-stash-pop", path, session_id.as_deref(), index).map(|_| ())
+}
+
+#[tauri::command]
+pub async fn git_stash_drop(
+    state: State<'_, AppState>,
+    path: String,
+    session_id: Option<String>,
+    index: Option<usize>,
+) -> Result<(), String> {
+    stash_entry_command(&state, "git-stash-drop", path, session_id.as_deref(), index).map(|_| ())
+}
+
+/// The changes stash `index` holds, shaped like `git_diff`
+#[tauri::command]
+pub async fn git_stash_show(
+    state: State<'_, AppState>,
+    path: String,
+    session_id: Option<String>,
+    index: Option<usize>,
+) -> Result<Vec<GitDiffFile>, String> {
+    let out = stash_entry_command(&state, "git-stash-show", path, session_id.as_deref(), index)?;
+    serde_json::from_str(&out).map_err(|e| format!("invalid git-stash-show output: {}", e))
+}
+
+/// Continue, abort or skip (`action`) the merge, rebase, cherry-pick,
+/// revert or bisect the repository at `path` is in; `GitStatus::operation`
+/// says which. Returns git's output.
+#[tauri::command]
+pub async fn git_operation(
+    state: State<'_, AppState>,
+    path: String,
+    session_id: Option<String>,
+    action: String,
+) -> Result<String, String> {
+    let args = vec!["git-operation".to_string(), path, action];
+    agent_exec(&state, session_id.as_deref(), &args)
+}
+
+/// Take "ours" or "theirs" (`side`) for a conflicted file and mark it resolved
+#[tauri::command]
+pub async fn git_resolve(
+    state: State<'_, AppState>,
+    path: String,
+    session_id: Option<String>,
+    file: String,
+    side: String,
+) -> Result<(), String> {
+    let args = vec!["git-resolve".to_string(), path, file, side];
+    agent_exec(&state, session_id.as_deref(), &args).map(|_| ())
+}
@@ -9,16 +9,24 @@ use std::os::unix::fs::PermissionsExt;
 // Include the generated helper module from build.rs
 include!(concat!(env!("OUT_DIR"), "/helper_generated.rs"));
 
-// Function to get the helper binary for SSH deployment
-pub fn get_helper_binary() -> &'static [u8] {
-    HELPER_BINARY
+/// The embedded helper build for a remote host, by the output of
+/// `uname -s` and `uname -m`
+pub(crate) fn helper_for(os: &str, arch: &str) -> Option<&'static HelperTarget> {
+    let os = os.to_ascii_lowercase();
+    let arch = match arch {
+        "arm64" => "aarch64",
+        "amd64" => "x86_64",
+        other => other,
+    };
+    HELPER_TARGETS.iter().find(|t| t.os == os && t.arch == arch)
 }
 
-// Function to get the Linux helper binary for SSH deployment (if available)
-pub fn get_linux_helper_binary() -> Option<&'static [u8]> {
-    // HELPER_BINARY_LINUX is now an Option<&[u8]> defined by build.rs
-    // It's Some(&[...]) when Linux binary is available, None otherwise
-    HELPER_BINARY_LINUX
+/// Names of the platforms an embedded helper exists for, e.g. `linux/x86_64`
+pub(crate) fn helper_platforms() -> Vec<String> {
+    HELPER_TARGETS
+        .iter()
+        .map(|t| format!("{}/{}", t.os, t.arch))
+        .collect()
 }
 
 #[derive(Serialize)]
@@ -28,14 +36,16 @@ pub struct HelperStatus {
     pub path: Option<String>,
 }
 
-fn local_helper_path() -> Result<PathBuf, String> {
+pub(crate) fn local_helper_path() -> Result<PathBuf, String> {
     let home = dirs::home_dir().ok_or_else(|| "no home dir".to_string())?;
     let dir = home.join(HELPER_REL_DIR);
     Ok(dir.join(HELPER_NAME))
 }
 
 #[tauri::command]
-pub async fn helper_local_ensure() -> Result<HelperStatus, String> {
+pub async fn helper_local_ensure(
+    state: tauri::State<'_, crate::state::app_state::AppState>,
+) -> Result<HelperStatus, String> {
     let path = local_helper_path()?;
 
     // Check if exists and reports healthy with matching version
@@ -62,25 +72,29 @@ pub async fn helper_local_ensure() -> Result<HelperStatus, String> {
         fs::create_dir_all(dir).map_err(|e| e.to_string())?;
     }
 
-    // Write the embedded binary
+    // Write the embedded binary next to the old one and move it into place,
+    // since a running agent keeps the old binary busy
+    crate::commands::agent_rpc::disconnect(&state, None);
+    let tmp = path.with_extension("new");
     {
-        let mut f = fs::File::create(&path).map_err(|e| e.to_string())?;
+        let mut f = fs::File::create(&tmp).map_err(|e| e.to_string())?;
         f.write_all(HELPER_BINARY).map_err(|e| e.to_string())?;
     }
 
     // Set executable permissions
     #[cfg(unix)]
     {
-        let mut perms = fs::metadata(&path)
+        let mut perms = fs::metadata(&tmp)
             .map_err(|e| e.to_string())?
             .permissions();
         perms.set_mode(0o755);
-        fs::set_permissions(&path, perms).map_err(|e| e.to_string())?;
+        fs::set_permissions(&tmp, perms).map_err(|e| e.to_string())?;
     }
     #[cfg(windows)]
     {
         // Windows doesn't need explicit executable permissions for .exe files
     }
+    fs::rename(&tmp, &path).map_err(|e| e.to_string())?;
 
     // Verify health
     let res = helper_local_exec_internal(&path, &["health"]).map_err(|e| e)?;
@@ -114,7 +128,7 @@ pub struct ExecResult {
     pub exit_code: i32,
 }
 
-fn helper_local_exec_internal(path: &std::path::Path, args: &[&str]) -> Result<ExecResult, String> {
+pub(crate) fn helper_local_exec_internal(path: &std::path::Path, args: &[&str]) -> Result<ExecResult, String> {
     use std::process::Command;
     let output = Command::new(path)
         .args(args)
@@ -134,13 +148,10 @@ pub async fn helper_get_version() -> Result<String, String> {
 
 #[tauri::command]
 pub async fn helper_local_exec(
+    state: tauri::State<'_, crate::state::app_state::AppState>,
     command: String,
     args: Option<Vec<String>>,
 ) -> Result<ExecResult, String> {
-    let path = local_helper_path()?;
-    if !path.exists() {
-        return Err("helper not installed".into());
-    }
     let mut all = Vec::new();
     all.push(command);
     if let Some(rest) = args {
@@ -148,6 +159,32 @@ pub async fn helper_local_exec(
             all.push(a);
         }
     }
-    let refs: Vec<&str> = all.iter().map(|s| s.as_str()).collect();
-    helper_local_exec_internal(&path, &refs)
+    // Failures come back as a non-zero exit like before, with the message on stderr
+    Ok(match crate::commands::agent::agent_exec(&state, None, &all) {
+        Ok(stdout) => ExecResult {
+            stdout,
+            stderr: String::new(),
+            exit_code: 0,
+        },
+        Err(e) if e == "helper not installed" => return Err(e),
+        Err(e) => ExecResult {
+            stdout: String::new(),
+            stderr: e,
+            exit_code: 1,
+        },
+    })
+}
+
+/// Call a helper subcommand through the agent of an SSH session, or of this
+/// machine when `session_id` is omitted. Subcommands that print JSON return
+/// it parsed; the others return their output as a string.
+#[tauri::command]
+pub async fn helper_call(
+    state: tauri::State<'_, crate::state::app_state::AppState>,
+    session_id: Option<String>,
+    method: String,
+    args: Option<Vec<String>>,
+) -> Result<serde_json::Value, String> {
+    let agent = crate::commands::agent_rpc::connect(&state, session_id.as_deref())?;
+    agent.call(&method, &args.unwrap_or_default())
 }
@@ -0,0 +1,176 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Instant;
+
+use serde::Deserialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::commands::agent_rpc;
+use crate::commands::transfer::PROGRESS_INTERVAL;
+use crate::state::app_state::AppState;
+
+// Lines are batched into one event until this many are waiting
+const BATCH_SIZE: usize = 200;
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LogStreamOptions {
+    pub files: Option<Vec<String>>,
+    pub units: Option<Vec<String>>,
+    /// Docker/Podman container names or ids
+    pub containers: Option<Vec<String>>,
+    /// Lines of history per source (default 100)
+    pub lines: Option<usize>,
+    /// Keep following (default true)
+    pub follow: Option<bool>,
+    pub grep: Option<String>,
+    pub ignore_case: Option<bool>,
+    /// Minimum level: "error", "warning", "info" or "debug"
+    pub level: Option<String>,
+}
+
+fn agent_args(opts: &LogStreamOptions) -> Vec<String> {
+    let mut args = Vec::new();
+    for file in opts.files.iter().flatten() {
+        args.push("--file".into());
+        args.push(file.clone());
+    }
+    for unit in opts.units.iter().flatten() {
+        args.push("--unit".into());
+        args.push(unit.clone());
+    }
+    for container in opts.containers.iter().flatten() {
+        args.push("--container".into());
+        args.push(container.clone());
+    }
+    args.push("--lines".into());
+    args.push(opts.lines.unwrap_or(100).to_string());
+    if opts.follow.unwrap_or(true) {
+        args.push("--follow".into());
+    }
+    if let Some(grep) = opts.grep.as_ref().filter(|g| !g.is_empty()) {
+        args.push("--grep".into());
+        args.push(grep.clone());
+    }
+    if opts.ignore_case.unwrap_or(false) {
+        args.push("--ignore-case".into());
+    }
+    if let Some(level) = &opts.level {
+        args.push("--level".into());
+        args.push(level.clone());
+    }
+    args
+}
+
+/// Collect parsed lines and emit them in batches on LOG_VIEWER_LINES
+fn emit_batches(app: &AppHandle, stream_id: &str, rx: mpsc::Receiver<Value>) {
+    let mut batch = Vec::new();
+    let mut last_emit = Instant::now();
+    let flush = |batch: &mut Vec<Value>| {
+        if !batch.is_empty() {
+            let _ = app.emit(
+                crate::events::LOG_VIEWER_LINES,
+                &serde_json::json!({
+                    "streamId": stream_id,
+                    "lines": std::mem::take(batch)
+                }),
+            );
+        }
+    };
+    loop {
+        match rx.recv_timeout(PROGRESS_INTERVAL) {
+            Ok(line) => {
+                batch.push(line);
+                if batch.len() < BATCH_SIZE && last_emit.elapsed() < PROGRESS_INTERVAL {
+                    continue;
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+        flush(&mut batch);
+        last_emit = Instant::now();
+    }
+    flush(&mut batch);
+}
+
+/// Stream log files, journal units and container logs from this machine (no `session_id`) or
+/// an SSH session's host. Lines arrive in batches on LOG_VIEWER_LINES and the
+/// stream ends with LOG_VIEWER_END.
+#[tauri::command]
+pub async fn log_stream_start(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    session_id: Option<String>,
+    opts: LogStreamOptions,
+) -> Result<String, String> {
+    if opts.files.as_ref().is_none_or(|f| f.is_empty())
+        && opts.units.as_ref().is_none_or(|u| u.is_empty())
+        && opts.containers.as_ref().is_none_or(|c| c.is_empty())
+    {
+        return Err("choose at least one log file, unit or container".into());
+    }
+    let args = agent_args(&opts);
+    let stream_id = format!("logs_{}", nanoid::nanoid!(8));
+    let cancel = Arc::new(AtomicBool::new(false));
+    {
+        let mut inner = state.inner.lock().map_err(|_| "lock")?;
+        if let Some(id) = &session_id {
+            if !inner.ssh.contains_key(id) {
+                return Err("ssh session not found".into());
+            }
+        }
+        inner.log_streams.insert(stream_id.clone(), cancel.clone());
+    }
+    eprintln!(
+        "[logs] {} on {} {:?}",
+        stream_id,
+        session_id.as_deref().unwrap_or("local"),
+        args
+    );
+
+    let id = stream_id.clone();
+    thread::spawn(move || {
+        let (tx, rx) = mpsc::channel();
+        let app_for_batches = app.clone();
+        let id_for_batches = id.clone();
+        let emitter = thread::spawn(move || emit_batches(&app_for_batches, &id_for_batches, rx));
+        let result = agent_rpc::connect(&app.state::<AppState>(), session_id.as_deref())
+            .and_then(|agent| {
+                agent.call_streaming("logs", &args, Some(&cancel), |line| {
+                    let _ = tx.send(line);
+                })
+            });
+        drop(tx);
+        let cancelled = cancel.load(Ordering::Relaxed);
+        // A stopped stream ends with a "cancelled" error, which is no failure
+        let error = result.err().filter(|_| !cancelled);
+        let _ = emitter.join();
+        if let Some(e) = &error {
+            eprintln!("[logs] {} failed: {}", id, e);
+        }
+        let _ = app.emit(
+            crate::events::LOG_VIEWER_END,
+            &serde_json::json!({
+                "streamId": id,
+                "cancelled": cancelled,
+                "error": error
+            }),
+        );
+        if let Ok(mut inner) = app.state::<AppState>().inner.lock() {
+            inner.log_streams.remove(&id);
+        }
+    });
+    Ok(stream_id)
+}
+
+#[tauri::command]
+pub async fn log_stream_stop(state: State<'_, AppState>, stream_id: String) -> Result<(), String> {
+    let inner = state.inner.lock().map_err(|_| "lock")?;
+    if let Some(cancel) = inner.log_streams.get(&stream_id) {
+        cancel.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
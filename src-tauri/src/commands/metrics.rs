@@ -0,0 +1,98 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::commands::agent_rpc;
+use crate::state::app_state::AppState;
+
+const DEFAULT_INTERVAL_MS: u64 = 2000;
+const DEFAULT_TOP: usize = 10;
+
+fn run_metrics(app: &AppHandle, session_id: &str, args: &[String], cancel: &AtomicBool) {
+    let result = agent_rpc::connect(&app.state::<AppState>(), Some(session_id)).and_then(|agent| {
+        agent.call_streaming("metrics", args, Some(cancel), |metrics: Value| {
+            let _ = app.emit(
+                crate::events::SSH_METRICS,
+                &serde_json::json!({
+                    "sessionId": session_id,
+                    "metrics": metrics
+                }),
+            );
+        })
+    });
+    let error = result.err();
+    if let Some(e) = &error {
+        eprintln!("[metrics] {} stopped: {}", session_id, e);
+    }
+    // Tell the dashboard the stream ended, unless it asked for that itself
+    if !cancel.load(Ordering::Relaxed) {
+        let _ = app.emit(
+            crate::events::SSH_METRICS,
+            &serde_json::json!({
+                "sessionId": session_id,
+                "metrics": null,
+                "error": error.unwrap_or_else(|| "metrics stream ended".into())
+            }),
+        );
+    }
+}
+
+/// Stream host metrics from the helper agent as SSH_METRICS events every
+/// `interval_ms`, replacing any metrics stream already running for the session
+#[tauri::command]
+pub async fn ssh_metrics_start(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    session_id: String,
+    interval_ms: Option<u64>,
+    top: Option<usize>,
+) -> Result<(), String> {
+    let cancel = Arc::new(AtomicBool::new(false));
+    {
+        let mut inner = state.inner.lock().map_err(|_| "lock")?;
+        if !inner.ssh.contains_key(&session_id) {
+            return Err("ssh session not found".into());
+        }
+        if let Some(old) = inner.metrics.insert(session_id.clone(), cancel.clone()) {
+            old.store(true, Ordering::Relaxed);
+        }
+    }
+    let args = vec![
+        "--watch".to_string(),
+        "--interval-ms".into(),
+        interval_ms.unwrap_or(DEFAULT_INTERVAL_MS).to_string(),
+        "--top".into(),
+        top.unwrap_or(DEFAULT_TOP).to_string(),
+    ];
+    eprintln!("[metrics] {} starting", session_id);
+
+    let app_for_thread = app.clone();
+    thread::spawn(move || {
+        run_metrics(&app_for_thread, &session_id, &args, &cancel);
+        if let Ok(mut inner) = app_for_thread.state::<AppState>().inner.lock() {
+            if inner
+                .metrics
+                .get(&session_id)
+                .is_some_and(|c| Arc::ptr_eq(c, &cancel))
+            {
+                inner.metrics.remove(&session_id);
+            }
+        }
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn ssh_metrics_stop(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<(), String> {
+    let mut inner = state.inner.lock().map_err(|_| "lock")?;
+    if let Some(cancel) = inner.metrics.remove(&session_id) {
+        cancel.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
@@ -1,9 +1,21 @@
+pub mod agent;
+pub mod agent_rpc;
 pub mod ai;
+pub mod archive;
 pub mod app;
+pub mod containers;
 pub mod encryption;
+pub mod environment;
 pub mod git;
 pub mod helper;
 pub mod keygen;
+pub mod logs;
+pub mod metrics;
+pub mod processes;
 pub mod pty;
+pub mod search;
 pub mod ssh;
+pub mod sync;
+pub mod transfer;
+pub mod watchdog;
 pub mod watcher;
@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::commands::agent::agent_exec;
+use crate::state::app_state::AppState;
+
+#[derive(Serialize, Deserialize)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub ppid: u32,
+    pub user: String,
+    pub name: String,
+    pub state: String,
+    /// Percentage of one core
+    pub cpu: f64,
+    /// Percentage of physical memory
+    pub mem: f64,
+    pub rss: u64,
+    /// Seconds since the Unix epoch
+    pub start_time: u64,
+    pub cmdline: String,
+    #[serde(default)]
+    pub children: Vec<ProcessInfo>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct KillResult {
+    pub pid: u32,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+/// List processes on this machine (no `session_id`) or on an SSH session's
+/// host, as a tree unless `flat` is set. `filter` matches name or command line.
+#[tauri::command]
+pub async fn process_list(
+    state: State<'_, AppState>,
+    session_id: Option<String>,
+    filter: Option<String>,
+    flat: Option<bool>,
+) -> Result<Vec<ProcessInfo>, String> {
+    let mut args = vec!["ps".to_string()];
+    if let Some(filter) = filter.filter(|f| !f.is_empty()) {
+        args.push("--filter".into());
+        args.push(filter);
+    }
+    if flat.unwrap_or(false) {
+        args.push("--flat".into());
+    }
+    let out = agent_exec(&state, session_id.as_deref(), &args)?;
+    serde_json::from_str(&out).map_err(|e| format!("invalid ps output: {}", e))
+}
+
+/// Send a signal (default TERM) to processes, locally or on an SSH session's host
+#[tauri::command]
+pub async fn process_kill(
+    state: State<'_, AppState>,
+    session_id: Option<String>,
+    pids: Vec<u32>,
+    signal: Option<String>,
+) -> Result<Vec<KillResult>, String> {
+    if pids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let signal = signal.unwrap_or_else(|| "TERM".into());
+    eprintln!(
+        "[process] kill -{} {:?} on {}",
+        signal,
+        pids,
+        session_id.as_deref().unwrap_or("local")
+    );
+    let mut args = vec!["kill".to_string(), "--signal".into(), signal];
+    args.extend(pids.iter().map(|p| p.to_string()));
+    let out = agent_exec(&state, session_id.as_deref(), &args)?;
+    serde_json::from_str(&out).map_err(|e| format!("invalid kill output: {}", e))
+}
@@ -0,0 +1,410 @@
+use std::collections::VecDeque;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::commands::agent_rpc::{self, Agent};
+use crate::commands::transfer::{LockedSession, PROGRESS_INTERVAL};
+use crate::state::app_state::AppState;
+
+const DEFAULT_MAX_RESULTS: usize = 1000;
+// Content search over SFTP downloads every candidate, so keep it to small files
+const SFTP_MAX_FILE_SIZE: u64 = 1024 * 1024;
+const AGENT_MAX_FILE_SIZE: u64 = 4 * 1024 * 1024;
+const MAX_TEXT: usize = 300;
+// Pseudo filesystems that are slow or endless to walk
+const SKIP_DIRS: &[&str] = &["/proc", "/sys", "/dev"];
+// Results are batched into one event until this many are waiting
+const BATCH_SIZE: usize = 50;
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchOptions {
+    pub regex: Option<bool>,
+    pub ignore_case: Option<bool>,
+    pub hidden: Option<bool>,
+    pub max_results: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SearchMatch {
+    pub path: String,
+    #[serde(rename = "type")]
+    pub file_type: String,
+    pub size: u64,
+    pub line: Option<u64>,
+    pub text: Option<String>,
+}
+
+struct Query {
+    root: String,
+    name: Option<String>,
+    content: Option<String>,
+    regex: bool,
+    ignore_case: bool,
+    hidden: bool,
+    max_results: usize,
+}
+
+struct Batcher<'a> {
+    app: &'a AppHandle,
+    search_id: &'a str,
+    batch: Vec<SearchMatch>,
+    last_emit: Instant,
+    found: usize,
+    max_results: usize,
+}
+
+impl Batcher<'_> {
+    fn full(&self) -> bool {
+        self.found >= self.max_results
+    }
+
+    fn push(&mut self, m: SearchMatch) {
+        if self.full() {
+            return;
+        }
+        self.found += 1;
+        self.batch.push(m);
+        if self.batch.len() >= BATCH_SIZE || self.last_emit.elapsed() >= PROGRESS_INTERVAL {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        self.last_emit = Instant::now();
+        if self.batch.is_empty() {
+            return;
+        }
+        let _ = self.app.emit(
+            crate::events::SSH_SEARCH_RESULTS,
+            &serde_json::json!({
+                "searchId": self.search_id,
+                "results": std::mem::take(&mut self.batch)
+            }),
+        );
+    }
+}
+
+struct Outcome {
+    via: &'static str,
+    truncated: bool,
+    scanned: u64,
+}
+
+fn search_agent(
+    agent: &Agent,
+    q: &Query,
+    cancel: &AtomicBool,
+    out: &mut Batcher,
+) -> Result<Outcome, String> {
+    let mut args = vec![
+        q.root.clone(),
+        "--max-results".into(),
+        q.max_results.to_string(),
+        "--max-file-size".into(),
+        AGENT_MAX_FILE_SIZE.to_string(),
+    ];
+    if let Some(name) = &q.name {
+        args.push("--name".into());
+        args.push(name.clone());
+    }
+    if let Some(content) = &q.content {
+        args.push("--content".into());
+        args.push(content.clone());
+    }
+    for (flag, on) in [
+        ("--regex", q.regex),
+        ("--ignore-case", q.ignore_case),
+        ("--hidden", q.hidden),
+    ] {
+        if on {
+            args.push(flag.into());
+        }
+    }
+
+    let mut summary: Option<serde_json::Value> = None;
+    let result = agent.call_streaming("search", &args, Some(cancel), |item| {
+        if item.get("done").is_some() {
+            summary = Some(item);
+        } else if let Ok(m) = serde_json::from_value::<SearchMatch>(item) {
+            out.push(m);
+        }
+    });
+    match result {
+        Err(_) if cancel.load(Ordering::Relaxed) => {}
+        Err(e) => return Err(e),
+        Ok(_) => {}
+    }
+    let summary = summary.unwrap_or_default();
+    Ok(Outcome {
+        via: "agent",
+        truncated: summary["truncated"].as_bool().unwrap_or(false),
+        scanned: summary["scanned"].as_u64().unwrap_or(0),
+    })
+}
+
+fn content_matcher(q: &Query) -> Result<Option<regex::Regex>, String> {
+    q.content
+        .as_deref()
+        .map(|p| {
+            let pattern = if q.regex {
+                p.to_string()
+            } else {
+                regex::escape(p)
+            };
+            regex::RegexBuilder::new(&pattern)
+                .case_insensitive(q.ignore_case)
+                .build()
+                .map_err(|e| format!("invalid content pattern '{}': {}", p, e))
+        })
+        .transpose()
+}
+
+/// Breadth-first walk over SFTP readdir for hosts without the agent
+fn search_sftp(
+    ls: &LockedSession,
+    q: &Query,
+    cancel: &AtomicBool,
+    out: &mut Batcher,
+) -> Result<Outcome, String> {
+    let name = q
+        .name
+        .as_deref()
+        .map(|p| {
+            globset::GlobBuilder::new(p)
+                .case_insensitive(q.ignore_case)
+                .literal_separator(p.contains('/'))
+                .build()
+                .map(|g| g.compile_matcher())
+                .map_err(|e| format!("invalid name pattern '{}': {}", p, e))
+        })
+        .transpose()?;
+    let name_is_path = q.name.as_deref().is_some_and(|p| p.contains('/'));
+    let content = content_matcher(q)?;
+
+    let sftp = ls.run(|| ls.sess.sftp()).map_err(|e| e.to_string())?;
+    // SFTP resolves relative paths against the login directory
+    let rel_root = match q.root.as_str() {
+        "~" | "" => ".",
+        r => r.strip_prefix("~/").unwrap_or(r),
+    };
+    let root = match ls.run(|| sftp.realpath(Path::new(rel_root))) {
+        Ok(root) => root,
+        Err(e) => {
+            ls.run(move || drop(sftp));
+            return Err(format!("{}: {}", q.root, e));
+        }
+    };
+
+    let mut scanned = 0u64;
+    let mut queue: VecDeque<PathBuf> = VecDeque::from([root.clone()]);
+    while let Some(dir) = queue.pop_front() {
+        if cancel.load(Ordering::Relaxed) || out.full() {
+            break;
+        }
+        let mut entries = match ls.run(|| sftp.readdir(&dir)) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        for (path, st) in entries {
+            if out.full() || cancel.load(Ordering::Relaxed) {
+                break;
+            }
+            let file_name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if file_name == "." || file_name == ".." || (!q.hidden && file_name.starts_with('.')) {
+                continue;
+            }
+            scanned += 1;
+            let matched = match &name {
+                None => true,
+                Some(glob) if name_is_path => {
+                    glob.is_match(path.strip_prefix(&root).unwrap_or(&path))
+                }
+                Some(glob) => glob.is_match(&file_name),
+            };
+            let file_type = st.file_type();
+            let size = st.size.unwrap_or(0);
+            let path_str = path.to_string_lossy().to_string();
+
+            if file_type.is_dir() {
+                if matched && content.is_none() && name.is_some() {
+                    out.push(SearchMatch {
+                        path: path_str.clone(),
+                        file_type: "dir".into(),
+                        size: 0,
+                        line: None,
+                        text: None,
+                    });
+                }
+                if !SKIP_DIRS.iter().any(|d| path == Path::new(d)) {
+                    queue.push_back(path);
+                }
+            } else if !matched {
+                continue;
+            } else if let Some(re) = &content {
+                if !file_type.is_file() || size > SFTP_MAX_FILE_SIZE {
+                    continue;
+                }
+                let data = ls.run(|| -> Result<Vec<u8>, String> {
+                    let mut file = sftp.open(&path).map_err(|e| e.to_string())?;
+                    let mut data = Vec::new();
+                    file.read_to_end(&mut data).map_err(|e| e.to_string())?;
+                    Ok(data)
+                });
+                let Ok(data) = data else { continue };
+                if data.iter().take(8192).any(|b| *b == 0) {
+                    continue;
+                }
+                for (idx, line) in String::from_utf8_lossy(&data).lines().enumerate() {
+                    if out.full() {
+                        break;
+                    }
+                    if re.is_match(line) {
+                        out.push(SearchMatch {
+                            path: path_str.clone(),
+                            file_type: "file".into(),
+                            size,
+                            line: Some(idx as u64 + 1),
+                            text: Some(line.chars().take(MAX_TEXT).collect()),
+                        });
+                    }
+                }
+            } else {
+                out.push(SearchMatch {
+                    path: path_str,
+                    file_type: if file_type.is_symlink() {
+                        "symlink"
+                    } else {
+                        "file"
+                    }
+                    .into(),
+                    size,
+                    line: None,
+                    text: None,
+                });
+            }
+        }
+    }
+    ls.run(move || drop(sftp));
+    Ok(Outcome {
+        via: "sftp",
+        truncated: out.full(),
+        scanned,
+    })
+}
+
+fn run_search(app: &AppHandle, search_id: &str, session_id: &str, q: Query, cancel: &AtomicBool) {
+    let mut out = Batcher {
+        app,
+        search_id,
+        batch: Vec::new(),
+        last_emit: Instant::now(),
+        found: 0,
+        max_results: q.max_results,
+    };
+    let state = app.state::<AppState>();
+    let result = match agent_rpc::connect(&state, Some(session_id)) {
+        Ok(agent) => search_agent(&agent, &q, cancel, &mut out),
+        Err(e) => {
+            eprintln!("[search] agent unavailable ({}), walking over sftp", e);
+            LockedSession::for_session(&state, session_id)
+                .and_then(|ls| search_sftp(&ls, &q, cancel, &mut out))
+        }
+    };
+    out.flush();
+
+    let cancelled = cancel.load(Ordering::Relaxed);
+    let payload = match result {
+        Ok(o) => serde_json::json!({
+            "searchId": search_id,
+            "via": o.via,
+            "found": out.found,
+            "truncated": o.truncated,
+            "scanned": o.scanned,
+            "cancelled": cancelled,
+            "error": null
+        }),
+        Err(e) => serde_json::json!({
+            "searchId": search_id,
+            "found": out.found,
+            "cancelled": cancelled,
+            "error": e
+        }),
+    };
+    let _ = app.emit(crate::events::SSH_SEARCH_DONE, &payload);
+    if let Ok(mut inner) = app.state::<AppState>().inner.lock() {
+        inner.searches.remove(search_id);
+    }
+}
+
+/// Start a remote search and return its id. Matches arrive in batches on
+/// SSH_SEARCH_RESULTS, followed by a single SSH_SEARCH_DONE.
+#[tauri::command]
+pub async fn ssh_search_start(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    session_id: String,
+    root: String,
+    name: Option<String>,
+    content: Option<String>,
+    opts: Option<SearchOptions>,
+) -> Result<String, String> {
+    let opts = opts.unwrap_or_default();
+    let name = name.filter(|n| !n.is_empty());
+    let content = content.filter(|c| !c.is_empty());
+    if name.is_none() && content.is_none() {
+        return Err("enter a name pattern or text to search for".into());
+    }
+    let q = Query {
+        root,
+        name,
+        content,
+        regex: opts.regex.unwrap_or(false),
+        ignore_case: opts.ignore_case.unwrap_or(true),
+        hidden: opts.hidden.unwrap_or(false),
+        max_results: opts.max_results.unwrap_or(DEFAULT_MAX_RESULTS),
+    };
+    // Report bad patterns up front rather than as a failed search
+    content_matcher(&q)?;
+
+    let search_id = format!("search_{}", nanoid::nanoid!(8));
+    let cancel = Arc::new(AtomicBool::new(false));
+    {
+        let mut inner = state.inner.lock().map_err(|_| "lock")?;
+        if !inner.ssh.contains_key(&session_id) {
+            return Err("ssh session not found".into());
+        }
+        inner.searches.insert(search_id.clone(), cancel.clone());
+    }
+    eprintln!(
+        "[search] {} root={} name={:?} content={:?}",
+        search_id, q.root, q.name, q.content
+    );
+
+    let id = search_id.clone();
+    thread::spawn(move || run_search(&app, &id, &session_id, q, &cancel));
+    Ok(search_id)
+}
+
+#[tauri::command]
+pub async fn ssh_search_cancel(
+    state: State<'_, AppState>,
+    search_id: String,
+) -> Result<(), String> {
+    let inner = state.inner.lock().map_err(|_| "lock")?;
+    if let Some(cancel) = inner.searches.get(&search_id) {
+        cancel.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
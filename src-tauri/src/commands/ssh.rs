@@ -1,6 +1,7 @@
 use base64::Engine;
 use serde::Deserialize;
 use serde::Serialize;
+use std::collections::HashMap;
 use std::io::{Read, Write};
 use std::net::TcpStream;
 use std::time::Duration;
@@ -417,49 +418,7 @@ pub async fn ssh_connect(
     sess.set_blocking(false);
     tcp.set_nonblocking(true).map_err(|e| format!("set_nonblocking: {e}"))?;
 
-    // Start watchdog for git status and port detection (non-blocking)
-    let session_id_for_watchdog = format!("ssh_{}", nanoid::nanoid!(8));
-    let app_for_watchdog = app.clone();
-    let host_for_watchdog = host_normalized.clone();
-    let port_for_watchdog = profile.port;
-    let user_for_watchdog = profile.user.clone();
-    let session_id_clone = session_id_for_watchdog.clone();
-
-    std::thread::spawn(move || {
-        std::thread::sleep(std::time::Duration::from_millis(500)); // Small delay to let connection establish
-
-        // Initial port detection
-        let output = std::process::Command::new("ssh")
-            .arg("-p")
-            .arg(port_for_watchdog.to_string())
-            .arg("-o")
-            .arg("StrictHostKeyChecking=no")
-            .arg("-o")
-            .arg("UserKnownHostsFile=/dev/null")
-            .arg(format!("{}@{}", user_for_watchdog, host_for_watchdog))
-            .arg("~/.jaterm-helper/jaterm-agent detect-ports 2>/dev/null || echo '[]'")
-            .output();
-
-        if let Ok(output) = output {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            if let Ok(ports) = serde_json::from_str::<Vec<u16>>(&stdout) {
-                eprintln!("[ssh] Detected {} open ports on remote", ports.len());
-                // Emit event with detected ports
-                let _ = app_for_watchdog.emit(
-                    "ssh_detected_ports",
-                    serde_json::json!({
-                      "sessionId": session_id_clone,
-                      "ports": ports
-                    }),
-                );
-            }
-        }
-
-        // TODO: Set up periodic watchdog polling for git + ports
-        // This would be better handled by the frontend polling mechanism
-    });
-
-    let id = session_id_for_watchdog;
+    let id = format!("ssh_{}", nanoid::nanoid!(8));
     {
         let mut inner = state.inner.lock().map_err(|_| "lock state")?;
         inner.ssh.insert(
@@ -474,9 +433,15 @@ pub async fn ssh_connect(
                 user: profile.user.clone(),
                 auth: profile.auth.clone(),
                 is_primary: true, // First connection is always primary
+                owner_names: None,
+                env: None,
             },
         );
     }
+    // Stream git status, port and cwd changes from the helper agent, if installed
+    if let Err(e) = crate::commands::watchdog::start(&app, &state, &id, None, None) {
+        eprintln!("[ssh] watchdog not started: {}", e);
+    }
     Ok(id)
 }
 
@@ -512,67 +477,344 @@ pub struct SftpEntry {
     pub name: String,
     pub path: String,
     pub is_dir: bool,
+    pub size: Option<u64>,
+    pub mtime: Option<u64>,
+    pub atime: Option<u64>,
+    pub mode: Option<u32>,
+    /// ls-style permission string, e.g. "drwxr-xr-x"
+    pub permissions: Option<String>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    pub owner: Option<String>,
+    pub group: Option<String>,
+    /// "file", "dir", "symlink", "fifo", "socket", "char", "block" or "unknown"
+    pub file_type: String,
+    pub is_symlink: bool,
+    pub link_target: Option<String>,
+    /// For symlinks: whether the link resolves to a directory
+    pub link_is_dir: Option<bool>,
 }
 
 use std::path::Path;
 
+/// Run an SFTP operation on a session: serializes on the session lock and keeps
+/// the session in blocking mode for the duration of `f`.
+pub(crate) fn with_sftp<T, F>(
+    state: &crate::state::app_state::AppState,
+    session_id: &str,
+    f: F,
+) -> Result<T, String>
+where
+    F: FnOnce(&ssh2::Session, &ssh2::Sftp) -> Result<T, String>,
+{
+    let mut inner = state.inner.lock().map_err(|_| "lock")?;
+    let s = inner
+        .ssh
+        .get_mut(session_id)
+        .ok_or("ssh session not found")?;
+
+    // Get session lock to serialize operations
+    let session_lock = s.lock.clone();
+    let _guard = session_lock.lock().unwrap();
+
+    // SFTP operations need blocking mode temporarily
+    s.sess.set_blocking(true);
+    let result = match s.sess.sftp() {
+        Ok(sftp) => f(&s.sess, &sftp),
+        Err(e) => Err(e.to_string()),
+    };
+    s.sess.set_blocking(false); // Restore non-blocking
+    result
+}
+
+/// Result of a single-file read, write or download
+#[derive(Serialize)]
+pub struct SftpTransferResult {
+    /// "sftp", or "scp" when the server has no SFTP subsystem
+    pub transport: &'static str,
+    pub bytes: u64,
+}
+
+#[derive(Serialize)]
+pub struct SftpReadResult {
+    pub data_b64: String,
+    pub transport: &'static str,
+}
+
+/// Open an SFTP channel, or None when the server refuses the SFTP subsystem
+/// (no `Subsystem sftp` in sshd_config, some embedded devices) so the caller
+/// can fall back to SCP. Must be called with the session in blocking mode.
+fn open_sftp(sess: &ssh2::Session) -> Result<Option<ssh2::Sftp>, String> {
+    match sess.sftp() {
+        Ok(sftp) => Ok(Some(sftp)),
+        // -21 is LIBSSH2_ERROR_CHANNEL_FAILURE, raised when the subsystem request is denied
+        Err(e)
+            if e.code() == ssh2::ErrorCode::Session(-21)
+                || e.message().contains("SFTP subsystem") =>
+        {
+            eprintln!("[ssh] sftp unavailable ({}), using scp", e);
+            Ok(None)
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn close_scp_channel(mut channel: ssh2::Channel) -> Result<(), String> {
+    channel.send_eof().map_err(|e| e.to_string())?;
+    channel.wait_eof().map_err(|e| e.to_string())?;
+    channel.close().map_err(|e| e.to_string())?;
+    channel.wait_close().map_err(|e| e.to_string())
+}
+
+/// Copy a remote file into `out` over SCP. Must be called with the session in
+/// blocking mode.
+fn scp_read(sess: &ssh2::Session, remote_path: &str, out: &mut dyn Write) -> Result<u64, String> {
+    let (mut channel, stat) = sess
+        .scp_recv(Path::new(remote_path))
+        .map_err(|e| format!("scp: {}", e))?;
+    let size = stat.size();
+    let mut buf = [0u8; 65536];
+    let mut read = 0u64;
+    // The protocol sends a status byte after the data, so stop at the announced size
+    while read < size {
+        let want = u64::min(buf.len() as u64, size - read) as usize;
+        let n = channel.read(&mut buf[..want]).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        out.write_all(&buf[..n]).map_err(|e| e.to_string())?;
+        read += n as u64;
+    }
+    close_scp_channel(channel)?;
+    if read < size {
+        return Err(format!("scp: transfer ended after {} of {} bytes", read, size));
+    }
+    Ok(read)
+}
+
+/// Read a whole remote file into memory
+fn sftp_read_all(sftp: &ssh2::Sftp, path: &Path) -> Result<Vec<u8>, String> {
+    let mut file = sftp.open(path).map_err(|e| e.to_string())?;
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf).map_err(|e| e.to_string())?;
+    Ok(buf)
+}
+
+/// Rename `from` over `to`, replacing an existing target. SFTPv3 servers such as
+/// OpenSSH refuse to overwrite on rename, so fall back to `mv -f` over exec.
+pub(crate) fn sftp_rename_overwrite(
+    sess: &ssh2::Session,
+    sftp: &ssh2::Sftp,
+    from: &Path,
+    to: &Path,
+) -> Result<(), String> {
+    let flags = ssh2::RenameFlags::OVERWRITE | ssh2::RenameFlags::ATOMIC | ssh2::RenameFlags::NATIVE;
+    if sftp.rename(from, to, Some(flags)).is_ok() {
+        return Ok(());
+    }
+    let cmd = format!(
+        "mv -f -- {} {}",
+        crate::utils::shell_quote(&from.to_string_lossy()),
+        crate::utils::shell_quote(&to.to_string_lossy())
+    );
+    let res = run_exec(sess, &cmd)?;
+    if res.exit_code != 0 {
+        return Err(format!("rename failed: {}", res.stderr.trim()));
+    }
+    Ok(())
+}
+
+/// Write a remote file atomically: upload into a temp file next to the target,
+/// give it the original mode (and owner where permitted), then rename it over
+/// the target. Returns the stat of the written file.
+fn sftp_write_atomic<F>(
+    sess: &ssh2::Session,
+    sftp: &ssh2::Sftp,
+    target: &Path,
+    data: &[u8],
+    mut progress: F,
+) -> Result<ssh2::FileStat, String>
+where
+    F: FnMut(usize, usize),
+{
+    let original = sftp.stat(target).ok();
+    let name = target
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or("invalid remote path")?;
+    let tmp = target.with_file_name(format!(".{}.jaterm-{}.tmp", name, nanoid::nanoid!(6)));
+    let mode = original
+        .as_ref()
+        .and_then(|st| st.perm)
+        .map(|p| (p & 0o7777) as i32)
+        .unwrap_or(0o644);
+
+    let result = (|| -> Result<(), String> {
+        let mut file = sftp
+            .open_mode(
+                &tmp,
+                ssh2::OpenFlags::WRITE | ssh2::OpenFlags::CREATE | ssh2::OpenFlags::EXCLUSIVE,
+                mode,
+                ssh2::OpenType::File,
+            )
+            .map_err(|e| format!("create temp file: {}", e))?;
+        let total = data.len();
+        let mut written = 0usize;
+        while written < total {
+            let end = usize::min(written + 32768, total);
+            file.write_all(&data[written..end])
+                .map_err(|e| format!("Write failed: {}", e))?;
+            written = end;
+            progress(written, total);
+        }
+        drop(file);
+
+        if let Some(orig) = &original {
+            let perm = orig.perm.map(|p| p & 0o7777);
+            let with_owner = ssh2::FileStat {
+                size: None,
+                uid: orig.uid,
+                gid: orig.gid,
+                perm,
+                atime: None,
+                mtime: None,
+            };
+            // chown is usually only allowed for root; keep at least the mode
+            if sftp.setstat(&tmp, with_owner).is_err() {
+                let mode_only = ssh2::FileStat {
+                    size: None,
+                    uid: None,
+                    gid: None,
+                    perm,
+                    atime: None,
+                    mtime: None,
+                };
+                sftp.setstat(&tmp, mode_only)
+                    .map_err(|e| format!("chmod temp file: {}", e))?;
+            }
+        }
+
+        sftp_rename_overwrite(sess, sftp, &tmp, target)
+    })();
+
+    if let Err(e) = result {
+        let _ = sftp.unlink(&tmp);
+        return Err(e);
+    }
+    sftp.stat(target).map_err(|e| e.to_string())
+}
+
+/// Build a listing entry from an lstat-style FileStat, resolving symlink targets
+fn sftp_entry(sftp: &ssh2::Sftp, path: &Path, name: &str, st: &ssh2::FileStat) -> SftpEntry {
+    let file_type = st
+        .perm
+        .map(crate::utils::file_type_name)
+        .unwrap_or("unknown");
+    let is_symlink = st.file_type().is_symlink();
+    let (link_target, link_is_dir) = if is_symlink {
+        (
+            sftp.readlink(path)
+                .ok()
+                .map(|t| t.to_string_lossy().to_string()),
+            sftp.stat(path).ok().map(|t| t.is_dir()),
+        )
+    } else {
+        (None, None)
+    };
+    SftpEntry {
+        name: name.to_string(),
+        path: path.to_string_lossy().to_string(),
+        is_dir: st.is_dir(),
+        size: st.size,
+        mtime: st.mtime,
+        atime: st.atime,
+        mode: st.perm.map(|p| p & 0o7777),
+        permissions: st.perm.map(crate::utils::mode_string),
+        uid: st.uid,
+        gid: st.gid,
+        owner: None,
+        group: None,
+        file_type: file_type.to_string(),
+        is_symlink,
+        link_target,
+        link_is_dir,
+    }
+}
+
+/// Parse `name:x:id:...` lines of a passwd or group file
+fn parse_id_names(contents: &str) -> HashMap<u32, String> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let id = fields.nth(1)?.parse().ok()?;
+            Some((id, name.to_string()))
+        })
+        .collect()
+}
+
+/// Fill in owner/group names, loading the remote passwd/group files once per session
+fn resolve_owner_names(
+    state: &crate::state::app_state::AppState,
+    session_id: &str,
+    entries: &mut [SftpEntry],
+) {
+    let cached = state
+        .inner
+        .lock()
+        .ok()
+        .and_then(|inner| inner.ssh.get(session_id).and_then(|s| s.owner_names.clone()));
+    let names = match cached {
+        Some(names) => names,
+        None => {
+            let names = with_sftp(state, session_id, |_, sftp| {
+                let read = |p: &str| {
+                    sftp_read_all(sftp, Path::new(p))
+                        .map(|b| String::from_utf8_lossy(&b).to_string())
+                        .unwrap_or_default()
+                };
+                Ok(crate::state::app_state::RemoteOwnerNames {
+                    users: parse_id_names(&read("/etc/passwd")),
+                    groups: parse_id_names(&read("/etc/group")),
+                })
+            })
+            .unwrap_or_default();
+            if let Ok(mut inner) = state.inner.lock() {
+                if let Some(s) = inner.ssh.get_mut(session_id) {
+                    s.owner_names = Some(names.clone());
+                }
+            }
+            names
+        }
+    };
+    for e in entries.iter_mut() {
+        e.owner = e.uid.and_then(|u| names.users.get(&u).cloned());
+        e.group = e.gid.and_then(|g| names.groups.get(&g).cloned());
+    }
+}
+
 #[tauri::command]
 pub async fn ssh_sftp_list(
     state: State<'_, crate::state::app_state::AppState>,
     session_id: String,
     path: String,
 ) -> Result<Vec<SftpEntry>, String> {
-    let entries = {
-        let mut inner = state.inner.lock().map_err(|_| "lock")?;
-        let s = inner
-            .ssh
-            .get_mut(&session_id)
-            .ok_or("ssh session not found")?;
-        
-        // Get session lock to serialize operations
-        let session_lock = s.lock.clone();
-        
-        // Lock the session for exclusive access during SFTP operation
-        let _guard = session_lock.lock().unwrap();
-        
-        // SFTP operations need blocking mode temporarily
-        s.sess.set_blocking(true);
-        let sftp = match s.sess.sftp() {
-            Ok(sftp) => sftp,
-            Err(e) => {
-                s.sess.set_blocking(false); // Restore non-blocking
-                return Err(e.to_string());
-            }
-        };
-        let entries = match sftp.readdir(Path::new(&path)) {
-            Ok(entries) => entries,
-            Err(e) => {
-                s.sess.set_blocking(false); // Restore non-blocking
-                return Err(e.to_string());
-            }
-        };
-        s.sess.set_blocking(false); // Restore non-blocking
-        
-        entries
-    };
-    
-    let mut out = Vec::new();
-    for (p, st) in entries {
-        if let Some(name_os) = p.file_name() {
-            if let Some(name) = name_os.to_str() {
+    let mut out = with_sftp(&state, &session_id, |_, sftp| {
+        let entries = sftp.readdir(Path::new(&path)).map_err(|e| e.to_string())?;
+        let mut out = Vec::new();
+        for (p, st) in entries {
+            if let Some(name) = p.file_name().and_then(|n| n.to_str()) {
                 if name == "." {
                     continue;
                 }
-                let is_dir = st.is_dir();
-                let child_path = p.to_string_lossy().to_string();
-                out.push(SftpEntry {
-                    name: name.to_string(),
-                    path: child_path,
-                    is_dir,
-                });
+                out.push(sftp_entry(sftp, &p, name, &st));
             }
         }
-    }
+        Ok(out)
+    })?;
+    resolve_owner_names(&state, &session_id, &mut out);
+
     // Sort: directories first, then names
     out.sort_by(|a, b| {
         b.is_dir
@@ -588,78 +830,70 @@ pub async fn ssh_sftp_download(
     session_id: String,
     remote_path: String,
     local_path: String,
-) -> Result<(), String> {
+) -> Result<SftpTransferResult, String> {
     eprintln!(
         "[ssh] sftp_download remote={} local={}",
         remote_path, local_path
     );
-    
-    // Get SFTP handle with blocking mode
-    let sftp = {
-        let mut inner = state.inner.lock().map_err(|_| "lock")?;
-        let s = inner
-            .ssh
-            .get_mut(&session_id)
-            .ok_or("ssh session not found")?;
-        
-        // Get session lock to serialize operations
-        let session_lock = s.lock.clone();
-        let _guard = session_lock.lock().unwrap();
-        
-        // SFTP operations need blocking mode temporarily
-        s.sess.set_blocking(true);
-        let sftp = match s.sess.sftp() {
-            Ok(sftp) => sftp,
-            Err(e) => {
-                s.sess.set_blocking(false); // Restore non-blocking
-                return Err(e.to_string());
-            }
-        };
-        
-        // Keep sftp handle, blocking mode stays on
-        sftp
-    };
+    let ls = crate::commands::transfer::LockedSession::for_session(&state, &session_id)?;
+
     // Ensure local parent directory exists
     if let Some(parent) = std::path::Path::new(&local_path).parent() {
         let _ = std::fs::create_dir_all(parent);
     }
-    
-    let mut remote = sftp.open(Path::new(&remote_path)).map_err(|e| e.to_string())?;
     let mut local = std::fs::File::create(&local_path).map_err(|e| e.to_string())?;
-    let mut buf = [0u8; 131072];
-    
-    loop {
-        match remote.read(&mut buf) {
-            Ok(0) => break,
-            Ok(n) => {
-                local.write_all(&buf[..n]).map_err(|e| e.to_string())?;
-            }
-            Err(e) => return Err(e.to_string()),
-        }
-    }
-    
-    // Restore non-blocking mode
-    {
-        let mut inner = state.inner.lock().map_err(|_| "lock")?;
-        if let Some(s) = inner.ssh.get_mut(&session_id) {
-            s.sess.set_blocking(false);
+
+    let result = ls.run(|| match open_sftp(&ls.sess)? {
+        Some(sftp) => {
+            let mut remote = sftp
+                .open(Path::new(&remote_path))
+                .map_err(|e| e.to_string())?;
+            let bytes = std::io::copy(&mut remote, &mut local).map_err(|e| e.to_string())?;
+            Ok(SftpTransferResult {
+                transport: "sftp",
+                bytes,
+            })
         }
+        None => Ok(SftpTransferResult {
+            transport: "scp",
+            bytes: scp_read(&ls.sess, &remote_path, &mut local)?,
+        }),
+    });
+    if result.is_err() {
+        drop(local);
+        let _ = std::fs::remove_file(&local_path);
     }
-    
-    Ok(())
+    result
 }
 
 #[tauri::command]
 pub async fn ssh_sftp_download_dir(
+    app: tauri::AppHandle,
     state: State<'_, crate::state::app_state::AppState>,
     session_id: String,
     remote_dir: String,
     local_dir: String,
-) -> Result<(), String> {
+    compress: Option<String>,
+) -> Result<crate::commands::archive::DownloadDirResult, String> {
     eprintln!(
-        "[ssh] sftp_download_dir remote_dir={} local_dir={}",
-        remote_dir, local_dir
+        "[ssh] sftp_download_dir remote_dir={} local_dir={} compress={:?}",
+        remote_dir, local_dir, compress
     );
+
+    // Packing on the server is much faster for many small files
+    if let Some(compression) = compress.filter(|c| c != "none") {
+        let ls = crate::commands::transfer::LockedSession::for_session(&state, &session_id)?;
+        if let Some(result) = crate::commands::archive::download_dir_archive(
+            &app,
+            &ls,
+            &remote_dir,
+            Path::new(&local_dir),
+            &compression,
+        )? {
+            return Ok(result);
+        }
+        eprintln!("[ssh] falling back to per-file download");
+    }
     
     // Get SFTP handle with blocking mode
     let sftp = {
@@ -702,6 +936,7 @@ pub async fn ssh_sftp_download_dir(
         rroot: &std::path::Path,
         lroot: &std::path::Path,
         rcur: &std::path::Path,
+        counts: &mut crate::commands::archive::DownloadDirResult,
     ) -> Result<(), String> {
         let entries = sftp.readdir(rcur).map_err(|e| e.to_string())?;
 
@@ -716,7 +951,8 @@ pub async fn ssh_sftp_download_dir(
             let lpath = lroot.join(rel(rroot, &rpath));
             if st.is_dir() {
                 std::fs::create_dir_all(&lpath).map_err(|e| e.to_string())?;
-                download_recursive(sftp, rroot, lroot, &rpath)?;
+                counts.dirs += 1;
+                download_recursive(sftp, rroot, lroot, &rpath, counts)?;
             } else {
                 // file copy
                 if let Some(parent) = lpath.parent() {
@@ -730,16 +966,22 @@ pub async fn ssh_sftp_download_dir(
                         Ok(0) => break,
                         Ok(n) => {
                             lfile.write_all(&buf[..n]).map_err(|e| e.to_string())?;
+                            counts.bytes += n as u64;
                         }
                         Err(e) => return Err(e.to_string()),
                     }
                 }
+                counts.files += 1;
             }
         }
         Ok(())
     }
 
-    let result = download_recursive(&sftp, &remote_root, &local_root, &remote_root);
+    let mut counts = crate::commands::archive::DownloadDirResult {
+        method: "sftp".to_string(),
+        ..Default::default()
+    };
+    let result = download_recursive(&sftp, &remote_root, &local_root, &remote_root, &mut counts);
     
     // Restore non-blocking mode
     {
@@ -749,96 +991,284 @@ pub async fn ssh_sftp_download_dir(
         }
     }
     
-    result
+    result.map(|_| counts)
+}
+
+#[derive(Serialize)]
+pub struct UploadDirResult {
+    pub files: usize,
+    pub dirs: usize,
+    pub bytes: u64,
+    pub skipped: usize,
+}
+
+pub(crate) struct LocalEntry {
+    pub(crate) rel: std::path::PathBuf,
+    pub(crate) is_dir: bool,
+    pub(crate) size: u64,
+    pub(crate) mode: u32,
+    pub(crate) mtime: u64,
+    pub(crate) atime: u64,
+}
+
+/// Walk a local tree, keeping entries that pass the filter. Directories come
+/// before their contents; symlinked directories are not followed.
+pub(crate) fn walk_local(
+    root: &std::path::Path,
+    cur: &std::path::Path,
+    filter: &crate::utils::PathFilter,
+    out: &mut Vec<LocalEntry>,
+    skipped: &mut usize,
+) -> Result<(), String> {
+    let mut entries: Vec<_> = std::fs::read_dir(cur)
+        .map_err(|e| format!("{}: {}", cur.display(), e))?
+        .filter_map(Result::ok)
+        .collect();
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let path = entry.path();
+        let rel = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+        let link_meta = match std::fs::symlink_metadata(&path) {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+        // Follow file symlinks, but never recurse through a linked directory
+        let meta = if link_meta.file_type().is_symlink() {
+            match std::fs::metadata(&path) {
+                Ok(m) if m.is_file() => m,
+                _ => {
+                    *skipped += 1;
+                    continue;
+                }
+            }
+        } else {
+            link_meta
+        };
+        if filter.is_excluded(&rel) {
+            *skipped += 1;
+            continue;
+        }
+        let secs = |t: std::io::Result<std::time::SystemTime>| {
+            t.ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+        };
+        #[cfg(unix)]
+        let mode = {
+            use std::os::unix::fs::PermissionsExt;
+            meta.permissions().mode() & 0o7777
+        };
+        #[cfg(not(unix))]
+        let mode = if meta.is_dir() { 0o755 } else { 0o644 };
+        let entry = LocalEntry {
+            rel: rel.clone(),
+            is_dir: meta.is_dir(),
+            size: meta.len(),
+            mode,
+            mtime: secs(meta.modified()),
+            atime: secs(meta.accessed()),
+        };
+        if meta.is_dir() {
+            out.push(entry);
+            walk_local(root, &path, filter, out, skipped)?;
+        } else if meta.is_file() {
+            if filter.includes_file(&rel) {
+                out.push(entry);
+            } else {
+                *skipped += 1;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Join a local relative path onto a remote directory using `/` separators
+pub(crate) fn remote_join(root: &str, rel: &std::path::Path) -> String {
+    let mut out = root.trim_end_matches('/').to_string();
+    for comp in rel.components() {
+        out.push('/');
+        out.push_str(&comp.as_os_str().to_string_lossy());
+    }
+    out
 }
 
 #[tauri::command]
-pub async fn ssh_sftp_read(
+pub async fn ssh_sftp_upload_dir(
+    app: tauri::AppHandle,
     state: State<'_, crate::state::app_state::AppState>,
     session_id: String,
-    remote_path: String,
-) -> Result<String, String> {
-    eprintln!("[ssh] sftp_read path={}", remote_path);
-    
-    let buf = {
-        let mut inner = state.inner.lock().map_err(|_| "lock")?;
-        let s = inner
-            .ssh
-            .get_mut(&session_id)
-            .ok_or("ssh session not found")?;
-        
-        // Get session lock to serialize operations
-        let session_lock = s.lock.clone();
-        let _guard = session_lock.lock().unwrap();
-        
-        // SFTP operations need blocking mode temporarily
-        s.sess.set_blocking(true);
-        let sftp = match s.sess.sftp() {
-            Ok(sftp) => sftp,
-            Err(e) => {
-                s.sess.set_blocking(false); // Restore non-blocking
-                return Err(e.to_string());
-            }
+    local_dir: String,
+    remote_dir: String,
+    include: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+) -> Result<UploadDirResult, String> {
+    eprintln!(
+        "[ssh] sftp_upload_dir local_dir={} remote_dir={}",
+        local_dir, remote_dir
+    );
+
+    let filter = crate::utils::PathFilter::new(
+        &include.unwrap_or_default(),
+        &exclude.unwrap_or_default(),
+    )?;
+    let local_root = std::path::Path::new(&local_dir).to_path_buf();
+    if !local_root.is_dir() {
+        return Err(format!("{} is not a directory", local_dir));
+    }
+    let mut entries = Vec::new();
+    let mut skipped = 0usize;
+    walk_local(&local_root, &local_root, &filter, &mut entries, &mut skipped)?;
+    let total_bytes: u64 = entries.iter().filter(|e| !e.is_dir).map(|e| e.size).sum();
+    let total_files = entries.iter().filter(|e| !e.is_dir).count();
+
+    // Every SFTP call takes the session lock, so a large upload does not
+    // hold the session in blocking mode between chunks
+    let ls = crate::commands::transfer::LockedSession::for_session(&state, &session_id)?;
+    let sftp = ls.run(|| ls.sess.sftp()).map_err(|e| e.to_string())?;
+
+    let upload = || -> Result<UploadDirResult, String> {
+        ls.run(|| sftp_mkdirs(&sftp, &remote_dir))?;
+        let mut written = 0u64;
+        let mut files = 0usize;
+        let mut dirs = 0usize;
+        let mut last_emit = std::time::Instant::now();
+        let mut buf = vec![0u8; 131072];
+        let emit = |file: &std::path::Path, written: u64, files: usize| {
+            let _ = app.emit(
+                crate::events::SSH_UPLOAD_PROGRESS,
+                &serde_json::json!({
+                    "path": local_dir,
+                    "file": file.to_string_lossy(),
+                    "written": written,
+                    "total": total_bytes,
+                    "filesDone": files,
+                    "filesTotal": total_files
+                }),
+            );
         };
-        
-        let mut file = match sftp.open(Path::new(&remote_path)) {
-            Ok(f) => f,
-            Err(e) => {
-                s.sess.set_blocking(false); // Restore non-blocking
-                return Err(e.to_string());
+
+        for entry in &entries {
+            let rpath = remote_join(&remote_dir, &entry.rel);
+            if entry.is_dir {
+                // Permissions and mtimes for directories are applied at the end
+                ls.run(|| sftp_mkdirs(&sftp, &rpath))?;
+                dirs += 1;
+                continue;
             }
-        };
-        
-        let mut buf = Vec::new();
-        let mut tmp = [0u8; 65536];
-        loop {
-            match file.read(&mut tmp) {
-                Ok(0) => break,
-                Ok(n) => {
-                    buf.extend_from_slice(&tmp[..n]);
-                }
-                Err(e) => {
-                    s.sess.set_blocking(false); // Restore non-blocking
-                    return Err(e.to_string());
+            let mut lfile = std::fs::File::open(local_root.join(&entry.rel))
+                .map_err(|e| format!("{}: {}", entry.rel.display(), e))?;
+            let mut rfile = ls
+                .run(|| {
+                    sftp.open_mode(
+                        Path::new(&rpath),
+                        ssh2::OpenFlags::WRITE
+                            | ssh2::OpenFlags::CREATE
+                            | ssh2::OpenFlags::TRUNCATE,
+                        entry.mode as i32,
+                        ssh2::OpenType::File,
+                    )
+                })
+                .map_err(|e| format!("{}: {}", rpath, e))?;
+            let copied = (|| -> Result<(), String> {
+                loop {
+                    let n = lfile.read(&mut buf).map_err(|e| e.to_string())?;
+                    if n == 0 {
+                        return Ok(());
+                    }
+                    ls.run(|| rfile.write_all(&buf[..n]))
+                        .map_err(|e| format!("Write failed: {}", e))?;
+                    written += n as u64;
+                    if last_emit.elapsed() >= Duration::from_millis(100) {
+                        last_emit = std::time::Instant::now();
+                        emit(&entry.rel, written, files);
+                    }
                 }
-            }
+            })();
+            ls.run(move || drop(rfile));
+            copied?;
+            files += 1;
+            ls.run(|| {
+                sftp.setstat(
+                    Path::new(&rpath),
+                    ssh2::FileStat {
+                        size: None,
+                        uid: None,
+                        gid: None,
+                        perm: Some(entry.mode),
+                        atime: Some(entry.atime),
+                        mtime: Some(entry.mtime),
+                    },
+                )
+            })
+            .map_err(|e| format!("{}: {}", rpath, e))?;
         }
-        
-        s.sess.set_blocking(false); // Restore non-blocking
-        buf
+
+        // Deepest directories first, so setting a parent's mtime is not undone
+        // by changes inside it and read-only modes do not block the upload
+        for entry in entries.iter().rev().filter(|e| e.is_dir) {
+            let rpath = remote_join(&remote_dir, &entry.rel);
+            let _ = ls.run(|| {
+                sftp.setstat(
+                    Path::new(&rpath),
+                    ssh2::FileStat {
+                        size: None,
+                        uid: None,
+                        gid: None,
+                        perm: Some(entry.mode),
+                        atime: Some(entry.atime),
+                        mtime: Some(entry.mtime),
+                    },
+                )
+            });
+        }
+
+        emit(std::path::Path::new(""), written, files);
+        Ok(UploadDirResult {
+            files,
+            dirs,
+            bytes: written,
+            skipped,
+        })
     };
-    
-    let b64 = base64::engine::general_purpose::STANDARD.encode(buf);
-    Ok(b64)
+    let result = upload();
+    ls.run(move || drop(sftp));
+
+    if let Ok(r) = &result {
+        eprintln!(
+            "[ssh] sftp_upload_dir uploaded {} files ({} bytes), skipped {}",
+            r.files, r.bytes, r.skipped
+        );
+    }
+    result
 }
 
 #[tauri::command]
-pub async fn ssh_sftp_mkdirs(
+pub async fn ssh_sftp_read(
     state: State<'_, crate::state::app_state::AppState>,
     session_id: String,
-    path: String,
-) -> Result<(), String> {
-    eprintln!("[ssh] mkdirs path={}", path);
-    
-    let mut inner = state.inner.lock().map_err(|_| "lock")?;
-    let s = inner
-        .ssh
-        .get_mut(&session_id)
-        .ok_or("ssh session not found")?;
-    
-    // Get session lock to serialize operations
-    let session_lock = s.lock.clone();
-    let _guard = session_lock.lock().unwrap();
-    
-    // SFTP operations need blocking mode temporarily
-    s.sess.set_blocking(true);
-    let sftp = match s.sess.sftp() {
-        Ok(sftp) => sftp,
-        Err(e) => {
-            s.sess.set_blocking(false); // Restore non-blocking
-            return Err(e.to_string());
+    remote_path: String,
+) -> Result<SftpReadResult, String> {
+    eprintln!("[ssh] sftp_read path={}", remote_path);
+    let ls = crate::commands::transfer::LockedSession::for_session(&state, &session_id)?;
+
+    let (buf, transport) = ls.run(|| match open_sftp(&ls.sess)? {
+        Some(sftp) => Ok((sftp_read_all(&sftp, Path::new(&remote_path))?, "sftp")),
+        None => {
+            let mut buf = Vec::new();
+            scp_read(&ls.sess, &remote_path, &mut buf)?;
+            Ok::<_, String>((buf, "scp"))
         }
-    };
+    })?;
+
+    Ok(SftpReadResult {
+        data_b64: base64::engine::general_purpose::STANDARD.encode(buf),
+        transport,
+    })
+}
+
+/// Create a remote directory and any missing parents (like `mkdir -p`)
+pub(crate) fn sftp_mkdirs(sftp: &ssh2::Sftp, path: &str) -> Result<(), String> {
     let parts: Vec<&str> = path
         .split('/')
         .filter(|p| !p.is_empty() && *p != ".")
@@ -868,94 +1298,515 @@ pub async fn ssh_sftp_mkdirs(
                     continue;
                 }
             }
-            // Error occurred
-            s.sess.set_blocking(false); // Restore non-blocking on error
             return Err(e.to_string());
         }
     }
-    s.sess.set_blocking(false); // Restore non-blocking
     Ok(())
 }
 
+#[tauri::command]
+pub async fn ssh_sftp_mkdirs(
+    state: State<'_, crate::state::app_state::AppState>,
+    session_id: String,
+    path: String,
+) -> Result<(), String> {
+    eprintln!("[ssh] mkdirs path={}", path);
+    with_sftp(&state, &session_id, |_, sftp| sftp_mkdirs(sftp, &path))
+}
+
+#[tauri::command]
+pub async fn ssh_sftp_rename(
+    state: State<'_, crate::state::app_state::AppState>,
+    session_id: String,
+    from: String,
+    to: String,
+    overwrite: Option<bool>,
+) -> Result<(), String> {
+    eprintln!("[ssh] sftp_rename from={} to={}", from, to);
+    with_sftp(&state, &session_id, |sess, sftp| {
+        let (src, dst) = (Path::new(&from), Path::new(&to));
+        if overwrite.unwrap_or(false) {
+            return sftp_rename_overwrite(sess, sftp, src, dst);
+        }
+        // Some servers replace silently on rename; refuse explicitly instead
+        if sftp.lstat(dst).is_ok() {
+            return Err(format!("{} already exists", to));
+        }
+        sftp.rename(src, dst, Some(ssh2::RenameFlags::ATOMIC | ssh2::RenameFlags::NATIVE))
+            .map_err(|e| e.to_string())
+    })
+}
+
+/// Collect a remote tree children-first (deletion order). Symlinks are not followed.
+fn sftp_collect_tree(
+    sftp: &ssh2::Sftp,
+    path: &Path,
+    out: &mut Vec<(std::path::PathBuf, bool)>,
+) -> Result<(), String> {
+    let st = sftp
+        .lstat(path)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    if st.is_dir() {
+        let entries = sftp
+            .readdir(path)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        for (child, _) in entries {
+            match child.file_name().and_then(|n| n.to_str()) {
+                Some(".") | Some("..") | None => continue,
+                _ => sftp_collect_tree(sftp, &child, out)?,
+            }
+        }
+        out.push((path.to_path_buf(), true));
+    } else {
+        out.push((path.to_path_buf(), false));
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+pub struct SftpDeleteResult {
+    /// Paths removed (or that would be removed on a dry run), children first
+    pub paths: Vec<String>,
+    pub dry_run: bool,
+}
+
+#[tauri::command]
+pub async fn ssh_sftp_delete(
+    state: State<'_, crate::state::app_state::AppState>,
+    session_id: String,
+    path: String,
+    recursive: Option<bool>,
+    dry_run: Option<bool>,
+) -> Result<SftpDeleteResult, String> {
+    let recursive = recursive.unwrap_or(false);
+    let dry_run = dry_run.unwrap_or(false);
+    eprintln!(
+        "[ssh] sftp_delete path={} recursive={} dry_run={}",
+        path, recursive, dry_run
+    );
+    if path.trim_end_matches('/').is_empty() {
+        return Err("refusing to delete the root directory".into());
+    }
+
+    with_sftp(&state, &session_id, |_, sftp| {
+        let p = Path::new(&path);
+        let mut targets = Vec::new();
+        if recursive {
+            sftp_collect_tree(sftp, p, &mut targets)?;
+        } else {
+            let st = sftp.lstat(p).map_err(|e| e.to_string())?;
+            targets.push((p.to_path_buf(), st.is_dir()));
+        }
+        let paths: Vec<String> = targets
+            .iter()
+            .map(|(t, _)| t.to_string_lossy().to_string())
+            .collect();
+        if !dry_run {
+            for (target, is_dir) in &targets {
+                let res = if *is_dir {
+                    sftp.rmdir(target)
+                } else {
+                    sftp.unlink(target)
+                };
+                res.map_err(|e| format!("{}: {}", target.display(), e))?;
+            }
+        }
+        Ok(SftpDeleteResult { paths, dry_run })
+    })
+}
+
+#[tauri::command]
+pub async fn ssh_sftp_chmod(
+    state: State<'_, crate::state::app_state::AppState>,
+    session_id: String,
+    path: String,
+    mode: u32,
+) -> Result<(), String> {
+    eprintln!("[ssh] sftp_chmod path={} mode={:o}", path, mode);
+    with_sftp(&state, &session_id, |_, sftp| {
+        sftp.setstat(
+            Path::new(&path),
+            ssh2::FileStat {
+                size: None,
+                uid: None,
+                gid: None,
+                perm: Some(mode & 0o7777),
+                atime: None,
+                mtime: None,
+            },
+        )
+        .map_err(|e| e.to_string())
+    })
+}
+
+#[tauri::command]
+pub async fn ssh_sftp_chown(
+    state: State<'_, crate::state::app_state::AppState>,
+    session_id: String,
+    path: String,
+    uid: Option<u32>,
+    gid: Option<u32>,
+) -> Result<(), String> {
+    eprintln!("[ssh] sftp_chown path={} uid={:?} gid={:?}", path, uid, gid);
+    with_sftp(&state, &session_id, |_, sftp| {
+        let p = Path::new(&path);
+        // SFTPv3 sets uid and gid together, so keep whichever side is unchanged
+        let current = sftp.stat(p).map_err(|e| e.to_string())?;
+        sftp.setstat(
+            p,
+            ssh2::FileStat {
+                size: None,
+                uid: uid.or(current.uid),
+                gid: gid.or(current.gid),
+                perm: None,
+                atime: None,
+                mtime: None,
+            },
+        )
+        .map_err(|e| e.to_string())
+    })
+}
+
+#[tauri::command]
+pub async fn ssh_sftp_symlink(
+    state: State<'_, crate::state::app_state::AppState>,
+    session_id: String,
+    target: String,
+    link_path: String,
+) -> Result<(), String> {
+    eprintln!("[ssh] sftp_symlink link={} -> {}", link_path, target);
+    with_sftp(&state, &session_id, |sess, sftp| {
+        let (target_p, link_p) = (Path::new(&target), Path::new(&link_path));
+        if sftp.symlink(target_p, link_p).is_ok() {
+            if sftp.readlink(link_p).is_ok_and(|t| t == target_p) {
+                return Ok(());
+            }
+            // OpenSSH's server swaps the SSH_FXP_SYMLINK arguments; undo the
+            // reversed link this call just made
+            if sftp.readlink(target_p).is_ok_and(|t| t == link_p) {
+                let _ = sftp.unlink(target_p);
+            }
+        }
+        let cmd = format!(
+            "ln -s -- {} {}",
+            crate::utils::shell_quote(&target),
+            crate::utils::shell_quote(&link_path)
+        );
+        let res = run_exec(sess, &cmd)?;
+        if res.exit_code != 0 {
+            return Err(format!("symlink failed: {}", res.stderr.trim()));
+        }
+        Ok(())
+    })
+}
+
+fn sftp_stat_entry(
+    state: &crate::state::app_state::AppState,
+    session_id: &str,
+    path: &str,
+    follow: bool,
+) -> Result<SftpEntry, String> {
+    let entry = with_sftp(state, session_id, |_, sftp| {
+        let p = Path::new(path);
+        let st = if follow { sftp.stat(p) } else { sftp.lstat(p) }.map_err(|e| e.to_string())?;
+        let name = p
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string());
+        Ok(sftp_entry(sftp, p, &name, &st))
+    })?;
+    let mut entries = [entry];
+    resolve_owner_names(state, session_id, &mut entries);
+    let [entry] = entries;
+    Ok(entry)
+}
+
+#[tauri::command]
+pub async fn ssh_sftp_stat(
+    state: State<'_, crate::state::app_state::AppState>,
+    session_id: String,
+    path: String,
+) -> Result<SftpEntry, String> {
+    sftp_stat_entry(&state, &session_id, &path, true)
+}
+
+#[tauri::command]
+pub async fn ssh_sftp_lstat(
+    state: State<'_, crate::state::app_state::AppState>,
+    session_id: String,
+    path: String,
+) -> Result<SftpEntry, String> {
+    sftp_stat_entry(&state, &session_id, &path, false)
+}
+
+/// Copy a remote file or tree by streaming it through SFTP (no shell needed)
+fn sftp_copy_stream(sftp: &ssh2::Sftp, from: &Path, to: &Path) -> Result<(), String> {
+    let st = sftp
+        .stat(from)
+        .map_err(|e| format!("{}: {}", from.display(), e))?;
+    if st.is_dir() {
+        sftp_mkdirs(sftp, &to.to_string_lossy())?;
+        for (child, _) in sftp.readdir(from).map_err(|e| e.to_string())? {
+            let name = match child.file_name() {
+                Some(n) if n != "." && n != ".." => n.to_owned(),
+                _ => continue,
+            };
+            sftp_copy_stream(sftp, &child, &to.join(name))?;
+        }
+    } else {
+        let mode = st.perm.map(|p| (p & 0o7777) as i32).unwrap_or(0o644);
+        let mut src = sftp.open(from).map_err(|e| e.to_string())?;
+        let mut dst = sftp
+            .open_mode(
+                to,
+                ssh2::OpenFlags::WRITE | ssh2::OpenFlags::CREATE | ssh2::OpenFlags::TRUNCATE,
+                mode,
+                ssh2::OpenType::File,
+            )
+            .map_err(|e| format!("{}: {}", to.display(), e))?;
+        std::io::copy(&mut src, &mut dst).map_err(|e| e.to_string())?;
+    }
+    // Keep timestamps like `cp -p`
+    let _ = sftp.setstat(
+        to,
+        ssh2::FileStat {
+            size: None,
+            uid: None,
+            gid: None,
+            perm: None,
+            atime: st.atime,
+            mtime: st.mtime,
+        },
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn ssh_sftp_copy(
+    state: State<'_, crate::state::app_state::AppState>,
+    session_id: String,
+    from: String,
+    to: String,
+) -> Result<(), String> {
+    eprintln!("[ssh] sftp_copy from={} to={}", from, to);
+    with_sftp(&state, &session_id, |sess, sftp| {
+        // SFTP has no server-side copy; prefer cp and only stream when there is no shell
+        let cmd = format!(
+            "cp -pR -- {} {}",
+            crate::utils::shell_quote(&from),
+            crate::utils::shell_quote(&to)
+        );
+        match run_exec(sess, &cmd) {
+            Ok(res) if res.exit_code == 0 => Ok(()),
+            Ok(res) if res.exit_code != 127 => {
+                Err(format!("copy failed: {}", res.stderr.trim()))
+            }
+            _ => {
+                eprintln!("[ssh] cp unavailable, copying over sftp");
+                sftp_copy_stream(sftp, Path::new(&from), Path::new(&to))
+            }
+        }
+    })
+}
+
+#[derive(Serialize)]
+pub struct HelperDeployResult {
+    /// False when the installed helper already had this version
+    pub deployed: bool,
+    pub version: String,
+    pub os: String,
+    pub arch: String,
+    /// A fresh install on a host whose login shell lacks cwd tracking, so
+    /// the app should offer `ssh_install_shell_integration`
+    pub offer_shell_integration: bool,
+}
+
+/// Version the helper at `remote_path` reports from `health`, if it runs at all
+fn remote_helper_version(
+    ls: &crate::commands::transfer::LockedSession,
+    remote_path: &str,
+) -> Option<String> {
+    let cmd = format!("{} health", crate::utils::shell_quote(remote_path));
+    let res = ls
+        .run(|| run_exec(&ls.sess, &cmd))
+        .ok()
+        .filter(|r| r.exit_code == 0)?;
+    let health: serde_json::Value = serde_json::from_str(res.stdout.trim()).ok()?;
+    health["version"].as_str().map(String::from)
+}
+
+/// Install the helper build matching the remote OS and architecture at
+/// `remote_path`, unless `health` already reports this version. The binary
+/// goes to a temp file next to it and only replaces the installed helper
+/// once its SHA-256 matches the embedded build, so a broken upload never
+/// leaves a corrupt agent behind.
 #[tauri::command]
 pub async fn ssh_deploy_helper(
     app: tauri::AppHandle,
     state: State<'_, crate::state::app_state::AppState>,
     session_id: String,
     remote_path: String,
-) -> Result<(), String> {
+) -> Result<HelperDeployResult, String> {
+    use crate::commands::helper::{helper_for, helper_platforms, HELPER_VERSION};
+    use crate::utils::shell_quote;
+
     eprintln!("[ssh] deploy_helper path={}", remote_path);
+    let ls = crate::commands::transfer::LockedSession::for_session(&state, &session_id)?;
+
+    let uname = ls.run(|| run_exec(&ls.sess, "uname -sm"))?;
+    let mut words = uname.stdout.split_whitespace();
+    let (os, arch) = match (words.next(), words.next()) {
+        (Some(os), Some(arch)) if uname.exit_code == 0 => (os.to_lowercase(), arch.to_string()),
+        _ => {
+            return Err(format!(
+                "could not detect remote platform: {}",
+                uname.stderr.trim()
+            ))
+        }
+    };
+    eprintln!("[ssh] detected platform: {} {}", os, arch);
+
+    let installed = remote_helper_version(&ls, &remote_path);
+    if installed.as_deref() == Some(HELPER_VERSION) {
+        eprintln!("[ssh] helper {} already installed", HELPER_VERSION);
+        let offer_shell_integration =
+            crate::commands::environment::load(&state, Some(&session_id), false)
+                .is_ok_and(|env| crate::commands::environment::wants_shell_integration(&env));
+        return Ok(HelperDeployResult {
+            deployed: false,
+            version: HELPER_VERSION.to_string(),
+            os,
+            arch,
+            offer_shell_integration,
+        });
+    }
+
+    let target = helper_for(&os, &arch).ok_or_else(|| {
+        format!(
+            "no helper build for {}/{} (available: {})",
+            os,
+            arch,
+            helper_platforms().join(", ")
+        )
+    })?;
+    eprintln!(
+        "[ssh] installing helper {} for {}/{} (installed: {})",
+        HELPER_VERSION,
+        target.os,
+        target.arch,
+        installed.as_deref().unwrap_or("none")
+    );
 
-    // Use ssh_exec to detect OS first - it handles the locking properly
-    let uname_result = ssh_exec(state.clone(), session_id.clone(), "uname -s".to_string()).await?;
-    let os_name = uname_result.stdout.trim().to_lowercase();
-    eprintln!("[ssh] detected OS: {}", os_name);
-
-    // Choose the appropriate helper binary based on the OS
-    let helper_binary = if os_name.contains("linux") {
-        // Try to get Linux binary if available
-        match crate::commands::helper::get_linux_helper_binary() {
-            Some(linux_bin) => {
-                eprintln!("[ssh] using Linux helper binary");
-                linux_bin
+    // The running agent is about to be replaced
+    crate::commands::agent_rpc::disconnect(&state, Some(&session_id));
+
+    let tmp_path = format!("{}.tmp-{}", remote_path, nanoid::nanoid!(8));
+    let binary = target.binary;
+    let total = binary.len();
+    let uploaded = ls.run(|| {
+        let mut written = 0usize;
+        let mut send = |out: &mut dyn Write| -> Result<(), String> {
+            while written < total {
+                let end = usize::min(written + 8192, total);
+                out.write_all(&binary[written..end])
+                    .map_err(|e| format!("Write failed: {}", e))?;
+                written = end;
+                let _ = app.emit(
+                    crate::events::SSH_UPLOAD_PROGRESS,
+                    &serde_json::json!({ "path": remote_path, "written": written, "total": total }),
+                );
+            }
+            Ok(())
+        };
+        match open_sftp(&ls.sess)? {
+            Some(sftp) => {
+                let mut file = sftp.create(Path::new(&tmp_path)).map_err(|e| {
+                    eprintln!("[ssh] sftp create failed: {}", e);
+                    e.to_string()
+                })?;
+                send(&mut file)
             }
             None => {
-                eprintln!("[ssh] Linux helper not available, using native binary");
-                crate::commands::helper::get_helper_binary()
+                let mut channel = ls
+                    .sess
+                    .scp_send(Path::new(&tmp_path), 0o755, total as u64, None)
+                    .map_err(|e| format!("scp: {}", e))?;
+                send(&mut channel)?;
+                close_scp_channel(channel)
             }
         }
-    } else {
-        eprintln!("[ssh] using native helper binary for OS: {}", os_name);
-        crate::commands::helper::get_helper_binary()
-    };
+    });
 
-    // Now open SFTP connection and deploy the binary
-    let mut inner = state.inner.lock().map_err(|_| "lock")?;
-    let s = inner
-        .ssh
-        .get_mut(&session_id)
-        .ok_or("ssh session not found")?;
-    
-    // Get session lock to serialize operations
-    let session_lock = s.lock.clone();
-    let _guard = session_lock.lock().unwrap();
-    
-    // SFTP operations need blocking mode temporarily
-    s.sess.set_blocking(true);
-    let sftp = match s.sess.sftp() {
-        Ok(sftp) => sftp,
-        Err(e) => {
-            s.sess.set_blocking(false); // Restore non-blocking
-            return Err(e.to_string());
+    let quoted_tmp = shell_quote(&tmp_path);
+    let remove_tmp = || {
+        let _ = ls.run(|| run_exec(&ls.sess, &format!("rm -f {}", quoted_tmp)));
+    };
+    if let Err(e) = uploaded {
+        remove_tmp();
+        return Err(e);
+    }
+    eprintln!("[ssh] helper uploaded {} bytes", total);
+
+    // Without sha256sum or shasum, read the upload back and hash it here
+    let actual = match crate::commands::transfer::remote_sha256(&ls, Path::new(&tmp_path)) {
+        Some(hash) => hash,
+        None => {
+            let mut data = Vec::new();
+            match ls.run(|| scp_read(&ls.sess, &tmp_path, &mut data)) {
+                Ok(_) => crate::utils::sha256_hex(&data),
+                Err(e) => {
+                    remove_tmp();
+                    return Err(format!("could not verify helper upload: {}", e));
+                }
+            }
         }
     };
+    if actual != target.sha256 {
+        remove_tmp();
+        return Err(format!(
+            "helper checksum mismatch: expected {}, got {}",
+            target.sha256, actual
+        ));
+    }
 
-    let total = helper_binary.len();
-    let mut written = 0usize;
-    let mut file = sftp.create(Path::new(&remote_path)).map_err(|e| {
-        eprintln!("[ssh] sftp create failed: {}", e);
-        e.to_string()
-    })?;
-
-    while written < total {
-        let end = usize::min(written + 8192, total);
-        let chunk = &helper_binary[written..end];
-        file.write_all(chunk).map_err(|e| {
-            s.sess.set_blocking(false); // Restore non-blocking on error
-            format!("Write failed: {}", e)
-        })?;
-        written = end;
-        let _ = app.emit(
-            crate::events::SSH_UPLOAD_PROGRESS,
-            &serde_json::json!({ "path": remote_path, "written": written, "total": total }),
-        );
-        std::thread::sleep(Duration::from_millis(1));
+    // rename() swaps the file in atomically, even while the old agent still runs
+    let install = format!(
+        "chmod 755 {0} && mv -f {0} {1}",
+        quoted_tmp,
+        shell_quote(&remote_path)
+    );
+    let res = ls.run(|| run_exec(&ls.sess, &install))?;
+    if res.exit_code != 0 {
+        remove_tmp();
+        return Err(format!("installing helper failed: {}", res.stderr.trim()));
     }
 
+    let version = remote_helper_version(&ls, &remote_path);
+    if version.as_deref() != Some(HELPER_VERSION) {
+        return Err(format!(
+            "helper health check failed after install (version {})",
+            version.as_deref().unwrap_or("unknown")
+        ));
+    }
+    eprintln!(
+        "[ssh] helper {} installed at {}",
+        HELPER_VERSION, remote_path
+    );
 
-    eprintln!("[ssh] helper uploaded {} bytes", written);
-    s.sess.set_blocking(false); // Restore non-blocking
-    Ok(())
+    // The watchdog went down with the old agent (or never started without one)
+    if let Err(e) = crate::commands::watchdog::start(&app, &state, &session_id, None, None) {
+        eprintln!("[ssh] watchdog not started: {}", e);
+    }
+    let offer_shell_integration =
+        crate::commands::environment::load(&state, Some(&session_id), true)
+            .is_ok_and(|env| crate::commands::environment::wants_shell_integration(&env));
+    Ok(HelperDeployResult {
+        deployed: true,
+        version: HELPER_VERSION.to_string(),
+        os,
+        arch,
+        offer_shell_integration,
+    })
 }
 
 #[tauri::command]
@@ -965,58 +1816,267 @@ pub async fn ssh_sftp_write(
     session_id: String,
     remote_path: String,
     data_b64: String,
-) -> Result<(), String> {
+) -> Result<SftpTransferResult, String> {
     eprintln!(
         "[ssh] sftp_write path={} size={}B",
         remote_path,
         data_b64.len()
     );
-    
-    let mut inner = state.inner.lock().map_err(|_| "lock")?;
-    let s = inner
-        .ssh
-        .get_mut(&session_id)
-        .ok_or("ssh session not found")?;
-    
-    // Get session lock to serialize operations
-    let session_lock = s.lock.clone();
-    let _guard = session_lock.lock().unwrap();
-    
-    // SFTP operations need blocking mode temporarily
-    s.sess.set_blocking(true);
-    let sftp = match s.sess.sftp() {
-        Ok(sftp) => sftp,
-        Err(e) => {
-            s.sess.set_blocking(false); // Restore non-blocking
-            return Err(e.to_string());
-        }
-    };
     let bytes = base64::engine::general_purpose::STANDARD
         .decode(data_b64)
         .map_err(|e| e.to_string())?;
     let total = bytes.len();
-    let mut written = 0usize;
-    let mut file = sftp.create(Path::new(&remote_path)).map_err(|e| {
-        eprintln!("[ssh] sftp create failed: {}", e);
-        e.to_string()
+    let ls = crate::commands::transfer::LockedSession::for_session(&state, &session_id)?;
+
+    ls.run(|| {
+        let mut written = 0usize;
+        let mut send = |out: &mut dyn Write| -> Result<(), String> {
+            while written < total {
+                let end = usize::min(written + 8192, total);
+                out.write_all(&bytes[written..end])
+                    .map_err(|e| format!("Write failed: {}", e))?;
+                written = end;
+                let _ = app.emit(
+                    crate::events::SSH_UPLOAD_PROGRESS,
+                    &serde_json::json!({ "path": remote_path, "written": written, "total": total }),
+                );
+            }
+            Ok(())
+        };
+        let transport = match open_sftp(&ls.sess)? {
+            Some(sftp) => {
+                let mut file = sftp.create(Path::new(&remote_path)).map_err(|e| {
+                    eprintln!("[ssh] sftp create failed: {}", e);
+                    e.to_string()
+                })?;
+                send(&mut file)?;
+                "sftp"
+            }
+            None => {
+                let mut channel = ls
+                    .sess
+                    .scp_send(Path::new(&remote_path), 0o644, total as u64, None)
+                    .map_err(|e| format!("scp: {}", e))?;
+                send(&mut channel)?;
+                close_scp_channel(channel)?;
+                "scp"
+            }
+        };
+        eprintln!("[ssh] file uploaded {} bytes via {}", written, transport);
+        Ok(SftpTransferResult {
+            transport,
+            bytes: written as u64,
+        })
+    })
+}
+
+#[derive(Serialize)]
+pub struct SftpEditOpen {
+    pub edit_id: String,
+    pub remote_path: String,
+    pub data_b64: String,
+    pub size: u64,
+    pub mtime: Option<u64>,
+    pub hash: String,
+}
+
+#[derive(Serialize)]
+pub struct SftpEditConflict {
+    /// The remote file no longer exists
+    pub deleted: bool,
+    pub remote_size: Option<u64>,
+    pub remote_mtime: Option<u64>,
+    pub remote_hash: Option<String>,
+    /// Unified diff of the remote changes since open (None for binary or very large files)
+    pub diff: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SftpEditSaveResult {
+    pub saved: bool,
+    pub size: u64,
+    pub mtime: Option<u64>,
+    pub hash: String,
+    pub conflict: Option<SftpEditConflict>,
+}
+
+enum EditSaveOutcome {
+    Saved(ssh2::FileStat),
+    Conflict(SftpEditConflict),
+}
+
+#[tauri::command]
+pub async fn ssh_sftp_edit_open(
+    state: State<'_, crate::state::app_state::AppState>,
+    session_id: String,
+    remote_path: String,
+) -> Result<SftpEditOpen, String> {
+    eprintln!("[ssh] sftp_edit_open path={}", remote_path);
+
+    let (st, data) = with_sftp(&state, &session_id, |_, sftp| {
+        let p = Path::new(&remote_path);
+        let st = sftp.stat(p).map_err(|e| e.to_string())?;
+        if st.is_dir() {
+            return Err("cannot edit a directory".to_string());
+        }
+        let data = sftp_read_all(sftp, p)?;
+        Ok((st, data))
     })?;
-    while written < total {
-        let end = usize::min(written + 8192, total);
-        let chunk = &bytes[written..end];
-        file.write_all(chunk).map_err(|e| {
-            s.sess.set_blocking(false); // Restore non-blocking on error
-            format!("Write failed: {}", e)
-        })?;
-        written = end;
-        let _ = app.emit(
-            crate::events::SSH_UPLOAD_PROGRESS,
-            &serde_json::json!({ "path": remote_path, "written": written, "total": total }),
+
+    let edit_id = format!("edit_{}", nanoid::nanoid!(8));
+    let hash = crate::utils::sha256_hex(&data);
+    let size = data.len() as u64;
+    let b64 = base64::engine::general_purpose::STANDARD.encode(&data);
+    {
+        let mut inner = state.inner.lock().map_err(|_| "lock")?;
+        inner.sftp_edits.insert(
+            edit_id.clone(),
+            crate::state::app_state::SftpEdit {
+                id: edit_id.clone(),
+                session_id,
+                remote_path: remote_path.clone(),
+                size,
+                mtime: st.mtime,
+                hash: hash.clone(),
+                base: data,
+            },
         );
-        std::thread::sleep(Duration::from_millis(1));
     }
-    
-    eprintln!("[ssh] file uploaded {} bytes", written);
-    s.sess.set_blocking(false); // Restore non-blocking
+
+    Ok(SftpEditOpen {
+        edit_id,
+        remote_path,
+        data_b64: b64,
+        size,
+        mtime: st.mtime,
+        hash,
+    })
+}
+
+#[tauri::command]
+pub async fn ssh_sftp_edit_save(
+    app: tauri::AppHandle,
+    state: State<'_, crate::state::app_state::AppState>,
+    edit_id: String,
+    data_b64: String,
+    force: Option<bool>,
+) -> Result<SftpEditSaveResult, String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(data_b64)
+        .map_err(|e| e.to_string())?;
+    let (session_id, remote_path, base_hash, base) = {
+        let inner = state.inner.lock().map_err(|_| "lock")?;
+        let edit = inner.sftp_edits.get(&edit_id).ok_or("edit session not found")?;
+        (
+            edit.session_id.clone(),
+            edit.remote_path.clone(),
+            edit.hash.clone(),
+            edit.base.clone(),
+        )
+    };
+    let force = force.unwrap_or(false);
+    eprintln!(
+        "[ssh] sftp_edit_save path={} size={}B force={}",
+        remote_path,
+        bytes.len(),
+        force
+    );
+
+    let outcome = with_sftp(&state, &session_id, |sess, sftp| {
+        let p = Path::new(&remote_path);
+        // Write through symlinks instead of replacing the link with a file
+        let target = match sftp.lstat(p) {
+            Ok(st) if st.file_type().is_symlink() => sftp.realpath(p).map_err(|e| e.to_string())?,
+            _ => p.to_path_buf(),
+        };
+
+        if !force {
+            match sftp.stat(&target) {
+                Ok(st) => {
+                    let current = sftp_read_all(sftp, &target)?;
+                    let current_hash = crate::utils::sha256_hex(&current);
+                    if current_hash != base_hash {
+                        let diff = match (std::str::from_utf8(&base), std::str::from_utf8(&current)) {
+                            (Ok(old), Ok(new)) => crate::utils::unified_diff(
+                                old,
+                                new,
+                                &format!("{} (opened)", remote_path),
+                                &format!("{} (remote)", remote_path),
+                            ),
+                            _ => None,
+                        };
+                        return Ok(EditSaveOutcome::Conflict(SftpEditConflict {
+                            deleted: false,
+                            remote_size: Some(current.len() as u64),
+                            remote_mtime: st.mtime,
+                            remote_hash: Some(current_hash),
+                            diff,
+                        }));
+                    }
+                }
+                Err(e) if e.code() == ssh2::ErrorCode::SFTP(2) => {
+                    // LIBSSH2_FX_NO_SUCH_FILE
+                    return Ok(EditSaveOutcome::Conflict(SftpEditConflict {
+                        deleted: true,
+                        remote_size: None,
+                        remote_mtime: None,
+                        remote_hash: None,
+                        diff: None,
+                    }));
+                }
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+
+        let st = sftp_write_atomic(sess, sftp, &target, &bytes, |written, total| {
+            let _ = app.emit(
+                crate::events::SSH_UPLOAD_PROGRESS,
+                &serde_json::json!({ "path": remote_path, "written": written, "total": total }),
+            );
+        })?;
+        Ok(EditSaveOutcome::Saved(st))
+    })?;
+
+    let mut inner = state.inner.lock().map_err(|_| "lock")?;
+    let edit = inner
+        .sftp_edits
+        .get_mut(&edit_id)
+        .ok_or("edit session not found")?;
+    match outcome {
+        EditSaveOutcome::Saved(st) => {
+            edit.hash = crate::utils::sha256_hex(&bytes);
+            edit.size = bytes.len() as u64;
+            edit.mtime = st.mtime;
+            edit.base = bytes;
+            eprintln!("[ssh] sftp_edit_save wrote {} bytes", edit.size);
+            Ok(SftpEditSaveResult {
+                saved: true,
+                size: edit.size,
+                mtime: edit.mtime,
+                hash: edit.hash.clone(),
+                conflict: None,
+            })
+        }
+        EditSaveOutcome::Conflict(conflict) => {
+            eprintln!("[ssh] sftp_edit_save conflict path={}", remote_path);
+            Ok(SftpEditSaveResult {
+                saved: false,
+                size: edit.size,
+                mtime: edit.mtime,
+                hash: edit.hash.clone(),
+                conflict: Some(conflict),
+            })
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn ssh_sftp_edit_close(
+    state: State<'_, crate::state::app_state::AppState>,
+    edit_id: String,
+) -> Result<(), String> {
+    let mut inner = state.inner.lock().map_err(|_| "lock")?;
+    inner.sftp_edits.remove(&edit_id);
     Ok(())
 }
 
@@ -1027,85 +2087,50 @@ pub struct ExecResult {
     pub exit_code: i32,
 }
 
-#[tauri::command]
-pub async fn ssh_exec(
-    state: State<'_, crate::state::app_state::AppState>,
-    session_id: String,
-    command: String,
-) -> Result<ExecResult, String> {
-    eprintln!("[ssh] exec cmd={}", command);
-    let mut inner = state.inner.lock().map_err(|_| "lock")?;
-    let s = inner
-        .ssh
-        .get_mut(&session_id)
-        .ok_or("ssh session not found")?;
-    
-    // Get session lock to serialize operations
-    let sess_lock = s.lock.clone();
-    let _guard = sess_lock.lock().unwrap();
-    
-    // SSH exec needs blocking mode temporarily
-    s.sess.set_blocking(true);
-    
+/// Run a command on a fresh exec channel. The session must already be in
+/// blocking mode and its lock held by the caller.
+pub(crate) fn run_exec(sess: &ssh2::Session, command: &str) -> Result<ExecResult, String> {
     // Create channel and execute
-    let mut chan = match s.sess.channel_session() {
-        Ok(c) => c,
-        Err(e) => {
-            s.sess.set_blocking(false); // Restore non-blocking
-            return Err(format!("channel_session: {}", e));
-        }
-    };
-    
+    let mut chan = sess
+        .channel_session()
+        .map_err(|e| format!("channel_session: {}", e))?;
+
     // Keep stderr separate
     let _ = chan.handle_extended_data(ssh2::ExtendedData::Normal);
-    
+
     // Execute command
-    if let Err(e) = chan.exec(&command) {
-        s.sess.set_blocking(false); // Restore non-blocking
-        return Err(format!("exec: {}", e));
-    }
-    
+    chan.exec(command).map_err(|e| format!("exec: {}", e))?;
+
     // Read output
     let mut out = Vec::new();
     let mut err = Vec::new();
     let mut buf = [0u8; 8192];
-    
+
     // Read stdout
     let mut stdout = chan.stream(0);
     loop {
         match stdout.read(&mut buf) {
             Ok(0) => break,
             Ok(n) => out.extend_from_slice(&buf[..n]),
-            Err(e) => {
-                s.sess.set_blocking(false); // Restore non-blocking
-                return Err(format!("read stdout: {}", e));
-            }
+            Err(e) => return Err(format!("read stdout: {}", e)),
         }
     }
-    
+
     // Read stderr
     let mut stderr = chan.stream(1);
     loop {
         match stderr.read(&mut buf) {
             Ok(0) => break,
             Ok(n) => err.extend_from_slice(&buf[..n]),
-            Err(e) => {
-                s.sess.set_blocking(false); // Restore non-blocking
-                return Err(format!("read stderr: {}", e));
-            }
+            Err(e) => return Err(format!("read stderr: {}", e)),
         }
     }
-    
+
     // Wait for channel to close and get exit status
-    chan.wait_close().map_err(|e| {
-        s.sess.set_blocking(false); // Restore non-blocking
-        format!("wait_close: {}", e)
-    })?;
-    
+    chan.wait_close().map_err(|e| format!("wait_close: {}", e))?;
+
     let code = chan.exit_status().unwrap_or(0);
-    
-    s.sess.set_blocking(false); // Restore non-blocking
-    
+
     Ok(ExecResult {
         stdout: String::from_utf8_lossy(&out).to_string(),
         stderr: String::from_utf8_lossy(&err).to_string(),
@@ -1113,34 +2138,51 @@ pub async fn ssh_exec(
     })
 }
 
+#[tauri::command]
+pub async fn ssh_exec(
+    state: State<'_, crate::state::app_state::AppState>,
+    session_id: String,
+    command: String,
+) -> Result<ExecResult, String> {
+    eprintln!("[ssh] exec cmd={}", command);
+    let mut inner = state.inner.lock().map_err(|_| "lock")?;
+    let s = inner
+        .ssh
+        .get_mut(&session_id)
+        .ok_or("ssh session not found")?;
+    
+    // Get session lock to serialize operations
+    let sess_lock = s.lock.clone();
+    let _guard = sess_lock.lock().unwrap();
+    
+    // SSH exec needs blocking mode temporarily
+    s.sess.set_blocking(true);
+    let result = run_exec(&s.sess, &command);
+    s.sess.set_blocking(false); // Restore non-blocking
+    
+    result
+}
+
 #[tauri::command]
 pub async fn ssh_detect_ports(
     app: tauri::AppHandle,
     state: State<'_, crate::state::app_state::AppState>,
     session_id: String,
 ) -> Result<Vec<u16>, String> {
-    // Get SSH session info
-    let (host, port, user) = {
-        let inner = state.inner.lock().map_err(|_| "lock")?;
-        let session = inner.ssh.get(&session_id).ok_or("ssh session not found")?;
-        (session.host.clone(), session.port, session.user.clone())
-    };
-
-    // Run port detection command
-    let output = std::process::Command::new("ssh")
-        .arg("-p")
-        .arg(port.to_string())
-        .arg("-o")
-        .arg("StrictHostKeyChecking=no")
-        .arg("-o")
-        .arg("UserKnownHostsFile=/dev/null")
-        .arg(format!("{}@{}", user, host))
-        .arg("~/.jaterm-helper/jaterm-agent detect-ports 2>/dev/null || echo '[]'")
-        .output()
-        .map_err(|e| format!("Failed to run port detection: {}", e))?;
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let ports = serde_json::from_str::<Vec<u16>>(&stdout).unwrap_or_default();
+    let out = crate::commands::agent::agent_exec(
+        &state,
+        Some(&session_id),
+        &["detect-ports".to_string(), "--detailed".to_string()],
+    )?;
+    // Bind address, protocol and owning process per socket, sorted by port
+    let sockets = serde_json::from_str::<Vec<serde_json::Value>>(&out)
+        .map_err(|e| format!("invalid detect-ports output: {}", e))?;
+    let mut ports: Vec<u16> = sockets
+        .iter()
+        .filter_map(|s| s["port"].as_u64())
+        .map(|p| p as u16)
+        .collect();
+    ports.dedup();
 
     eprintln!("[ssh] Manual port detection found {} ports", ports.len());
 
@@ -1149,7 +2191,8 @@ pub async fn ssh_detect_ports(
         "ssh_detected_ports",
         serde_json::json!({
           "sessionId": session_id,
-          "ports": ports
+          "ports": ports,
+          "sockets": sockets
         }),
     );
 
@@ -1165,6 +2208,23 @@ pub async fn ssh_disconnect(
     if let Some(s) = inner.ssh.remove(&session_id) {
         let _ = s.sess.disconnect(None, "bye", None);
     }
+    for cancel in [
+        inner.watchdogs.remove(&session_id),
+        inner.metrics.remove(&session_id),
+    ]
+    .into_iter()
+    .flatten()
+    {
+        cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+    if let Some(agent) = inner.agents.remove(&session_id) {
+        agent.shutdown();
+    }
+    inner.sftp_edits.retain(|_, e| e.session_id != session_id);
+    inner.sync_plans.retain(|_, p| p.session_id() != session_id);
+    inner
+        .watches
+        .retain(|_, w| w.session_id.as_deref() != Some(session_id.as_str()));
     Ok(())
 }
 
@@ -1398,6 +2458,24 @@ pub async fn ssh_open_shell(
     cwd: Option<String>,
     cols: Option<u16>,
     rows: Option<u16>,
+) -> Result<String, String> {
+    let command = cwd.map(|dir| {
+        let esc = dir.replace("'", "'\\''");
+        format!("bash -lc 'cd \"{}\"; exec $SHELL -l'", esc)
+    });
+    open_shell_channel(&app, &state, &session_id, command, cols, rows)
+}
+
+/// Open a terminal channel on a new connection to the session's host,
+/// running `command` on a PTY instead of the login shell when given.
+/// Returns the channel and new session ids as JSON.
+pub(crate) fn open_shell_channel(
+    app: &tauri::AppHandle,
+    state: &crate::state::app_state::AppState,
+    session_id: &str,
+    command: Option<String>,
+    cols: Option<u16>,
+    rows: Option<u16>,
 ) -> Result<String, String> {
     // For splits, create a new SSH connection instead of reusing the existing session
     // This prevents channel interference and makes each split independent
@@ -1407,7 +2485,7 @@ pub async fn ssh_open_shell(
         let inner = state.inner.lock().map_err(|_| "lock")?;
         let s = inner
             .ssh
-            .get(&session_id)
+            .get(session_id)
             .ok_or("ssh session not found")?;
         (s.host.clone(), s.port, s.user.clone(), s.auth.clone())
     };
@@ -1433,6 +2511,8 @@ pub async fn ssh_open_shell(
                 user: user.clone(),
                 auth,
                 is_primary: false, // Splits are not primary by default
+                owner_names: None,
+                env: None,
             },
         );
     }
@@ -1456,9 +2536,7 @@ pub async fn ssh_open_shell(
         let _ = chan.handle_extended_data(ssh2::ExtendedData::Merge);
         
         // Start the shell with retry for non-blocking mode
-        if let Some(dir) = cwd {
-            let esc = dir.replace("'", "'\\''");
-            let cmd = format!("bash -lc 'cd \"{}\"; exec $SHELL -l'", esc);
+        if let Some(cmd) = command {
             retry_would_block(|| chan.exec(&cmd), 10).map_err(|e| format!("exec(shell): {e}"))?;
         } else {
             retry_would_block(|| chan.shell(), 10).map_err(|e| format!("shell: {e}"))?;
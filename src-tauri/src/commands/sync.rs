@@ -0,0 +1,1125 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::commands::ssh::{remote_join, sftp_mkdirs, sftp_rename_overwrite, walk_local};
+use crate::commands::transfer::{
+    fill, local_mode, remote_sha256, sha256_file, LockedSession, CHUNK_SIZE, PROGRESS_INTERVAL,
+};
+use crate::state::app_state::AppState;
+use crate::utils::PathFilter;
+
+// Read from the local root when no other ignore file is given
+const DEFAULT_IGNORE_FILE: &str = ".jatermignore";
+// Suffix of the files a transfer writes before renaming them into place
+const TEMP_SUFFIX: &str = ".jaterm-sync.tmp";
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncMode {
+    /// Make the remote directory an exact copy of the local one
+    Push,
+    /// Make the local directory an exact copy of the remote one
+    Pull,
+    /// Propagate changes both ways, using the last sync to detect deletions
+    TwoWay,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncActionKind {
+    MkdirLocal,
+    MkdirRemote,
+    Upload,
+    Download,
+    DeleteLocal,
+    DeleteRemote,
+}
+
+#[derive(Serialize, Clone)]
+pub struct SyncAction {
+    pub kind: SyncActionKind,
+    /// Path relative to both roots, `/`-separated
+    pub path: String,
+    pub is_dir: bool,
+    pub size: u64,
+    pub reason: String,
+    /// Both sides changed since the last sync; the newer side wins
+    pub conflict: bool,
+}
+
+#[derive(Serialize, Clone)]
+pub struct SyncPlan {
+    pub plan_id: String,
+    pub session_id: String,
+    pub local_dir: String,
+    pub remote_dir: String,
+    pub mode: SyncMode,
+    pub actions: Vec<SyncAction>,
+    pub upload_bytes: u64,
+    pub download_bytes: u64,
+    pub conflicts: usize,
+    pub unchanged: usize,
+    pub skipped: usize,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncOptions {
+    /// Compare SHA-256 when sizes match but mtimes differ
+    pub checksum: Option<bool>,
+    pub exclude: Option<Vec<String>>,
+    /// Ignore file, relative to the local root (default `.jatermignore`)
+    pub ignore_file: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+struct Entry {
+    is_dir: bool,
+    size: u64,
+    mtime: u64,
+    #[serde(default)]
+    mode: u32,
+}
+
+impl Entry {
+    fn same(&self, other: &Entry) -> bool {
+        self.is_dir == other.is_dir
+            && (self.is_dir || (self.size == other.size && self.mtime == other.mtime))
+    }
+}
+
+type Tree = BTreeMap<String, Entry>;
+
+/// A computed plan waiting to be applied or discarded
+pub struct PendingSync {
+    plan: SyncPlan,
+    local: Tree,
+    remote: Tree,
+    baseline_key: String,
+    cancel: Arc<AtomicBool>,
+}
+
+impl PendingSync {
+    pub fn session_id(&self) -> &str {
+        &self.plan.session_id
+    }
+}
+
+#[derive(Serialize)]
+pub struct SyncFailure {
+    pub path: String,
+    pub kind: SyncActionKind,
+    pub error: String,
+}
+
+#[derive(Serialize)]
+pub struct SyncResult {
+    pub applied: usize,
+    pub failed: Vec<SyncFailure>,
+    pub cancelled: bool,
+    pub bytes: u64,
+}
+
+fn baseline_path(key: &str) -> Result<PathBuf, String> {
+    let dir = crate::config::ensure_config_dir(None)
+        .map_err(|e| e.to_string())?
+        .join("sync");
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir.join(format!("{}.json", key)))
+}
+
+fn load_baseline(key: &str) -> Tree {
+    baseline_path(key)
+        .ok()
+        .and_then(|p| fs::read(p).ok())
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default()
+}
+
+/// Remember which paths were identical on both sides after a sync
+fn save_baseline(key: &str, local: &Tree, remote: &Tree) -> Result<(), String> {
+    let synced: Tree = local
+        .iter()
+        .filter(|(rel, l)| remote.get(*rel).is_some_and(|r| r.same(l)))
+        .map(|(rel, l)| (rel.clone(), *l))
+        .collect();
+    let path = baseline_path(key)?;
+    let tmp = path.with_extension("json.tmp");
+    let data = serde_json::to_vec(&synced).map_err(|e| e.to_string())?;
+    fs::write(&tmp, data).map_err(|e| e.to_string())?;
+    fs::rename(&tmp, &path).map_err(|e| e.to_string())
+}
+
+fn rel_string(rel: &Path) -> String {
+    rel.components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn scan_local(root: &Path, filter: &PathFilter, skipped: &mut usize) -> Result<Tree, String> {
+    let mut tree = Tree::new();
+    if !root.exists() {
+        return Ok(tree);
+    }
+    let mut entries = Vec::new();
+    walk_local(root, root, filter, &mut entries, skipped)?;
+    for e in entries {
+        // Left behind by an interrupted download
+        if !e.is_dir && e.rel.to_string_lossy().ends_with(TEMP_SUFFIX) {
+            continue;
+        }
+        tree.insert(
+            rel_string(&e.rel),
+            Entry {
+                is_dir: e.is_dir,
+                size: if e.is_dir { 0 } else { e.size },
+                mtime: if e.is_dir { 0 } else { e.mtime },
+                mode: e.mode,
+            },
+        );
+    }
+    Ok(tree)
+}
+
+/// Walk a remote tree with the same filter rules as the local side.
+/// Symlinks are skipped so neither side follows links into other trees.
+fn scan_remote(
+    ls: &LockedSession,
+    sftp: &ssh2::Sftp,
+    root: &str,
+    rel: &str,
+    filter: &PathFilter,
+    out: &mut Tree,
+    skipped: &mut usize,
+) -> Result<(), String> {
+    let dir = remote_join(root, Path::new(rel));
+    let mut entries = ls
+        .run(|| sftp.readdir(Path::new(&dir)))
+        .map_err(|e| format!("{}: {}", dir, e))?;
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    for (path, st) in entries {
+        let name = match path.file_name().and_then(|n| n.to_str()) {
+            Some(".") | Some("..") | None => continue,
+            Some(n) => n.to_string(),
+        };
+        let child = if rel.is_empty() {
+            name
+        } else {
+            format!("{}/{}", rel, name)
+        };
+        // Left behind by an interrupted upload
+        if st.is_file() && child.ends_with(TEMP_SUFFIX) {
+            continue;
+        }
+        if st.file_type().is_symlink() || filter.is_excluded(Path::new(&child)) {
+            *skipped += 1;
+            continue;
+        }
+        let mode = st.perm.unwrap_or(0) & 0o7777;
+        if st.is_dir() {
+            out.insert(
+                child.clone(),
+                Entry {
+                    is_dir: true,
+                    size: 0,
+                    mtime: 0,
+                    mode,
+                },
+            );
+            scan_remote(ls, sftp, root, &child, filter, out, skipped)?;
+        } else if st.is_file() && filter.includes_file(Path::new(&child)) {
+            out.insert(
+                child,
+                Entry {
+                    is_dir: false,
+                    size: st.size.unwrap_or(0),
+                    mtime: st.mtime.unwrap_or(0),
+                    mode,
+                },
+            );
+        } else {
+            *skipped += 1;
+        }
+    }
+    Ok(())
+}
+
+fn action(kind: SyncActionKind, path: &str, e: &Entry, reason: &str, conflict: bool) -> SyncAction {
+    SyncAction {
+        kind,
+        path: path.to_string(),
+        is_dir: e.is_dir,
+        size: e.size,
+        reason: reason.to_string(),
+        conflict,
+    }
+}
+
+fn is_under(path: &str, dir: &str) -> bool {
+    path.len() > dir.len() && path.starts_with(dir) && path.as_bytes()[dir.len()] == b'/'
+}
+
+/// Decide what to do with every file, then with directories so that a
+/// directory is only deleted when nothing new has to be placed inside it.
+/// A path that is a file on one side and a directory on the other is first
+/// cleared on the losing side; when both changed the directory is kept.
+fn build_actions(
+    mode: SyncMode,
+    local: &Tree,
+    remote: &Tree,
+    base: &Tree,
+    mut content_equal: impl FnMut(&str) -> bool,
+    unchanged: &mut usize,
+) -> Vec<SyncAction> {
+    use SyncActionKind::*;
+    let (mut local, mut remote, mut base) = (local.clone(), remote.clone(), base.clone());
+    let mut clearing = Vec::new();
+    let mismatched: Vec<String> = local
+        .iter()
+        .filter(|(p, l)| remote.get(*p).is_some_and(|r| r.is_dir != l.is_dir))
+        .map(|(p, _)| p.clone())
+        .collect();
+    for rel in &mismatched {
+        // Already cleared as part of a mismatched parent
+        let (Some(l), Some(r)) = (local.get(rel).copied(), remote.get(rel).copied()) else {
+            continue;
+        };
+        let (local_wins, conflict) = match mode {
+            SyncMode::Push => (true, false),
+            SyncMode::Pull => (false, false),
+            SyncMode::TwoWay => {
+                let b = base.get(rel);
+                let local_changed = b.is_none_or(|b| !b.same(&l));
+                let remote_changed = b.is_none_or(|b| !b.same(&r));
+                if local_changed != remote_changed {
+                    (local_changed, false)
+                } else {
+                    (l.is_dir, true)
+                }
+            }
+        };
+        let (loser, kind, winner) = if local_wins {
+            (&mut remote, DeleteRemote, l)
+        } else {
+            (&mut local, DeleteLocal, r)
+        };
+        let reason = if winner.is_dir {
+            "replaced by a directory"
+        } else {
+            "replaced by a file"
+        };
+        let gone: Vec<String> = loser
+            .keys()
+            .filter(|p| *p == rel || is_under(p, rel))
+            .cloned()
+            .collect();
+        for p in gone {
+            if let Some(e) = loser.remove(&p) {
+                clearing.push(action(kind, &p, &e, reason, conflict && p == *rel));
+            }
+        }
+        // With the losing side gone the winner is placed as if it were new
+        base.retain(|p, _| p != rel && !is_under(p, rel));
+    }
+    // Files before directories, directories deepest first
+    clearing.sort_by(|a, b| {
+        a.is_dir.cmp(&b.is_dir).then_with(|| {
+            if a.is_dir {
+                b.path.cmp(&a.path)
+            } else {
+                a.path.cmp(&b.path)
+            }
+        })
+    });
+
+    let mut actions = Vec::new();
+    let mut paths: Vec<&String> = local.keys().chain(remote.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    for rel in paths.iter().copied() {
+        let (l, r) = (local.get(rel), remote.get(rel));
+        if l.is_some_and(|e| e.is_dir) || r.is_some_and(|e| e.is_dir) {
+            continue;
+        }
+        match (l, r) {
+            (Some(l), Some(r)) => {
+                if l.same(r) || (l.size == r.size && content_equal(rel)) {
+                    *unchanged += 1;
+                    continue;
+                }
+                match mode {
+                    SyncMode::Push => actions.push(action(Upload, rel, l, "differs", false)),
+                    SyncMode::Pull => actions.push(action(Download, rel, r, "differs", false)),
+                    SyncMode::TwoWay => {
+                        let b = base.get(rel);
+                        let local_changed = b.is_none_or(|b| !b.same(l));
+                        let remote_changed = b.is_none_or(|b| !b.same(r));
+                        if local_changed && !remote_changed {
+                            actions.push(action(Upload, rel, l, "changed locally", false));
+                        } else if remote_changed && !local_changed {
+                            actions.push(action(Download, rel, r, "changed remotely", false));
+                        } else if l.mtime >= r.mtime {
+                            actions.push(action(Upload, rel, l, "changed on both sides", true));
+                        } else {
+                            actions.push(action(Download, rel, r, "changed on both sides", true));
+                        }
+                    }
+                }
+            }
+            (Some(l), None) => match mode {
+                SyncMode::Pull => actions.push(action(DeleteLocal, rel, l, "not on remote", false)),
+                SyncMode::Push => actions.push(action(Upload, rel, l, "missing on remote", false)),
+                SyncMode::TwoWay => match base.get(rel) {
+                    Some(b) if b.same(l) => {
+                        actions.push(action(DeleteLocal, rel, l, "deleted remotely", false))
+                    }
+                    Some(_) => actions.push(action(
+                        Upload,
+                        rel,
+                        l,
+                        "changed locally, deleted remotely",
+                        true,
+                    )),
+                    None => actions.push(action(Upload, rel, l, "new locally", false)),
+                },
+            },
+            (None, Some(r)) => match mode {
+                SyncMode::Push => {
+                    actions.push(action(DeleteRemote, rel, r, "not in local folder", false))
+                }
+                SyncMode::Pull => actions.push(action(Download, rel, r, "missing locally", false)),
+                SyncMode::TwoWay => match base.get(rel) {
+                    Some(b) if b.same(r) => {
+                        actions.push(action(DeleteRemote, rel, r, "deleted locally", false))
+                    }
+                    Some(_) => actions.push(action(
+                        Download,
+                        rel,
+                        r,
+                        "changed remotely, deleted locally",
+                        true,
+                    )),
+                    None => actions.push(action(Download, rel, r, "new remotely", false)),
+                },
+            },
+            (None, None) => {}
+        }
+    }
+
+    for rel in paths.iter().copied() {
+        let (l, r) = (local.get(rel), remote.get(rel));
+        let fills = |kind: SyncActionKind, acts: &[SyncAction]| {
+            acts.iter()
+                .any(|a| a.kind == kind && is_under(&a.path, rel))
+        };
+        match (l.filter(|e| e.is_dir), r.filter(|e| e.is_dir)) {
+            (Some(_), Some(_)) => {}
+            (Some(l), None) => {
+                let delete = match mode {
+                    SyncMode::Pull => true,
+                    SyncMode::Push => false,
+                    SyncMode::TwoWay => base.contains_key(rel) && !fills(Upload, &actions),
+                };
+                if delete {
+                    actions.push(action(DeleteLocal, rel, l, "not on remote", false));
+                } else {
+                    actions.push(action(MkdirRemote, rel, l, "missing on remote", false));
+                }
+            }
+            (None, Some(r)) => {
+                let delete = match mode {
+                    SyncMode::Push => true,
+                    SyncMode::Pull => false,
+                    SyncMode::TwoWay => base.contains_key(rel) && !fills(Download, &actions),
+                };
+                if delete {
+                    actions.push(action(DeleteRemote, rel, r, "not in local folder", false));
+                } else {
+                    actions.push(action(MkdirLocal, rel, r, "missing locally", false));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Create directories shallow-first, copy files, then delete files before
+    // the directories that contain them
+    let rank = |a: &SyncAction| match (a.kind, a.is_dir) {
+        (MkdirLocal | MkdirRemote, _) => 0,
+        (Upload | Download, _) => 1,
+        (DeleteLocal | DeleteRemote, false) => 2,
+        (DeleteLocal | DeleteRemote, true) => 3,
+    };
+    actions.sort_by(|a, b| {
+        rank(a).cmp(&rank(b)).then_with(|| {
+            if rank(a) == 3 {
+                b.path.cmp(&a.path)
+            } else {
+                a.path.cmp(&b.path)
+            }
+        })
+    });
+    clearing.extend(actions);
+    clearing
+}
+
+#[tauri::command]
+pub async fn ssh_sync_plan(
+    state: State<'_, AppState>,
+    session_id: String,
+    local_dir: String,
+    remote_dir: String,
+    mode: SyncMode,
+    opts: Option<SyncOptions>,
+) -> Result<SyncPlan, String> {
+    let opts = opts.unwrap_or_default();
+    eprintln!(
+        "[sync] plan local={} remote={} mode={:?}",
+        local_dir, remote_dir, mode
+    );
+    let local_root = PathBuf::from(&local_dir);
+
+    let mut exclude = opts.exclude.clone().unwrap_or_default();
+    exclude.extend(read_ignore_patterns(
+        &local_root,
+        opts.ignore_file.as_deref(),
+    )?);
+    let filter = PathFilter::new(&[], &exclude)?;
+
+    let (ls, identity) = {
+        let ls = LockedSession::for_session(&state, &session_id)?;
+        let inner = state.inner.lock().map_err(|_| "lock")?;
+        let s = inner.ssh.get(&session_id).ok_or("ssh session not found")?;
+        (ls, format!("{}@{}:{}", s.user, s.host, s.port))
+    };
+
+    let mut skipped = 0;
+    let local = scan_local(&local_root, &filter, &mut skipped)?;
+    let sftp = ls.run(|| ls.sess.sftp()).map_err(|e| e.to_string())?;
+    let remote = (|| {
+        let mut tree = Tree::new();
+        if ls.run(|| sftp.stat(Path::new(&remote_dir))).is_ok() {
+            scan_remote(
+                &ls,
+                &sftp,
+                &remote_dir,
+                "",
+                &filter,
+                &mut tree,
+                &mut skipped,
+            )?;
+        }
+        Ok::<_, String>(tree)
+    })();
+    ls.run(move || drop(sftp));
+    let remote = remote?;
+
+    let baseline_key = crate::utils::sha256_hex(
+        format!("{}|{}|{}", identity, remote_dir, local_root.display()).as_bytes(),
+    );
+    let base = load_baseline(&baseline_key);
+    let checksum = opts.checksum.unwrap_or(false);
+    let mut unchanged = 0;
+    let actions = build_actions(
+        mode,
+        &local,
+        &remote,
+        &base,
+        |rel| {
+            if !checksum {
+                return false;
+            }
+            // Equal only when both hashes could be computed
+            let Ok(local_hash) = sha256_file(&local_root.join(rel)) else {
+                return false;
+            };
+            remote_sha256(&ls, Path::new(&remote_join(&remote_dir, Path::new(rel))))
+                .is_some_and(|h| h == local_hash)
+        },
+        &mut unchanged,
+    );
+
+    let bytes = |kind: SyncActionKind| -> u64 {
+        actions
+            .iter()
+            .filter(|a| a.kind == kind)
+            .map(|a| a.size)
+            .sum()
+    };
+    let plan = SyncPlan {
+        plan_id: format!("sync_{}", nanoid::nanoid!(8)),
+        session_id: session_id.clone(),
+        local_dir,
+        remote_dir,
+        mode,
+        upload_bytes: bytes(SyncActionKind::Upload),
+        download_bytes: bytes(SyncActionKind::Download),
+        conflicts: actions.iter().filter(|a| a.conflict).count(),
+        unchanged,
+        skipped,
+        actions,
+    };
+    eprintln!(
+        "[sync] plan {} has {} actions ({} conflicts)",
+        plan.plan_id,
+        plan.actions.len(),
+        plan.conflicts
+    );
+
+    let mut inner = state.inner.lock().map_err(|_| "lock")?;
+    inner.sync_plans.insert(
+        plan.plan_id.clone(),
+        PendingSync {
+            plan: plan.clone(),
+            local,
+            remote,
+            baseline_key,
+            cancel: Arc::new(AtomicBool::new(false)),
+        },
+    );
+    Ok(plan)
+}
+
+/// Upload one file through a temp file and rename, so a failure never
+/// truncates the previous copy. Parent directories are created as needed and
+/// the local mode and mtime are carried over. `on_chunk` gets the bytes just
+/// written and returns false to abort; Ok(false) means the upload was aborted.
+pub(crate) fn sftp_upload_file(
+    ls: &LockedSession,
+    sftp: &ssh2::Sftp,
+    local_path: &Path,
+    remote_path: &str,
+    on_chunk: &mut dyn FnMut(usize) -> bool,
+) -> Result<bool, String> {
+    if let Some(parent) = Path::new(remote_path).parent() {
+        let parent = parent.to_string_lossy();
+        ls.run(|| sftp_mkdirs(sftp, &parent))?;
+    }
+    let meta = fs::metadata(local_path).map_err(|e| e.to_string())?;
+    let mut local = fs::File::open(local_path).map_err(|e| e.to_string())?;
+    let tmp = format!("{}{}", remote_path, TEMP_SUFFIX);
+    let mut rfile = ls
+        .run(|| {
+            sftp.open_mode(
+                Path::new(&tmp),
+                ssh2::OpenFlags::WRITE | ssh2::OpenFlags::CREATE | ssh2::OpenFlags::TRUNCATE,
+                local_mode(&meta),
+                ssh2::OpenType::File,
+            )
+        })
+        .map_err(|e| e.to_string())?;
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    let result = loop {
+        let n = match fill(&mut local, &mut buf) {
+            Ok(0) => break Ok(true),
+            Ok(n) => n,
+            Err(e) => break Err(e.to_string()),
+        };
+        if let Err(e) = ls.run(|| rfile.write_all(&buf[..n])) {
+            break Err(format!("Write failed: {}", e));
+        }
+        if !on_chunk(n) {
+            break Ok(false);
+        }
+    };
+    ls.run(move || drop(rfile));
+    let result = result.and_then(|complete| {
+        if complete {
+            ls.run(|| {
+                sftp_rename_overwrite(&ls.sess, sftp, Path::new(&tmp), Path::new(remote_path))
+            })?;
+        }
+        Ok(complete)
+    });
+    if result != Ok(true) {
+        let _ = ls.run(|| sftp.unlink(Path::new(&tmp)));
+        return result;
+    }
+    // Match mtimes so the next comparison sees the pair as in sync
+    let mtime = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs());
+    let _ = ls.run(|| {
+        sftp.setstat(
+            Path::new(remote_path),
+            ssh2::FileStat {
+                size: None,
+                uid: None,
+                gid: None,
+                perm: None,
+                atime: mtime,
+                mtime,
+            },
+        )
+    });
+    Ok(true)
+}
+
+/// Exclude patterns from an ignore file under `root` (`.jatermignore` unless
+/// another name is given). A missing default file is not an error.
+pub(crate) fn read_ignore_patterns(
+    root: &Path,
+    ignore_file: Option<&str>,
+) -> Result<Vec<String>, String> {
+    let path = root.join(ignore_file.unwrap_or(DEFAULT_IGNORE_FILE));
+    match fs::read_to_string(&path) {
+        Ok(contents) => Ok(contents.lines().map(str::to_string).collect()),
+        Err(e) if ignore_file.is_some() => Err(format!("{}: {}", path.display(), e)),
+        Err(_) => Ok(Vec::new()),
+    }
+}
+
+fn set_local_times(path: &Path, mtime: u64) {
+    if let Ok(file) = fs::File::options().write(true).open(path) {
+        let _ = file.set_modified(UNIX_EPOCH + Duration::from_secs(mtime));
+    }
+}
+
+#[cfg(unix)]
+fn set_local_mode(path: &Path, mode: u32) {
+    use std::os::unix::fs::PermissionsExt;
+    if mode != 0 {
+        let _ = fs::set_permissions(path, fs::Permissions::from_mode(mode));
+    }
+}
+
+#[cfg(not(unix))]
+fn set_local_mode(_path: &Path, _mode: u32) {}
+
+struct SyncProgress<'a> {
+    app: &'a AppHandle,
+    plan_id: &'a str,
+    done: usize,
+    total: usize,
+    bytes_done: u64,
+    bytes_total: u64,
+    last_emit: Option<Instant>,
+}
+
+impl SyncProgress<'_> {
+    fn emit(&mut self, path: &str, kind: Option<SyncActionKind>, force: bool) {
+        if !force
+            && self
+                .last_emit
+                .is_some_and(|t| t.elapsed() < PROGRESS_INTERVAL)
+        {
+            return;
+        }
+        self.last_emit = Some(Instant::now());
+        let _ = self.app.emit(
+            crate::events::SSH_SYNC_PROGRESS,
+            &serde_json::json!({
+                "planId": self.plan_id,
+                "path": path,
+                "action": kind,
+                "done": self.done,
+                "total": self.total,
+                "bytesDone": self.bytes_done,
+                "bytesTotal": self.bytes_total
+            }),
+        );
+    }
+}
+
+struct Applier<'a> {
+    ls: LockedSession,
+    sftp: ssh2::Sftp,
+    local_root: PathBuf,
+    remote_root: String,
+    progress: SyncProgress<'a>,
+}
+
+impl Applier<'_> {
+    fn remote(&self, rel: &str) -> String {
+        remote_join(&self.remote_root, Path::new(rel))
+    }
+
+    fn upload(&mut self, rel: &str, cancel: &AtomicBool) -> Result<bool, String> {
+        let local_path = self.local_root.join(rel);
+        let remote_path = self.remote(rel);
+        let progress = &mut self.progress;
+        let mut on_chunk = |n: usize| {
+            progress.bytes_done += n as u64;
+            progress.emit(rel, Some(SyncActionKind::Upload), false);
+            !cancel.load(Ordering::Relaxed)
+        };
+        sftp_upload_file(
+            &self.ls,
+            &self.sftp,
+            &local_path,
+            &remote_path,
+            &mut on_chunk,
+        )
+    }
+
+    fn download(&mut self, rel: &str, e: &Entry, cancel: &AtomicBool) -> Result<bool, String> {
+        let local_path = self.local_root.join(rel);
+        let remote_path = self.remote(rel);
+        if let Some(parent) = local_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let mut rfile = self
+            .ls
+            .run(|| self.sftp.open(Path::new(&remote_path)))
+            .map_err(|e| e.to_string())?;
+        // Write beside the target so an interrupted download never leaves a torn file
+        let tmp = local_path.with_file_name(format!(
+            ".{}{}",
+            local_path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            TEMP_SUFFIX
+        ));
+        let result = (|| -> Result<bool, String> {
+            let mut local = fs::File::create(&tmp).map_err(|e| e.to_string())?;
+            let mut buf = vec![0u8; CHUNK_SIZE];
+            loop {
+                if cancel.load(Ordering::Relaxed) {
+                    return Ok(false);
+                }
+                let n = self
+                    .ls
+                    .run(|| fill(&mut rfile, &mut buf))
+                    .map_err(|e| e.to_string())?;
+                if n == 0 {
+                    break;
+                }
+                local.write_all(&buf[..n]).map_err(|e| e.to_string())?;
+                self.progress.bytes_done += n as u64;
+                self.progress
+                    .emit(rel, Some(SyncActionKind::Download), false);
+            }
+            local.sync_all().map_err(|e| e.to_string())?;
+            Ok(true)
+        })();
+        self.ls.run(move || drop(rfile));
+        match result {
+            Ok(true) => {
+                fs::rename(&tmp, &local_path).map_err(|e| e.to_string())?;
+                set_local_times(&local_path, e.mtime);
+                set_local_mode(&local_path, e.mode);
+                Ok(true)
+            }
+            other => {
+                let _ = fs::remove_file(&tmp);
+                other
+            }
+        }
+    }
+
+    /// Run one action; Ok(false) means it was interrupted by a cancel
+    fn apply(&mut self, a: &SyncAction, e: &Entry, cancel: &AtomicBool) -> Result<bool, String> {
+        use SyncActionKind::*;
+        let rel = a.path.as_str();
+        match (a.kind, a.is_dir) {
+            (Upload, _) => self.upload(rel, cancel),
+            (Download, _) => self.download(rel, e, cancel),
+            (MkdirLocal, _) => fs::create_dir_all(self.local_root.join(rel))
+                .map(|_| true)
+                .map_err(|e| e.to_string()),
+            (MkdirRemote, _) => {
+                let path = self.remote(rel);
+                self.ls.run(|| sftp_mkdirs(&self.sftp, &path)).map(|_| true)
+            }
+            (DeleteLocal, true) => fs::remove_dir(self.local_root.join(rel))
+                .map(|_| true)
+                .map_err(|e| e.to_string()),
+            (DeleteLocal, false) => fs::remove_file(self.local_root.join(rel))
+                .map(|_| true)
+                .map_err(|e| e.to_string()),
+            (DeleteRemote, is_dir) => {
+                let path = self.remote(rel);
+                self.ls
+                    .run(|| {
+                        if is_dir {
+                            self.sftp.rmdir(Path::new(&path))
+                        } else {
+                            self.sftp.unlink(Path::new(&path))
+                        }
+                    })
+                    .map(|_| true)
+                    .map_err(|e| e.to_string())
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn ssh_sync_apply(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    plan_id: String,
+) -> Result<SyncResult, String> {
+    let (plan, mut local, mut remote, baseline_key, cancel) = {
+        let inner = state.inner.lock().map_err(|_| "lock")?;
+        let p = inner
+            .sync_plans
+            .get(&plan_id)
+            .ok_or("sync plan not found")?;
+        (
+            p.plan.clone(),
+            p.local.clone(),
+            p.remote.clone(),
+            p.baseline_key.clone(),
+            p.cancel.clone(),
+        )
+    };
+    eprintln!("[sync] apply {} ({} actions)", plan_id, plan.actions.len());
+
+    let ls = LockedSession::for_session(&state, &plan.session_id)?;
+    let sftp = ls.run(|| ls.sess.sftp()).map_err(|e| e.to_string())?;
+    let mut applier = Applier {
+        ls,
+        sftp,
+        local_root: PathBuf::from(&plan.local_dir),
+        remote_root: plan.remote_dir.clone(),
+        progress: SyncProgress {
+            app: &app,
+            plan_id: &plan_id,
+            done: 0,
+            total: plan.actions.len(),
+            bytes_done: 0,
+            bytes_total: plan.upload_bytes + plan.download_bytes,
+            last_emit: None,
+        },
+    };
+    if plan.mode != SyncMode::Pull {
+        let root = plan.remote_dir.clone();
+        applier.ls.run(|| sftp_mkdirs(&applier.sftp, &root))?;
+    }
+    let _ = fs::create_dir_all(&applier.local_root);
+
+    let mut failed = Vec::new();
+    let mut cancelled = false;
+    for a in &plan.actions {
+        if cancel.load(Ordering::Relaxed) {
+            cancelled = true;
+            break;
+        }
+        let source = match a.kind {
+            SyncActionKind::Upload | SyncActionKind::MkdirRemote | SyncActionKind::DeleteLocal => {
+                local.get(&a.path).copied()
+            }
+            _ => remote.get(&a.path).copied(),
+        };
+        let Some(entry) = source else { continue };
+        applier.progress.emit(&a.path, Some(a.kind), true);
+        match applier.apply(a, &entry, &cancel) {
+            Ok(true) => {
+                match a.kind {
+                    SyncActionKind::Upload | SyncActionKind::MkdirRemote => {
+                        remote.insert(a.path.clone(), entry);
+                    }
+                    SyncActionKind::Download | SyncActionKind::MkdirLocal => {
+                        local.insert(a.path.clone(), entry);
+                    }
+                    SyncActionKind::DeleteLocal => {
+                        local.remove(&a.path);
+                    }
+                    SyncActionKind::DeleteRemote => {
+                        remote.remove(&a.path);
+                    }
+                }
+                applier.progress.done += 1;
+            }
+            Ok(false) => {
+                cancelled = true;
+                break;
+            }
+            Err(e) => {
+                eprintln!("[sync] {:?} {} failed: {}", a.kind, a.path, e);
+                failed.push(SyncFailure {
+                    path: a.path.clone(),
+                    kind: a.kind,
+                    error: e,
+                });
+            }
+        }
+    }
+    applier.progress.emit("", None, true);
+    let Applier {
+        ls, sftp, progress, ..
+    } = applier;
+    let (done, bytes_done) = (progress.done, progress.bytes_done);
+    ls.run(move || drop(sftp));
+
+    if let Err(e) = save_baseline(&baseline_key, &local, &remote) {
+        eprintln!("[sync] failed to save sync state: {}", e);
+    }
+    if let Ok(mut inner) = state.inner.lock() {
+        inner.sync_plans.remove(&plan_id);
+    }
+    Ok(SyncResult {
+        applied: done,
+        failed,
+        cancelled,
+        bytes: bytes_done,
+    })
+}
+
+#[tauri::command]
+pub async fn ssh_sync_cancel(state: State<'_, AppState>, plan_id: String) -> Result<(), String> {
+    let inner = state.inner.lock().map_err(|_| "lock")?;
+    let p = inner
+        .sync_plans
+        .get(&plan_id)
+        .ok_or("sync plan not found")?;
+    p.cancel.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn ssh_sync_discard(state: State<'_, AppState>, plan_id: String) -> Result<(), String> {
+    let mut inner = state.inner.lock().map_err(|_| "lock")?;
+    inner.sync_plans.remove(&plan_id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(size: u64, mtime: u64) -> Entry {
+        Entry {
+            is_dir: false,
+            size,
+            mtime,
+            mode: 0o644,
+        }
+    }
+
+    fn tree(entries: &[(&str, Entry)]) -> Tree {
+        entries.iter().map(|(p, e)| (p.to_string(), *e)).collect()
+    }
+
+    fn kinds(actions: &[SyncAction]) -> Vec<(SyncActionKind, &str)> {
+        actions.iter().map(|a| (a.kind, a.path.as_str())).collect()
+    }
+
+    #[test]
+    fn test_push_mirrors_remote() {
+        let local = tree(&[("a", file(1, 10)), ("b", file(2, 10))]);
+        let remote = tree(&[("a", file(1, 10)), ("b", file(3, 5)), ("c", file(1, 1))]);
+        let mut unchanged = 0;
+        let actions = build_actions(
+            SyncMode::Push,
+            &local,
+            &remote,
+            &Tree::new(),
+            |_| false,
+            &mut unchanged,
+        );
+        assert_eq!(
+            kinds(&actions),
+            vec![
+                (SyncActionKind::Upload, "b"),
+                (SyncActionKind::DeleteRemote, "c")
+            ]
+        );
+        assert_eq!(unchanged, 1);
+    }
+
+    #[test]
+    fn test_two_way_uses_baseline() {
+        let base = tree(&[("gone", file(1, 1)), ("edited", file(1, 1))]);
+        let local = tree(&[("edited", file(2, 5)), ("new", file(1, 1))]);
+        let remote = tree(&[("gone", file(1, 1)), ("edited", file(1, 1))]);
+        let mut unchanged = 0;
+        let actions = build_actions(
+            SyncMode::TwoWay,
+            &local,
+            &remote,
+            &base,
+            |_| false,
+            &mut unchanged,
+        );
+        assert_eq!(
+            kinds(&actions),
+            vec![
+                (SyncActionKind::Upload, "edited"),
+                (SyncActionKind::Upload, "new"),
+                (SyncActionKind::DeleteRemote, "gone")
+            ]
+        );
+        assert!(actions.iter().all(|a| !a.conflict));
+    }
+
+    #[test]
+    fn test_two_way_conflict_newer_wins() {
+        let base = tree(&[("f", file(1, 1))]);
+        let local = tree(&[("f", file(2, 5))]);
+        let remote = tree(&[("f", file(3, 9))]);
+        let mut unchanged = 0;
+        let actions = build_actions(
+            SyncMode::TwoWay,
+            &local,
+            &remote,
+            &base,
+            |_| false,
+            &mut unchanged,
+        );
+        assert_eq!(kinds(&actions), vec![(SyncActionKind::Download, "f")]);
+        assert!(actions[0].conflict);
+    }
+
+    #[test]
+    fn test_file_replacing_directory() {
+        let dir = Entry {
+            is_dir: true,
+            size: 0,
+            mtime: 0,
+            mode: 0o755,
+        };
+        let local = tree(&[("x", dir), ("x/a", file(1, 1))]);
+        let remote = tree(&[("x", file(2, 2))]);
+        let mut unchanged = 0;
+        let actions = build_actions(
+            SyncMode::Pull,
+            &local,
+            &remote,
+            &Tree::new(),
+            |_| false,
+            &mut unchanged,
+        );
+        assert_eq!(
+            kinds(&actions),
+            vec![
+                (SyncActionKind::DeleteLocal, "x/a"),
+                (SyncActionKind::DeleteLocal, "x"),
+                (SyncActionKind::Download, "x")
+            ]
+        );
+
+        // Both sides changed: the directory is kept and the file replaced
+        let actions = build_actions(
+            SyncMode::TwoWay,
+            &local,
+            &remote,
+            &Tree::new(),
+            |_| false,
+            &mut unchanged,
+        );
+        assert_eq!(
+            kinds(&actions),
+            vec![
+                (SyncActionKind::DeleteRemote, "x"),
+                (SyncActionKind::MkdirRemote, "x"),
+                (SyncActionKind::Upload, "x/a")
+            ]
+        );
+        assert!(actions[0].conflict);
+    }
+}
@@ -0,0 +1,765 @@
+use std::fs;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::state::app_state::{
+    AppState, Inner, SftpTransfer, TransferDirection, TransferStatus, TRANSFER_CANCEL,
+    TRANSFER_PAUSE, TRANSFER_RUN,
+};
+
+// Bytes moved per session-lock acquisition; other SFTP calls can interleave between chunks
+pub(crate) const CHUNK_SIZE: usize = 256 * 1024;
+// Minimum time between progress events for one transfer
+pub(crate) const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Serialize)]
+pub struct TransferInfo {
+    pub id: String,
+    pub session_id: String,
+    pub direction: TransferDirection,
+    pub remote_path: String,
+    pub local_path: String,
+    pub total: u64,
+    pub transferred: u64,
+    pub status: TransferStatus,
+    pub error: Option<String>,
+    pub verified: Option<String>,
+    pub created_at: u64,
+}
+
+impl From<&SftpTransfer> for TransferInfo {
+    fn from(t: &SftpTransfer) -> Self {
+        TransferInfo {
+            id: t.id.clone(),
+            session_id: t.session_id.clone(),
+            direction: t.direction,
+            remote_path: t.remote_path.clone(),
+            local_path: t.local_path.clone(),
+            total: t.total,
+            transferred: t.transferred,
+            status: t.status,
+            error: t.error.clone(),
+            verified: t.verified.clone(),
+            created_at: t.created_at,
+        }
+    }
+}
+
+enum Outcome {
+    // Carries the verification that passed ("size" or "sha256")
+    Completed(String),
+    Paused,
+    Cancelled,
+}
+
+/// Session handle used by transfer workers. Every libssh2 call goes through
+/// `run`, which holds the session lock and blocking mode only for that call so
+/// other commands on the session can interleave between chunks.
+pub(crate) struct LockedSession {
+    pub(crate) sess: ssh2::Session,
+    pub(crate) lock: Arc<Mutex<()>>,
+}
+
+impl LockedSession {
+    pub(crate) fn for_session(state: &AppState, session_id: &str) -> Result<Self, String> {
+        let inner = state.inner.lock().map_err(|_| "lock")?;
+        let s = inner.ssh.get(session_id).ok_or("ssh session not found")?;
+        Ok(LockedSession {
+            sess: s.sess.clone(),
+            lock: s.lock.clone(),
+        })
+    }
+
+    pub(crate) fn run<T>(&self, f: impl FnOnce() -> T) -> T {
+        let _guard = self.lock.lock().unwrap();
+        self.sess.set_blocking(true);
+        let out = f();
+        self.sess.set_blocking(false); // Restore non-blocking
+        out
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn emit_state(app: &AppHandle, t: &SftpTransfer) {
+    let _ = app.emit(
+        crate::events::SSH_TRANSFER_STATE,
+        &serde_json::json!({
+            "transferId": t.id,
+            "status": t.status,
+            "error": t.error,
+            "verified": t.verified
+        }),
+    );
+}
+
+/// Read until `buf` is full or the reader is exhausted
+pub(crate) fn fill<R: Read>(r: &mut R, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match r.read(&mut buf[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+pub(crate) fn sha256_file(path: &Path) -> Result<String, String> {
+    use sha2::{Digest, Sha256};
+    let mut file = fs::File::open(path).map_err(|e| e.to_string())?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        let n = file.read(&mut buf).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+#[cfg(unix)]
+pub(crate) fn local_mode(meta: &fs::Metadata) -> i32 {
+    use std::os::unix::fs::PermissionsExt;
+    (meta.permissions().mode() & 0o7777) as i32
+}
+
+#[cfg(not(unix))]
+pub(crate) fn local_mode(_meta: &fs::Metadata) -> i32 {
+    0o644
+}
+
+struct Progress<'a> {
+    app: &'a AppHandle,
+    id: &'a str,
+    direction: TransferDirection,
+    path: &'a str,
+    last_emit: Option<Instant>,
+}
+
+impl Progress<'_> {
+    fn update(&mut self, written: u64, total: u64, force: bool) {
+        if let Ok(mut inner) = self.app.state::<AppState>().inner.lock() {
+            if let Some(t) = inner.transfers.get_mut(self.id) {
+                t.transferred = written;
+                t.total = total;
+            }
+        }
+        if !force {
+            if let Some(last) = self.last_emit {
+                if last.elapsed() < PROGRESS_INTERVAL {
+                    return;
+                }
+            }
+        }
+        self.last_emit = Some(Instant::now());
+        let event = match self.direction {
+            TransferDirection::Upload => crate::events::SSH_UPLOAD_PROGRESS,
+            TransferDirection::Download => crate::events::SSH_DOWNLOAD_PROGRESS,
+        };
+        let _ = self.app.emit(
+            event,
+            &serde_json::json!({
+                "transferId": self.id,
+                "path": self.path,
+                "written": written,
+                "total": total
+            }),
+        );
+    }
+}
+
+/// SHA-256 of a remote file via sha256sum or shasum; None when neither is available
+pub(crate) fn remote_sha256(ls: &LockedSession, remote_path: &Path) -> Option<String> {
+    let quoted = crate::utils::shell_quote(&remote_path.to_string_lossy());
+    let cmd = format!(
+        "sha256sum -- {0} 2>/dev/null || shasum -a 256 -- {0}",
+        quoted
+    );
+    ls.run(|| crate::commands::ssh::run_exec(&ls.sess, &cmd))
+        .ok()
+        .filter(|r| r.exit_code == 0)
+        .and_then(|r| r.stdout.split_whitespace().next().map(|h| h.to_lowercase()))
+        .filter(|h| h.len() == 64)
+}
+
+/// Compare the finished transfer by size and, when asked, by SHA-256.
+/// Falls back to size only when the remote has neither sha256sum nor shasum.
+fn verify(
+    ls: &LockedSession,
+    sftp: &ssh2::Sftp,
+    local_path: &Path,
+    remote_path: &Path,
+    mode: &str,
+) -> Result<String, String> {
+    let local_size = fs::metadata(local_path).map_err(|e| e.to_string())?.len();
+    let remote_size = ls
+        .run(|| sftp.stat(remote_path))
+        .map_err(|e| e.to_string())?
+        .size
+        .unwrap_or(0);
+    if local_size != remote_size {
+        return Err(format!(
+            "size mismatch: local {} bytes, remote {} bytes",
+            local_size, remote_size
+        ));
+    }
+    if mode != "sha256" {
+        return Ok("size".to_string());
+    }
+
+    let remote_hash = remote_sha256(ls, remote_path);
+    match remote_hash {
+        Some(remote_hash) => {
+            if sha256_file(local_path)? == remote_hash {
+                Ok("sha256".to_string())
+            } else {
+                Err("checksum mismatch".to_string())
+            }
+        }
+        None => {
+            eprintln!("[transfer] no remote sha256 tool, verified by size only");
+            Ok("size".to_string())
+        }
+    }
+}
+
+fn download(
+    ls: &LockedSession,
+    progress: &mut Progress,
+    control: &AtomicU8,
+    remote_path: &Path,
+    local_path: &Path,
+    resume: bool,
+    verify_mode: &str,
+) -> Result<Outcome, String> {
+    let (sftp, total, mut rfile) = ls.run(|| -> Result<_, String> {
+        let sftp = ls.sess.sftp().map_err(|e| e.to_string())?;
+        let st = sftp.stat(remote_path).map_err(|e| e.to_string())?;
+        if st.is_dir() {
+            return Err("remote path is a directory".to_string());
+        }
+        let file = sftp.open(remote_path).map_err(|e| e.to_string())?;
+        Ok((sftp, st.size.unwrap_or(0), file))
+    })?;
+
+    let result = (|| -> Result<Outcome, String> {
+        if let Some(parent) = local_path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let existing = if resume {
+            fs::metadata(local_path).map(|m| m.len()).unwrap_or(0)
+        } else {
+            0
+        };
+        // A partial file larger than the source cannot be a prefix of it
+        let offset = if existing <= total { existing } else { 0 };
+        let mut local = fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(local_path)
+            .map_err(|e| e.to_string())?;
+        local.set_len(offset).map_err(|e| e.to_string())?;
+        local
+            .seek(SeekFrom::Start(offset))
+            .map_err(|e| e.to_string())?;
+        if offset > 0 {
+            eprintln!("[transfer] resuming download at {} of {} bytes", offset, total);
+            rfile
+                .seek(SeekFrom::Start(offset))
+                .map_err(|e| e.to_string())?;
+        }
+
+        let mut transferred = offset;
+        progress.update(transferred, total, true);
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        loop {
+            match control.load(Ordering::Relaxed) {
+                TRANSFER_PAUSE => {
+                    progress.update(transferred, total, true);
+                    return Ok(Outcome::Paused);
+                }
+                TRANSFER_CANCEL => {
+                    drop(local);
+                    let _ = fs::remove_file(local_path);
+                    return Ok(Outcome::Cancelled);
+                }
+                _ => {}
+            }
+            let n = ls
+                .run(|| fill(&mut rfile, &mut buf))
+                .map_err(|e| e.to_string())?;
+            if n == 0 {
+                break;
+            }
+            local.write_all(&buf[..n]).map_err(|e| e.to_string())?;
+            transferred += n as u64;
+            progress.update(transferred, total, false);
+        }
+        local.sync_all().map_err(|e| e.to_string())?;
+        progress.update(transferred, total, true);
+
+        verify(ls, &sftp, local_path, remote_path, verify_mode).map(Outcome::Completed)
+    })();
+
+    // Close remote handles in blocking mode
+    ls.run(move || drop((rfile, sftp)));
+    result
+}
+
+fn upload(
+    ls: &LockedSession,
+    progress: &mut Progress,
+    control: &AtomicU8,
+    local_path: &Path,
+    remote_path: &Path,
+    resume: bool,
+    verify_mode: &str,
+) -> Result<Outcome, String> {
+    let meta = fs::metadata(local_path).map_err(|e| e.to_string())?;
+    if meta.is_dir() {
+        return Err("local path is a directory".to_string());
+    }
+    let total = meta.len();
+    let mut local = fs::File::open(local_path).map_err(|e| e.to_string())?;
+
+    let (sftp, existing) = ls.run(|| -> Result<_, String> {
+        let sftp = ls.sess.sftp().map_err(|e| e.to_string())?;
+        let existing = if resume {
+            sftp.stat(remote_path).ok().and_then(|st| st.size).unwrap_or(0)
+        } else {
+            0
+        };
+        Ok((sftp, existing))
+    })?;
+    let offset = if existing <= total { existing } else { 0 };
+    let flags = if offset > 0 {
+        ssh2::OpenFlags::WRITE
+    } else {
+        ssh2::OpenFlags::WRITE | ssh2::OpenFlags::CREATE | ssh2::OpenFlags::TRUNCATE
+    };
+    let mut rfile = match ls.run(|| {
+        sftp.open_mode(remote_path, flags, local_mode(&meta), ssh2::OpenType::File)
+    }) {
+        Ok(f) => f,
+        Err(e) => {
+            ls.run(move || drop(sftp));
+            return Err(e.to_string());
+        }
+    };
+
+    let result = (|| -> Result<Outcome, String> {
+        if offset > 0 {
+            eprintln!("[transfer] resuming upload at {} of {} bytes", offset, total);
+            rfile
+                .seek(SeekFrom::Start(offset))
+                .map_err(|e| e.to_string())?;
+            local
+                .seek(SeekFrom::Start(offset))
+                .map_err(|e| e.to_string())?;
+        }
+
+        let mut transferred = offset;
+        progress.update(transferred, total, true);
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        loop {
+            match control.load(Ordering::Relaxed) {
+                TRANSFER_PAUSE => {
+                    progress.update(transferred, total, true);
+                    return Ok(Outcome::Paused);
+                }
+                TRANSFER_CANCEL => {
+                    let _ = ls.run(|| sftp.unlink(remote_path));
+                    return Ok(Outcome::Cancelled);
+                }
+                _ => {}
+            }
+            let n = fill(&mut local, &mut buf).map_err(|e| e.to_string())?;
+            if n == 0 {
+                break;
+            }
+            ls.run(|| rfile.write_all(&buf[..n]))
+                .map_err(|e| format!("Write failed: {}", e))?;
+            transferred += n as u64;
+            progress.update(transferred, total, false);
+        }
+        // Flush the remote handle before comparing sizes
+        ls.run(|| rfile.fsync()).ok();
+        progress.update(transferred, total, true);
+
+        verify(ls, &sftp, local_path, remote_path, verify_mode).map(Outcome::Completed)
+    })();
+
+    ls.run(move || drop((rfile, sftp)));
+    result
+}
+
+fn run_transfer(app: &AppHandle, id: &str) -> Result<Outcome, String> {
+    let state = app.state::<AppState>();
+    let (ls, direction, remote_path, local_path, resume, verify_mode, control) = {
+        let inner = state.inner.lock().map_err(|_| "lock")?;
+        let t = inner.transfers.get(id).ok_or("transfer not found")?;
+        let s = inner
+            .ssh
+            .get(&t.session_id)
+            .ok_or("ssh session not found")?;
+        (
+            LockedSession {
+                sess: s.sess.clone(),
+                lock: s.lock.clone(),
+            },
+            t.direction,
+            t.remote_path.clone(),
+            t.local_path.clone(),
+            t.resume,
+            t.verify.clone(),
+            t.control.clone(),
+        )
+    };
+
+    let path = match direction {
+        TransferDirection::Upload => &local_path,
+        TransferDirection::Download => &remote_path,
+    };
+    let mut progress = Progress {
+        app,
+        id,
+        direction,
+        path,
+        last_emit: None,
+    };
+    match direction {
+        TransferDirection::Download => download(
+            &ls,
+            &mut progress,
+            &control,
+            Path::new(&remote_path),
+            Path::new(&local_path),
+            resume,
+            &verify_mode,
+        ),
+        TransferDirection::Upload => upload(
+            &ls,
+            &mut progress,
+            &control,
+            Path::new(&local_path),
+            Path::new(&remote_path),
+            resume,
+            &verify_mode,
+        ),
+    }
+}
+
+/// Runs queued transfers for one session, one at a time, until none are left
+fn worker(app: AppHandle, session_id: String) {
+    let state = app.state::<AppState>();
+    loop {
+        let next = {
+            let mut guard = match state.inner.lock() {
+                Ok(g) => g,
+                Err(_) => return,
+            };
+            let inner = &mut *guard;
+            let pos = inner.transfer_queue.iter().position(|id| {
+                inner
+                    .transfers
+                    .get(id)
+                    .map(|t| t.session_id == session_id)
+                    .unwrap_or(false)
+            });
+            let Some(pos) = pos else {
+                inner.transfer_workers.remove(&session_id);
+                return;
+            };
+            let id = inner.transfer_queue.remove(pos).unwrap_or_default();
+            if let Some(t) = inner.transfers.get_mut(&id) {
+                t.status = TransferStatus::Running;
+                t.error = None;
+                t.control.store(TRANSFER_RUN, Ordering::Relaxed);
+                emit_state(&app, t);
+            }
+            id
+        };
+
+        let result = run_transfer(&app, &next);
+
+        let mut inner = match state.inner.lock() {
+            Ok(g) => g,
+            Err(_) => return,
+        };
+        if let Some(t) = inner.transfers.get_mut(&next) {
+            match result {
+                Ok(Outcome::Completed(verified)) => {
+                    t.status = TransferStatus::Completed;
+                    t.verified = Some(verified);
+                }
+                Ok(Outcome::Paused) => t.status = TransferStatus::Paused,
+                Ok(Outcome::Cancelled) => t.status = TransferStatus::Cancelled,
+                Err(e) => {
+                    t.status = TransferStatus::Failed;
+                    t.error = Some(e);
+                }
+            }
+            eprintln!("[transfer] {} finished: {:?}", next, t.status);
+            emit_state(&app, t);
+        }
+    }
+}
+
+/// Queue a transfer and make sure its session has a worker running
+fn queue(app: &AppHandle, inner: &mut Inner, id: String, session_id: &str) {
+    inner.transfer_queue.push_back(id);
+    if inner.transfer_workers.insert(session_id.to_string()) {
+        let app = app.clone();
+        let session_id = session_id.to_string();
+        thread::spawn(move || worker(app, session_id));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn enqueue(
+    app: &AppHandle,
+    state: &AppState,
+    session_id: String,
+    direction: TransferDirection,
+    remote_path: String,
+    local_path: String,
+    resume: Option<bool>,
+    verify: Option<String>,
+) -> Result<String, String> {
+    let verify = match verify.as_deref() {
+        None | Some("size") => "size".to_string(),
+        Some("sha256") => "sha256".to_string(),
+        Some(other) => return Err(format!("unknown verify mode: {}", other)),
+    };
+    let id = format!("xfer_{}", nanoid::nanoid!(8));
+    let mut inner = state.inner.lock().map_err(|_| "lock")?;
+    if !inner.ssh.contains_key(&session_id) {
+        return Err("ssh session not found".into());
+    }
+    let transfer = SftpTransfer {
+        id: id.clone(),
+        session_id: session_id.clone(),
+        direction,
+        remote_path,
+        local_path,
+        total: 0,
+        transferred: 0,
+        status: TransferStatus::Queued,
+        error: None,
+        resume: resume.unwrap_or(false),
+        verify,
+        verified: None,
+        control: Arc::new(AtomicU8::new(TRANSFER_RUN)),
+        created_at: now_ms(),
+    };
+    emit_state(app, &transfer);
+    inner.transfers.insert(id.clone(), transfer);
+    queue(app, &mut inner, id.clone(), &session_id);
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn ssh_transfer_download(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    session_id: String,
+    remote_path: String,
+    local_path: String,
+    resume: Option<bool>,
+    verify: Option<String>,
+) -> Result<String, String> {
+    eprintln!(
+        "[transfer] queue download remote={} local={}",
+        remote_path, local_path
+    );
+    enqueue(
+        &app,
+        &state,
+        session_id,
+        TransferDirection::Download,
+        remote_path,
+        local_path,
+        resume,
+        verify,
+    )
+}
+
+#[tauri::command]
+pub async fn ssh_transfer_upload(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    session_id: String,
+    local_path: String,
+    remote_path: String,
+    resume: Option<bool>,
+    verify: Option<String>,
+) -> Result<String, String> {
+    eprintln!(
+        "[transfer] queue upload local={} remote={}",
+        local_path, remote_path
+    );
+    enqueue(
+        &app,
+        &state,
+        session_id,
+        TransferDirection::Upload,
+        remote_path,
+        local_path,
+        resume,
+        verify,
+    )
+}
+
+#[tauri::command]
+pub async fn ssh_transfer_list(
+    state: State<'_, AppState>,
+    session_id: Option<String>,
+) -> Result<Vec<TransferInfo>, String> {
+    let inner = state.inner.lock().map_err(|_| "lock")?;
+    let mut out: Vec<TransferInfo> = inner
+        .transfers
+        .values()
+        .filter(|t| session_id.as_ref().is_none_or(|s| &t.session_id == s))
+        .map(TransferInfo::from)
+        .collect();
+    out.sort_by_key(|t| t.created_at);
+    Ok(out)
+}
+
+#[tauri::command]
+pub async fn ssh_transfer_pause(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    transfer_id: String,
+) -> Result<(), String> {
+    let mut inner = state.inner.lock().map_err(|_| "lock")?;
+    let status = inner
+        .transfers
+        .get(&transfer_id)
+        .ok_or("transfer not found")?
+        .status;
+    match status {
+        TransferStatus::Queued => {
+            inner.transfer_queue.retain(|id| id != &transfer_id);
+            if let Some(t) = inner.transfers.get_mut(&transfer_id) {
+                t.status = TransferStatus::Paused;
+                emit_state(&app, t);
+            }
+            Ok(())
+        }
+        TransferStatus::Running => {
+            if let Some(t) = inner.transfers.get(&transfer_id) {
+                t.control.store(TRANSFER_PAUSE, Ordering::Relaxed);
+            }
+            Ok(())
+        }
+        _ => Err("transfer is not active".into()),
+    }
+}
+
+#[tauri::command]
+pub async fn ssh_transfer_resume(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    transfer_id: String,
+) -> Result<(), String> {
+    let mut inner = state.inner.lock().map_err(|_| "lock")?;
+    let t = inner
+        .transfers
+        .get_mut(&transfer_id)
+        .ok_or("transfer not found")?;
+    if !matches!(t.status, TransferStatus::Paused | TransferStatus::Failed) {
+        return Err("transfer is not paused or failed".into());
+    }
+    // Continue from whatever already reached the destination
+    t.resume = true;
+    t.status = TransferStatus::Queued;
+    t.error = None;
+    t.control.store(TRANSFER_RUN, Ordering::Relaxed);
+    emit_state(&app, t);
+    let session_id = t.session_id.clone();
+    queue(&app, &mut inner, transfer_id, &session_id);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn ssh_transfer_cancel(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    transfer_id: String,
+) -> Result<(), String> {
+    let (started, direction, session_id, remote_path, local_path) = {
+        let mut inner = state.inner.lock().map_err(|_| "lock")?;
+        let t = inner
+            .transfers
+            .get_mut(&transfer_id)
+            .ok_or("transfer not found")?;
+        match t.status {
+            TransferStatus::Running => {
+                // The worker removes the partial file itself
+                t.control.store(TRANSFER_CANCEL, Ordering::Relaxed);
+                return Ok(());
+            }
+            TransferStatus::Queued | TransferStatus::Paused | TransferStatus::Failed => {
+                t.status = TransferStatus::Cancelled;
+                emit_state(&app, t);
+                let info = (
+                    t.transferred > 0,
+                    t.direction,
+                    t.session_id.clone(),
+                    t.remote_path.clone(),
+                    t.local_path.clone(),
+                );
+                inner.transfer_queue.retain(|id| id != &transfer_id);
+                info
+            }
+            _ => return Err("transfer is not active".into()),
+        }
+    };
+
+    // Drop partial data left by an earlier attempt; a transfer that never
+    // started has not touched the destination
+    if !started {
+        return Ok(());
+    }
+    match direction {
+        TransferDirection::Download => {
+            let _ = fs::remove_file(&local_path);
+        }
+        TransferDirection::Upload => {
+            let _ = crate::commands::ssh::with_sftp(&state, &session_id, |_, sftp| {
+                sftp.unlink(Path::new(&remote_path)).map_err(|e| e.to_string())
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Forget transfers that are no longer active
+#[tauri::command]
+pub async fn ssh_transfer_clear(state: State<'_, AppState>) -> Result<(), String> {
+    let mut inner = state.inner.lock().map_err(|_| "lock")?;
+    inner.transfers.retain(|_, t| {
+        matches!(
+            t.status,
+            TransferStatus::Queued | TransferStatus::Running | TransferStatus::Paused
+        )
+    });
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::commands::agent_rpc;
+use crate::state::app_state::AppState;
+
+/// Turn one event of `jaterm-agent watch` output into app events
+fn forward_event(app: &AppHandle, session_id: &str, event: Value) {
+    match event.get("type").and_then(Value::as_str) {
+        Some("git") => {
+            let _ = app.emit(
+                crate::events::GIT_STATUS,
+                &serde_json::json!({
+                    "sessionId": session_id,
+                    "cwd": event["cwd"],
+                    "status": event["status"]
+                }),
+            );
+        }
+        Some("ports") => {
+            let _ = app.emit(
+                crate::events::SSH_DETECTED_PORTS,
+                &serde_json::json!({
+                    "sessionId": session_id,
+                    "ports": event["ports"],
+                    // Bind address, protocol and owning process per socket
+                    "sockets": event["sockets"]
+                }),
+            );
+        }
+        Some("cwd") => {
+            let _ = app.emit(
+                crate::events::SSH_REMOTE_CWD,
+                &serde_json::json!({
+                    "sessionId": session_id,
+                    "cwd": event["cwd"]
+                }),
+            );
+        }
+        Some("hello") => {
+            eprintln!(
+                "[watchdog] {} agent {} streaming",
+                session_id, event["version"]
+            );
+        }
+        _ => {}
+    }
+}
+
+fn run_watchdog(app: &AppHandle, session_id: &str, args: &[String], cancel: &AtomicBool) {
+    let result = agent_rpc::connect(&app.state::<AppState>(), Some(session_id)).and_then(|agent| {
+        agent.call_streaming("watch", args, Some(cancel), |event| {
+            forward_event(app, session_id, event)
+        })
+    });
+    match result {
+        Ok(_) => eprintln!("[watchdog] {} stopped", session_id),
+        Err(_) if cancel.load(Ordering::Relaxed) => eprintln!("[watchdog] {} stopped", session_id),
+        Err(e) => eprintln!("[watchdog] {} not running: {}", session_id, e),
+    }
+}
+
+/// Start streaming git status, listening ports and cwd changes for a session
+/// through its helper agent, replacing any watchdog already running for it
+pub(crate) fn start(
+    app: &AppHandle,
+    state: &AppState,
+    session_id: &str,
+    dir: Option<&str>,
+    pid: Option<u32>,
+) -> Result<(), String> {
+    let cancel = Arc::new(AtomicBool::new(false));
+    {
+        let mut inner = state.inner.lock().map_err(|_| "lock")?;
+        if !inner.ssh.contains_key(session_id) {
+            return Err("ssh session not found".into());
+        }
+        if let Some(old) = inner
+            .watchdogs
+            .insert(session_id.to_string(), cancel.clone())
+        {
+            old.store(true, Ordering::Relaxed);
+        }
+    }
+
+    // The agent does not go through a shell, so "~" is expanded by the helper
+    let mut args = vec![dir.unwrap_or("~").to_string()];
+    if let Some(pid) = pid {
+        args.push("--pid".into());
+        args.push(pid.to_string());
+    }
+
+    let app = app.clone();
+    let id = session_id.to_string();
+    thread::spawn(move || {
+        run_watchdog(&app, &id, &args, &cancel);
+        if let Ok(mut inner) = app.state::<AppState>().inner.lock() {
+            // A restart may already have replaced this watchdog's entry
+            if inner
+                .watchdogs
+                .get(&id)
+                .is_some_and(|c| Arc::ptr_eq(c, &cancel))
+            {
+                inner.watchdogs.remove(&id);
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Point the session watchdog at another directory, or at the working
+/// directory of a remote process (e.g. the shell) when `pid` is given
+#[tauri::command]
+pub async fn ssh_watchdog_start(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    session_id: String,
+    dir: Option<String>,
+    pid: Option<u32>,
+) -> Result<(), String> {
+    start(&app, &state, &session_id, dir.as_deref(), pid)
+}
+
+#[tauri::command]
+pub async fn ssh_watchdog_stop(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<(), String> {
+    let mut inner = state.inner.lock().map_err(|_| "lock")?;
+    if let Some(cancel) = inner.watchdogs.remove(&session_id) {
+        cancel.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
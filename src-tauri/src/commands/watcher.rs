@@ -1,5 +1,359 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use notify::{EventKind, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::commands::ssh::{remote_join, sftp_mkdirs, walk_local};
+use crate::commands::sync::{read_ignore_patterns, sftp_upload_file};
+use crate::commands::transfer::LockedSession;
+use crate::state::app_state::{AppState, FileWatch};
+use crate::utils::PathFilter;
+
+const DEFAULT_DEBOUNCE_MS: u64 = 300;
+// Flush even while events keep arriving, so a busy tree still gets uploaded
+const MAX_DEBOUNCE_FACTOR: u32 = 10;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchSubscription {
+    pub subscription_id: String,
+}
+
+fn event_kind(kind: &EventKind) -> Option<&'static str> {
+    match kind {
+        EventKind::Create(_) => Some("create"),
+        EventKind::Modify(notify::event::ModifyKind::Name(_)) => Some("rename"),
+        EventKind::Modify(_) => Some("modify"),
+        EventKind::Remove(_) => Some("remove"),
+        EventKind::Access(_) => None,
+        _ => Some("other"),
+    }
+}
+
+/// Start a watcher that emits WATCH_EVENT for every change and hands the
+/// changed paths to `forward`, flagged when they were created or renamed
+fn start_watch(
+    app: &AppHandle,
+    id: &str,
+    paths: &[String],
+    forward: Option<mpsc::Sender<(PathBuf, bool)>>,
+) -> Result<notify::RecommendedWatcher, String> {
+    let app_for_events = app.clone();
+    let sub_id = id.to_string();
+    let mut watcher =
+        notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+            Ok(event) => {
+                let Some(kind) = event_kind(&event.kind) else {
+                    return;
+                };
+                let _ = app_for_events.emit(
+                    crate::events::WATCH_EVENT,
+                    &serde_json::json!({
+                        "subscriptionId": sub_id,
+                        "kind": kind,
+                        "paths": event.paths
+                    }),
+                );
+                if let Some(tx) = &forward {
+                    let created = matches!(kind, "create" | "rename");
+                    for path in event.paths {
+                        let _ = tx.send((path, created));
+                    }
+                }
+            }
+            Err(e) => eprintln!("[watch] {} error: {}", sub_id, e),
+        })
+        .map_err(|e| e.to_string())?;
+
+    for path in paths {
+        let p = Path::new(path);
+        let mode = if p.is_dir() {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        watcher
+            .watch(p, mode)
+            .map_err(|e| format!("{}: {}", path, e))?;
+    }
+    Ok(watcher)
+}
+
 #[tauri::command]
-pub async fn watch_subscribe() -> Result<(), String> {
-    // TODO: initialize notify watcher and emit events
+pub async fn watch_subscribe(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    paths: Vec<String>,
+) -> Result<WatchSubscription, String> {
+    let id = format!("watch_{}", nanoid::nanoid!(8));
+    let watcher = start_watch(&app, &id, &paths, None)?;
+    eprintln!("[watch] {} watching {:?}", id, paths);
+    let mut inner = state.inner.lock().map_err(|_| "lock")?;
+    inner.watches.insert(
+        id.clone(),
+        FileWatch {
+            watcher,
+            session_id: None,
+        },
+    );
+    Ok(WatchSubscription {
+        subscription_id: id,
+    })
+}
+
+#[tauri::command]
+pub async fn watch_unsubscribe(
+    state: State<'_, AppState>,
+    subscription_id: String,
+) -> Result<(), String> {
+    let mut inner = state.inner.lock().map_err(|_| "lock")?;
+    // Dropping the watcher stops it and ends any upload worker attached to it
+    if inner.watches.remove(&subscription_id).is_some() {
+        eprintln!("[watch] {} stopped", subscription_id);
+    }
     Ok(())
 }
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchUploadOptions {
+    pub debounce_ms: Option<u64>,
+    pub exclude: Option<Vec<String>>,
+    /// Ignore file, relative to the local root (default `.jatermignore`)
+    pub ignore_file: Option<String>,
+    /// Also remove remote files when they are deleted locally
+    pub delete: Option<bool>,
+}
+
+struct UploadMapping {
+    id: String,
+    session_id: String,
+    local_root: PathBuf,
+    remote_root: String,
+    filter: PathFilter,
+    delete: bool,
+}
+
+impl UploadMapping {
+    fn report(&self, app: &AppHandle, rel: &Path, status: &str, error: Option<String>) {
+        let _ = app.emit(
+            crate::events::SSH_WATCH_UPLOAD,
+            &serde_json::json!({
+                "subscriptionId": self.id,
+                "path": self.local_root.join(rel),
+                "remotePath": remote_join(&self.remote_root, rel),
+                "status": status,
+                "error": error
+            }),
+        );
+    }
+
+    /// Mirror one changed path: upload files, create directories and
+    /// optionally delete what disappeared. A directory that was created or
+    /// moved in is uploaded with its contents, since only the top may be reported.
+    fn push(
+        &self,
+        app: &AppHandle,
+        ls: &LockedSession,
+        sftp: &ssh2::Sftp,
+        rel: &Path,
+        created: bool,
+    ) -> Result<(), String> {
+        let local = self.local_root.join(rel);
+        let remote = remote_join(&self.remote_root, rel);
+        let is_dir = std::fs::symlink_metadata(&local).is_ok_and(|m| m.is_dir());
+        if is_dir {
+            ls.run(|| sftp_mkdirs(sftp, &remote))?;
+            self.report(app, rel, "mkdir", None);
+            if created {
+                let mut entries = Vec::new();
+                let mut skipped = 0;
+                walk_local(
+                    &self.local_root,
+                    &local,
+                    &self.filter,
+                    &mut entries,
+                    &mut skipped,
+                )?;
+                for entry in entries {
+                    self.push(app, ls, sftp, &entry.rel, false)?;
+                }
+            }
+        } else if local.is_file() {
+            sftp_upload_file(ls, sftp, &local, &remote, &mut |_| true)?;
+            self.report(app, rel, "uploaded", None);
+        } else if !local.exists() && self.delete {
+            let p = Path::new(&remote);
+            ls.run(|| match sftp.lstat(p) {
+                Ok(st) if st.is_dir() => sftp.rmdir(p),
+                Ok(_) => sftp.unlink(p),
+                Err(e) => Err(e),
+            })
+            .map_err(|e| e.to_string())?;
+            self.report(app, rel, "deleted", None);
+        }
+        Ok(())
+    }
+
+    fn flush(&self, app: &AppHandle, changes: BTreeMap<PathBuf, bool>) {
+        let changes: BTreeMap<PathBuf, bool> = changes
+            .into_iter()
+            .filter_map(|(p, created)| {
+                let rel = p.strip_prefix(&self.local_root).ok()?.to_path_buf();
+                (!rel.as_os_str().is_empty()).then_some((rel, created))
+            })
+            .filter(|(rel, _)| {
+                !rel.ancestors()
+                    .any(|a| !a.as_os_str().is_empty() && self.filter.is_excluded(a))
+                    && (self.local_root.join(rel).is_dir() || self.filter.includes_file(rel))
+            })
+            .collect();
+        // A new directory is walked as a whole, so skip changes reported inside it
+        let new_dirs: Vec<&PathBuf> = changes
+            .iter()
+            .filter(|(rel, created)| **created && self.local_root.join(rel).is_dir())
+            .map(|(rel, _)| rel)
+            .collect();
+        let rels: Vec<(&PathBuf, bool)> = changes
+            .iter()
+            .filter(|(rel, _)| !new_dirs.iter().any(|d| rel.starts_with(d) && rel != d))
+            .map(|(rel, created)| (rel, *created))
+            .collect();
+        if rels.is_empty() {
+            return;
+        }
+
+        let sftp =
+            LockedSession::for_session(&app.state::<AppState>(), &self.session_id).and_then(|ls| {
+                let sftp = ls.run(|| ls.sess.sftp()).map_err(|e| e.to_string())?;
+                Ok((ls, sftp))
+            });
+        let (ls, sftp) = match sftp {
+            Ok(pair) => pair,
+            Err(e) => {
+                for (rel, _) in &rels {
+                    self.report(app, rel, "error", Some(e.clone()));
+                }
+                return;
+            }
+        };
+        // Parents before children for uploads; children before parents for deletes
+        let (present, gone): (Vec<_>, Vec<_>) = rels
+            .into_iter()
+            .partition(|(rel, _)| self.local_root.join(rel).exists());
+        for (rel, created) in present.into_iter().chain(gone.into_iter().rev()) {
+            if let Err(e) = self.push(app, &ls, &sftp, rel, created) {
+                eprintln!("[watch] upload {} failed: {}", rel.display(), e);
+                self.report(app, rel, "error", Some(e));
+            }
+        }
+        ls.run(move || drop(sftp));
+    }
+}
+
+/// Collects changed paths until the tree has been quiet for `debounce`, then
+/// uploads them in one batch. Ends when the watcher is dropped.
+fn upload_worker(
+    app: AppHandle,
+    mapping: UploadMapping,
+    rx: mpsc::Receiver<(PathBuf, bool)>,
+    debounce: Duration,
+) {
+    let mut pending = BTreeMap::new();
+    let mut first_change: Option<Instant> = None;
+    loop {
+        match rx.recv_timeout(debounce) {
+            Ok((path, created)) => {
+                *pending.entry(path).or_insert(false) |= created;
+                let first = *first_change.get_or_insert_with(Instant::now);
+                if first.elapsed() < debounce * MAX_DEBOUNCE_FACTOR {
+                    continue;
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+        if !pending.is_empty() {
+            first_change = None;
+            mapping.flush(&app, std::mem::take(&mut pending));
+        }
+    }
+    eprintln!("[watch] upload worker {} exiting", mapping.id);
+}
+
+#[tauri::command]
+pub async fn watch_upload_start(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    session_id: String,
+    local_dir: String,
+    remote_dir: String,
+    opts: Option<WatchUploadOptions>,
+) -> Result<WatchSubscription, String> {
+    let opts = opts.unwrap_or_default();
+    let local_root = PathBuf::from(&local_dir)
+        .canonicalize()
+        .map_err(|e| format!("{}: {}", local_dir, e))?;
+    if !local_root.is_dir() {
+        return Err(format!("{} is not a directory", local_dir));
+    }
+    if !state
+        .inner
+        .lock()
+        .map_err(|_| "lock")?
+        .ssh
+        .contains_key(&session_id)
+    {
+        return Err("ssh session not found".into());
+    }
+
+    let mut exclude = opts.exclude.unwrap_or_default();
+    exclude.extend(read_ignore_patterns(
+        &local_root,
+        opts.ignore_file.as_deref(),
+    )?);
+    let filter = PathFilter::new(&[], &exclude)?;
+    let debounce = Duration::from_millis(opts.debounce_ms.unwrap_or(DEFAULT_DEBOUNCE_MS));
+
+    let id = format!("watch_{}", nanoid::nanoid!(8));
+    let (tx, rx) = mpsc::channel();
+    let watcher = start_watch(
+        &app,
+        &id,
+        &[local_root.to_string_lossy().to_string()],
+        Some(tx),
+    )?;
+    let mapping = UploadMapping {
+        id: id.clone(),
+        session_id: session_id.clone(),
+        local_root,
+        remote_root: remote_dir.clone(),
+        filter,
+        delete: opts.delete.unwrap_or(false),
+    };
+    eprintln!(
+        "[watch] {} uploading {} -> {}",
+        id,
+        mapping.local_root.display(),
+        remote_dir
+    );
+    let app_for_worker = app.clone();
+    thread::spawn(move || upload_worker(app_for_worker, mapping, rx, debounce));
+
+    let mut inner = state.inner.lock().map_err(|_| "lock")?;
+    inner.watches.insert(
+        id.clone(),
+        FileWatch {
+            watcher,
+            session_id: Some(session_id),
+        },
+    );
+    Ok(WatchSubscription {
+        subscription_id: id,
+    })
+}
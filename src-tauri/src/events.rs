@@ -1,14 +1,24 @@
 // Centralized backend event names to keep parity with frontend
 pub const PTY_OUTPUT: &str = "PTY_OUTPUT";
 pub const PTY_EXIT: &str = "PTY_EXIT";
-#[allow(dead_code)]
 pub const GIT_STATUS: &str = "GIT_STATUS";
-#[allow(dead_code)]
 pub const WATCH_EVENT: &str = "WATCH_EVENT";
+// Log viewer streams (kept apart from terminal output)
+pub const LOG_VIEWER_LINES: &str = "LOG_VIEWER_LINES";
+pub const LOG_VIEWER_END: &str = "LOG_VIEWER_END";
 // SSH channel events
 pub const SSH_OUTPUT: &str = "SSH_OUTPUT";
 pub const SSH_EXIT: &str = "SSH_EXIT";
 pub const SSH_UPLOAD_PROGRESS: &str = "SSH_UPLOAD_PROGRESS";
+pub const SSH_DOWNLOAD_PROGRESS: &str = "SSH_DOWNLOAD_PROGRESS";
+pub const SSH_TRANSFER_STATE: &str = "SSH_TRANSFER_STATE";
+pub const SSH_SYNC_PROGRESS: &str = "SSH_SYNC_PROGRESS";
+pub const SSH_WATCH_UPLOAD: &str = "SSH_WATCH_UPLOAD";
+pub const SSH_SEARCH_RESULTS: &str = "SSH_SEARCH_RESULTS";
+pub const SSH_SEARCH_DONE: &str = "SSH_SEARCH_DONE";
+pub const SSH_DETECTED_PORTS: &str = "ssh_detected_ports";
+pub const SSH_REMOTE_CWD: &str = "SSH_REMOTE_CWD";
+pub const SSH_METRICS: &str = "SSH_METRICS";
 pub const SSH_OPENED: &str = "SSH_OPENED";
 #[allow(dead_code)]
 pub const SSH_TUNNEL_STATE: &str = "SSH_TUNNEL_STATE";
@@ -65,17 +65,75 @@ fn main() {
             commands::ssh::scan_ssh_keys,
             commands::ssh::ssh_sftp_list,
             commands::ssh::ssh_sftp_mkdirs,
+            commands::ssh::ssh_sftp_rename,
+            commands::ssh::ssh_sftp_delete,
+            commands::ssh::ssh_sftp_chmod,
+            commands::ssh::ssh_sftp_chown,
+            commands::ssh::ssh_sftp_symlink,
+            commands::ssh::ssh_sftp_stat,
+            commands::ssh::ssh_sftp_lstat,
+            commands::ssh::ssh_sftp_copy,
             commands::ssh::ssh_sftp_read,
             commands::ssh::ssh_sftp_write,
+            commands::ssh::ssh_sftp_edit_open,
+            commands::ssh::ssh_sftp_edit_save,
+            commands::ssh::ssh_sftp_edit_close,
             commands::ssh::ssh_deploy_helper,
             commands::ssh::ssh_sftp_download,
             commands::ssh::ssh_sftp_download_dir,
+            commands::ssh::ssh_sftp_upload_dir,
             commands::ssh::ssh_exec,
+            commands::transfer::ssh_transfer_download,
+            commands::transfer::ssh_transfer_upload,
+            commands::transfer::ssh_transfer_list,
+            commands::transfer::ssh_transfer_pause,
+            commands::transfer::ssh_transfer_resume,
+            commands::transfer::ssh_transfer_cancel,
+            commands::transfer::ssh_transfer_clear,
+            commands::sync::ssh_sync_plan,
+            commands::sync::ssh_sync_apply,
+            commands::sync::ssh_sync_cancel,
+            commands::sync::ssh_sync_discard,
+            commands::search::ssh_search_start,
+            commands::search::ssh_search_cancel,
+            commands::watchdog::ssh_watchdog_start,
+            commands::watchdog::ssh_watchdog_stop,
+            commands::metrics::ssh_metrics_start,
+            commands::metrics::ssh_metrics_stop,
+            commands::processes::process_list,
+            commands::processes::process_kill,
+            commands::logs::log_stream_start,
+            commands::logs::log_stream_stop,
+            commands::containers::container_list,
+            commands::containers::container_shell,
+            commands::environment::env_report,
+            commands::environment::ssh_install_shell_integration,
+            commands::environment::ssh_uninstall_shell_integration,
             commands::helper::helper_local_ensure,
             commands::helper::helper_local_exec,
+            commands::helper::helper_call,
             commands::helper::helper_get_version,
             commands::git::git_status,
+            commands::git::git_log,
+            commands::git::git_branches,
+            commands::git::git_branch_create,
+            commands::git::git_checkout,
+            commands::git::git_branch_delete,
+            commands::git::git_branch_rename,
+            commands::git::git_branch_set_upstream,
+            commands::git::git_diff,
+            commands::git::git_apply_lines,
+            commands::git::git_stash_list,
+            commands::git::git_stash_push,
+            commands::git::git_stash_apply,
+            commands::git::git_stash_pop,
+            commands::git::git_stash_drop,
+            commands::git::git_stash_show,
+            commands::git::git_operation,
+            commands::git::git_resolve,
             commands::watcher::watch_subscribe,
+            commands::watcher::watch_unsubscribe,
+            commands::watcher::watch_upload_start,
             commands::encryption::encryption_status,
             commands::encryption::set_master_key,
             commands::encryption::verify_master_key,
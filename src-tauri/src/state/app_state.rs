@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::io::Write as IoWrite;
 use std::sync::{Arc, Mutex};
 
@@ -31,6 +31,25 @@ pub struct Inner {
     pub ssh: HashMap<String, SshSession>,
     pub ssh_channels: HashMap<String, SshChannel>,
     pub forwards: HashMap<String, SshForward>,
+    pub sftp_edits: HashMap<String, SftpEdit>,
+    pub transfers: HashMap<String, SftpTransfer>,
+    // Transfer ids waiting to run, in order
+    pub transfer_queue: VecDeque<String>,
+    // Sessions that currently have a transfer worker thread
+    pub transfer_workers: HashSet<String>,
+    // Directory sync plans waiting to be applied
+    pub sync_plans: HashMap<String, crate::commands::sync::PendingSync>,
+    pub watches: HashMap<String, FileWatch>,
+    // Cancel flags for running remote searches
+    pub searches: HashMap<String, Arc<std::sync::atomic::AtomicBool>>,
+    // Cancel flags for helper watch streams, by SSH session id
+    pub watchdogs: HashMap<String, Arc<std::sync::atomic::AtomicBool>>,
+    // Cancel flags for host metrics streams, by SSH session id
+    pub metrics: HashMap<String, Arc<std::sync::atomic::AtomicBool>>,
+    // Cancel flags for log viewer streams
+    pub log_streams: HashMap<String, Arc<std::sync::atomic::AtomicBool>>,
+    // Helper agents serving RPC, by SSH session id ("local" for this machine)
+    pub agents: HashMap<String, Arc<crate::commands::agent_rpc::Agent>>,
 }
 
 impl Default for AppState {
@@ -49,6 +68,17 @@ impl Default for AppState {
                 ssh: HashMap::new(),
                 ssh_channels: HashMap::new(),
                 forwards: HashMap::new(),
+                sftp_edits: HashMap::new(),
+                transfers: HashMap::new(),
+                transfer_queue: VecDeque::new(),
+                transfer_workers: HashSet::new(),
+                sync_plans: HashMap::new(),
+                watches: HashMap::new(),
+                searches: HashMap::new(),
+                watchdogs: HashMap::new(),
+                metrics: HashMap::new(),
+                log_streams: HashMap::new(),
+                agents: HashMap::new(),
             })),
             encryption,
             encryption_v2,
@@ -107,6 +137,16 @@ pub struct SshSession {
     pub auth: Option<crate::commands::ssh::SshAuth>,
     // Track if this is the primary connection for Git/SFTP
     pub is_primary: bool,
+    // uid/gid names from the remote passwd/group files, loaded on first listing
+    pub owner_names: Option<RemoteOwnerNames>,
+    // Host report from the helper's `env`, loaded on first request
+    pub env: Option<crate::commands::environment::EnvReport>,
+}
+
+#[derive(Default, Clone)]
+pub struct RemoteOwnerNames {
+    pub users: HashMap<u32, String>,
+    pub groups: HashMap<u32, String>,
 }
 
 pub struct SshChannel {
@@ -143,3 +183,68 @@ pub struct SshForward {
     pub dst_port: u16,
     pub backend: ForwardBackend,
 }
+
+// Remote file opened for editing; remembers what the file looked like on open
+// (or last save) so a later save can detect concurrent changes.
+pub struct SftpEdit {
+    #[allow(dead_code)]
+    pub id: String,
+    pub session_id: String,
+    pub remote_path: String,
+    pub size: u64,
+    pub mtime: Option<u64>,
+    pub hash: String,
+    // Content at open/last save, used as the base for conflict diffs
+    pub base: Vec<u8>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferDirection {
+    Upload,
+    Download,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferStatus {
+    Queued,
+    Running,
+    Paused,
+    Cancelled,
+    Completed,
+    Failed,
+}
+
+// Values for SftpTransfer::control, polled by the worker between chunks
+pub const TRANSFER_RUN: u8 = 0;
+pub const TRANSFER_PAUSE: u8 = 1;
+pub const TRANSFER_CANCEL: u8 = 2;
+
+pub struct SftpTransfer {
+    pub id: String,
+    pub session_id: String,
+    pub direction: TransferDirection,
+    pub remote_path: String,
+    pub local_path: String,
+    pub total: u64,
+    pub transferred: u64,
+    pub status: TransferStatus,
+    pub error: Option<String>,
+    // Continue from the bytes already at the destination
+    pub resume: bool,
+    // "size" or "sha256"
+    pub verify: String,
+    // Which check actually passed once completed
+    pub verified: Option<String>,
+    pub control: Arc<std::sync::atomic::AtomicU8>,
+    pub created_at: u64,
+}
+
+pub struct FileWatch {
+    // Dropping the watcher stops it
+    #[allow(dead_code)]
+    pub watcher: notify::RecommendedWatcher,
+    // Set for watch-upload mappings, which end with their session
+    pub session_id: Option<String>,
+}
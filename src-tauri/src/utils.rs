@@ -1 +1,243 @@
 // Small helpers go here
+
+/// Quote a string for safe use as a single POSIX shell argument
+pub fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// Hex-encoded SHA-256 of a byte slice
+pub fn sha256_hex(data: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    let digest = Sha256::digest(data);
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Name of the file type encoded in a POSIX st_mode
+pub fn file_type_name(mode: u32) -> &'static str {
+    match mode & 0o170000 {
+        0o040000 => "dir",
+        0o100000 => "file",
+        0o120000 => "symlink",
+        0o010000 => "fifo",
+        0o140000 => "socket",
+        0o020000 => "char",
+        0o060000 => "block",
+        _ => "unknown",
+    }
+}
+
+/// ls-style mode string, e.g. `drwxr-xr-x` or `-rwsr-x--T`
+pub fn mode_string(mode: u32) -> String {
+    let kind = match file_type_name(mode) {
+        "dir" => 'd',
+        "symlink" => 'l',
+        "fifo" => 'p',
+        "socket" => 's',
+        "char" => 'c',
+        "block" => 'b',
+        _ => '-',
+    };
+    let mut out = String::with_capacity(10);
+    out.push(kind);
+    // (read bit, write bit, exec bit, special bit, special char)
+    let triads = [
+        (0o400, 0o200, 0o100, 0o4000, 's'),
+        (0o040, 0o020, 0o010, 0o2000, 's'),
+        (0o004, 0o002, 0o001, 0o1000, 't'),
+    ];
+    for (r, w, x, special, c) in triads {
+        out.push(if mode & r != 0 { 'r' } else { '-' });
+        out.push(if mode & w != 0 { 'w' } else { '-' });
+        out.push(match (mode & x != 0, mode & special != 0) {
+            (true, true) => c,
+            (false, true) => c.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    out
+}
+
+// Upper bound on the LCS table (old lines * new lines) to keep memory in check
+const MAX_DIFF_CELLS: usize = 4_000_000;
+const DIFF_CONTEXT: usize = 3;
+
+/// Line-based unified diff between two texts.
+/// Returns None when the inputs are too large to diff; an empty string means no changes.
+pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> Option<String> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let (n, m) = (a.len(), b.len());
+    if n.saturating_mul(m) > MAX_DIFF_CELLS {
+        return None;
+    }
+
+    // Longest common subsequence lengths for every suffix pair
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    // Walk the table into a list of (op, old index, new index)
+    let mut ops: Vec<(char, usize, usize)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && a[i] == b[j] {
+            ops.push((' ', i, j));
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(('-', i, j));
+            i += 1;
+        } else {
+            ops.push(('+', i, j));
+            j += 1;
+        }
+    }
+
+    // Group changes into hunks with surrounding context
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for (k, op) in ops.iter().enumerate() {
+        if op.0 == ' ' {
+            continue;
+        }
+        let start = k.saturating_sub(DIFF_CONTEXT);
+        let end = usize::min(k + DIFF_CONTEXT + 1, ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    if hunks.is_empty() {
+        return Some(String::new());
+    }
+
+    let mut out = format!("--- {}\n+++ {}\n", old_label, new_label);
+    for (start, end) in hunks {
+        let slice = &ops[start..end];
+        let old_len = slice.iter().filter(|o| o.0 != '+').count();
+        let new_len = slice.iter().filter(|o| o.0 != '-').count();
+        let old_start = if old_len == 0 { slice[0].1 } else { slice[0].1 + 1 };
+        let new_start = if new_len == 0 { slice[0].2 } else { slice[0].2 + 1 };
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start, old_len, new_start, new_len
+        ));
+        for (op, oi, ni) in slice {
+            let line = if *op == '+' { b[*ni] } else { a[*oi] };
+            out.push(*op);
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    Some(out)
+}
+
+/// Include/exclude glob filter for relative paths. Patterns without a `/`
+/// match a name at any depth (e.g. `node_modules`, `*.log`), like .gitignore.
+pub struct PathFilter {
+    include: Option<globset::GlobSet>,
+    exclude: globset::GlobSet,
+}
+
+fn build_globset(patterns: &[String]) -> Result<globset::GlobSet, String> {
+    let mut builder = globset::GlobSetBuilder::new();
+    for pattern in patterns {
+        let p = pattern.trim().trim_end_matches('/');
+        if p.is_empty() || p.starts_with('#') {
+            continue;
+        }
+        let p = match p.strip_prefix('/') {
+            Some(anchored) => anchored.to_string(),
+            None if !p.contains('/') => format!("**/{}", p),
+            None => p.to_string(),
+        };
+        let glob = globset::GlobBuilder::new(&p)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| format!("invalid pattern '{}': {}", pattern, e))?;
+        builder.add(glob);
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+impl PathFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self, String> {
+        let include = if include.iter().any(|p| !p.trim().is_empty()) {
+            Some(build_globset(include)?)
+        } else {
+            None
+        };
+        Ok(PathFilter {
+            include,
+            exclude: build_globset(exclude)?,
+        })
+    }
+
+    /// Excluded entries (and, for directories, everything below them) are skipped
+    pub fn is_excluded(&self, rel: &std::path::Path) -> bool {
+        self.exclude.is_match(rel)
+    }
+
+    /// Whether a file passes both the exclude and include lists
+    pub fn includes_file(&self, rel: &std::path::Path) -> bool {
+        !self.is_excluded(rel) && self.include.as_ref().is_none_or(|set| set.is_match(rel))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("plain"), "'plain'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    }
+
+    #[test]
+    fn test_mode_string() {
+        assert_eq!(mode_string(0o040755), "drwxr-xr-x");
+        assert_eq!(mode_string(0o100644), "-rw-r--r--");
+        assert_eq!(mode_string(0o104755), "-rwsr-xr-x");
+        assert_eq!(mode_string(0o041777), "drwxrwxrwt");
+        assert_eq!(mode_string(0o120777), "lrwxrwxrwx");
+    }
+
+    #[test]
+    fn test_unified_diff_identical() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", "a", "b"), Some(String::new()));
+    }
+
+    #[test]
+    fn test_unified_diff_change() {
+        let diff = unified_diff("one\ntwo\nthree\n", "one\n2\nthree\n", "old", "new").unwrap();
+        assert_eq!(
+            diff,
+            "--- old\n+++ new\n@@ -1,3 +1,3 @@\n one\n-two\n+2\n three\n"
+        );
+    }
+
+    #[test]
+    fn test_path_filter() {
+        let filter = PathFilter::new(
+            &["*.rs".to_string()],
+            &["node_modules".to_string(), ".git/".to_string(), "/build".to_string()],
+        )
+        .unwrap();
+        let p = std::path::Path::new;
+        assert!(filter.is_excluded(p("node_modules")));
+        assert!(filter.is_excluded(p("web/node_modules")));
+        assert!(filter.is_excluded(p(".git")));
+        assert!(filter.is_excluded(p("build")));
+        assert!(!filter.is_excluded(p("src/build")));
+        assert!(filter.includes_file(p("src/main.rs")));
+        assert!(!filter.includes_file(p("README.md")));
+    }
+}
@@ -9,6 +9,7 @@ import { SettingsPane } from '@/components/SettingsPane';
 import PortsPanel from '@/components/PortsPanel';
 import Sessions from '@/components/sessions';
 import type { LayoutShape } from '@/store/sessions';
+import type { ContainerInfo, ListeningSocket } from '@/types/ipc';
 import ComposeDrawer from '@/components/ComposeDrawer';
 import { CommandPalette } from '@/components/CommandPalette';
 import { commandRegistry } from '@/services/commandRegistry';
@@ -56,6 +57,8 @@ export default function App() {
     view?: 'terminal' | 'git' | 'ports' | 'files';
     forwards?: { id: string; type: 'L' | 'R'; srcHost: string; srcPort: number; dstHost: string; dstPort: number; status?: 'starting'|'active'|'error'|'closed' }[];
     detectedPorts?: number[];
+    detectedSockets?: ListeningSocket[];
+    containers?: ContainerInfo[];
     sftpCwd?: string;
     indicator?: 'activity' | 'bell';
     terminalSettings?: { theme?: string; fontSize?: number; fontFamily?: string };
@@ -2303,7 +2306,7 @@ export default function App() {
       try {
         const { listen } = await import('@tauri-apps/api/event');
         const unlisten = await listen('ssh_detected_ports', (event) => {
-          const { sessionId, ports } = event.payload as any;
+          const { sessionId, ports, sockets } = event.payload as any;
           logger.info(`Detected ${ports.length} open ports on session ${sessionId}`);
           
           // Get or create the set of already notified ports for this session
@@ -2401,7 +2404,7 @@ export default function App() {
           // Update the tab's detected ports
           setTabs((prev) => prev.map((tb) => {
             if (tb.kind === 'ssh' && tb.sshSessionId === sessionId) {
-              return { ...tb, detectedPorts: ports };
+              return { ...tb, detectedPorts: ports, detectedSockets: sockets };
             }
             return tb;
           }));
@@ -2411,12 +2414,25 @@ export default function App() {
     })();
   }, [show]);
 
+  // Published container ports become forwarding suggestions in the ports panel
+  const refreshContainers = React.useCallback(async (sessionId: string) => {
+    try {
+      const { containerList } = await import('@/types/ipc');
+      const containers = await containerList(sessionId);
+      setTabs((prev) => prev.map((tb) => (tb.kind === 'ssh' && tb.sshSessionId === sessionId ? { ...tb, containers } : tb)));
+    } catch (e) {
+      // Most hosts have no docker or podman
+      logger.debug('Container listing unavailable:', e);
+    }
+  }, []);
+
   // Event-driven port detection with debouncing
   const detectPorts = React.useCallback(debounce(async (sessionId: string) => {
     try {
       const { sshDetectPorts } = await import('@/types/ipc');
       const ports = await sshDetectPorts(sessionId);
       logger.info(`Event-driven port detection found ${ports.length} ports`);
+      refreshContainers(sessionId);
       // The event handler will update the state
     } catch (e) {
       logger.error('Port detection failed:', e);
@@ -3043,6 +3059,8 @@ export default function App() {
                 <PortsPanel
                   forwards={t.forwards || []}
                   detectedPorts={t.detectedPorts || []}
+                  detectedSockets={t.detectedSockets || []}
+                  containers={t.containers || []}
                   suggestedPorts={[3000, 3001, 4000, 4200, 5173, 5174, 8000, 8080, 8081, 8888, 9000]}
                   onRefreshPorts={async () => {
                     if (t.kind !== 'ssh' || !t.sshSessionId) return;
@@ -3050,6 +3068,7 @@ export default function App() {
                       const { sshDetectPorts } = await import('@/types/ipc');
                       const ports = await sshDetectPorts(t.sshSessionId);
                       logger.info(`Manual refresh found ${ports.length} ports`);
+                      refreshContainers(t.sshSessionId);
                     } catch (e) {
                       logger.error('Port refresh failed:', e);
                     }
@@ -49,7 +49,7 @@ export default function EditorPane({
           // Load remote file via SSH
           const result = await sshSftpRead(sessionId, filePath);
           // Decode base64 response
-          fileContent = atob(result);
+          fileContent = atob(result.data_b64);
         } else {
           throw new Error('Remote file requires session ID');
         }
@@ -46,7 +46,7 @@ export default function FileExplorerWithEditor({
       } else if (sessionId) {
         const result = await sshSftpRead(sessionId, path);
         // Decode base64 response
-        fileContent = atob(result);
+        fileContent = atob(result.data_b64);
       } else {
         throw new Error('Remote file requires session ID');
       }
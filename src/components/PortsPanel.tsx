@@ -1,4 +1,5 @@
 import React from 'react';
+import type { ContainerInfo, ListeningSocket } from '@/types/ipc';
 
 type Forward = { 
   id?: string; 
@@ -9,6 +10,8 @@ type Forward = {
   dstPort: number; 
   status?: 'starting'|'active'|'error'|'closed'|'detected'|'suggested';
   auto?: boolean; // Auto-detected or suggested port
+  label?: string; // Shown instead of the common port name, e.g. the container
+  detail?: string; // Hover text, e.g. the listening process's command line
 };
 
 interface PortsPanelProps {
@@ -20,7 +23,9 @@ interface PortsPanelProps {
   onActivate?: (f: Forward) => void;
   onRefreshPorts?: () => void;
   detectedPorts?: number[]; // Ports detected as open on remote
+  detectedSockets?: ListeningSocket[]; // Bind address and owning process of the detected ports
   suggestedPorts?: number[]; // Common dev ports to suggest
+  containers?: ContainerInfo[]; // Containers on the remote; published ports become suggestions
 }
 
 // Common development ports
@@ -51,7 +56,9 @@ export default function PortsPanel({
   onActivate,
   onRefreshPorts,
   detectedPorts = [],
-  suggestedPorts = []
+  detectedSockets = [],
+  suggestedPorts = [],
+  containers = []
 }: PortsPanelProps) {
   const [form, setForm] = React.useState<Forward>({ 
     type: 'L', 
@@ -69,26 +76,68 @@ export default function PortsPanel({
   const suggestedForwards = React.useMemo(() => {
     const existingPorts = new Set(forwards.map(f => f.dstPort));
     const suggested: Forward[] = [];
+
+    // Published TCP ports of running containers, by host port
+    const published = new Map<number, { label: string; host: string }>();
+    containers.filter(c => c.state === 'running').forEach(c => {
+      c.ports.forEach(p => {
+        if (p.host_port == null || p.protocol !== 'tcp' || published.has(p.host_port)) return;
+        // Wildcard binds are reachable on loopback
+        const host = !p.host_ip || p.host_ip === '0.0.0.0' || p.host_ip === '::' ? '127.0.0.1' : p.host_ip;
+        published.set(p.host_port, { label: `${c.name} (${c.image}) :${p.container_port}`, host });
+      });
+    });
     
+    // Listening process and bind addresses per port, e.g. "node (1234) on 0.0.0.0, ::"
+    const listeners = new Map<number, { label?: string; detail?: string }>();
+    detectedPorts.forEach(port => {
+      const sockets = detectedSockets.filter(s => s.port === port);
+      if (sockets.length === 0) return;
+      const owner = sockets.find(s => s.process);
+      const addresses = Array.from(new Set(sockets.map(s => s.address).filter(Boolean))).join(', ');
+      const who = owner ? `${owner.process}${owner.pid != null ? ` (${owner.pid})` : ''}` : '';
+      listeners.set(port, {
+        label: [who, addresses && `on ${addresses}`].filter(Boolean).join(' ') || undefined,
+        detail: owner?.cmdline || undefined
+      });
+    });
+
     // Add detected ports (that are actually running on remote)
     detectedPorts.forEach(port => {
       if (!existingPorts.has(port)) {
-        const portInfo = COMMON_DEV_PORTS.find(p => p.port === port);
         suggested.push({
           type: 'L',
           srcHost: '127.0.0.1',
           srcPort: port,
-          dstHost: '127.0.0.1', 
+          dstHost: published.get(port)?.host || '127.0.0.1',
           dstPort: port,
           status: 'detected',
-          auto: true
+          auto: true,
+          label: published.get(port)?.label || listeners.get(port)?.label,
+          detail: listeners.get(port)?.detail
+        });
+      }
+    });
+
+    // Container ports the port scan did not see (e.g. bound by rootless podman)
+    published.forEach(({ label, host }, port) => {
+      if (!existingPorts.has(port) && !detectedPorts.includes(port)) {
+        suggested.push({
+          type: 'L',
+          srcHost: '127.0.0.1',
+          srcPort: port,
+          dstHost: host,
+          dstPort: port,
+          status: 'detected',
+          auto: true,
+          label
         });
       }
     });
     
     // Add common dev ports as suggestions (may or may not be running)
     suggestedPorts.forEach(port => {
-      if (!existingPorts.has(port) && !detectedPorts.includes(port)) {
+      if (!existingPorts.has(port) && !detectedPorts.includes(port) && !published.has(port)) {
         suggested.push({
           type: 'L',
           srcHost: '127.0.0.1',
@@ -102,7 +151,7 @@ export default function PortsPanel({
     });
     
     return suggested;
-  }, [forwards, detectedPorts, suggestedPorts]);
+  }, [forwards, detectedPorts, detectedSockets, suggestedPorts, containers]);
 
   // Filter suggestions based on whether we're showing inactive ports
   const visibleSuggestions = React.useMemo(() => {
@@ -255,8 +304,8 @@ export default function PortsPanel({
               }} title={f.status === 'detected' ? 'Port is open on remote' : 'Port not detected'} />
               <div>
                 <strong>{f.type}</strong> {f.srcHost}:{f.srcPort} → {f.dstHost}:{f.dstPort}
-                <span style={{ marginLeft: 8, fontSize: 11, color: '#888' }}>
-                  {getPortName(f.dstPort)}
+                <span style={{ marginLeft: 8, fontSize: 11, color: '#888' }} title={f.detail}>
+                  {f.label || getPortName(f.dstPort)}
                 </span>
                 <span style={{ 
                   marginLeft: 8, 
@@ -122,7 +122,7 @@ export default function SftpPanel({
       const base = await pickDirectory();
       if (!base) { setBusy(false); return; }
       if (entry.is_dir) {
-        await (await import('@/types/ipc')).sshSftpDownloadDir(sessionId, entry.path, base + '/' + entry.name);
+        await (await import('@/types/ipc')).sshSftpDownloadDir(sessionId, entry.path, base + '/' + entry.name, 'gzip');
       } else {
         await (await import('@/types/ipc')).sshSftpDownload(sessionId, entry.path, base + '/' + entry.name);
       }
@@ -1,184 +1,107 @@
-import { helperLocalExec, sshExec } from '@/types/ipc';
-
-export type GitStatus = { branch: string; ahead: number; behind: number; staged: number; unstaged: number };
-
-export async function gitStatusViaHelper(opts: { kind?: 'local' | 'ssh'; sessionId?: string; helperPath?: string | null }, cwd: string): Promise<GitStatus> {
-  // Local: use helperLocalExec
-  if (opts.kind !== 'ssh') {
-    try {
-      console.info('[git] helper local git-status cwd=', cwd);
-      const res = await helperLocalExec('git-status', [cwd]);
-      if (res.exit_code === 0 && res.stdout) {
-        const j = JSON.parse(res.stdout);
-        return normalize(j);
-      }
-    } catch {}
-    // Fallback: conservative defaults
-    return { branch: '-', ahead: 0, behind: 0, staged: 0, unstaged: 0 };
+import { helperCall, gitLog as gitLogIpc, GitLogOptions, GitLogPage } from '@/types/ipc';
+
+export type GitStatus = {
+  branch: string;
+  ahead: number;
+  behind: number;
+  staged: number;
+  unstaged: number;
+  operation?: string | null;
+  step?: number | null;
+  total?: number | null;
+  conflicted?: number;
+  conflicted_files?: string[];
+};
+
+type HelperOpts = { kind?: 'local' | 'ssh'; sessionId?: string; helperPath?: string | null };
+
+type HelperRun = { ok: true; value: unknown } | { ok: false; error: string };
+
+// Run a helper subcommand through the agent of the SSH session (or the local
+// agent). SSH needs a sessionId and a deployed helper; null when not ready.
+async function runHelper(opts: HelperOpts, method: string, args: string[]): Promise<HelperRun | null> {
+  if (opts.kind === 'ssh' && (!opts.sessionId || !opts.helperPath)) return null;
+  try {
+    const value = await helperCall(method, args, opts.kind === 'ssh' ? opts.sessionId : undefined);
+    return { ok: true, value };
+  } catch (e) {
+    return { ok: false, error: String(e) };
   }
-  // SSH: require helperPath + sessionId
-  if (!opts.sessionId || !opts.helperPath) {
+}
+
+// Output of a command-style subcommand (commit, stage, pull, ...)
+async function runOutput(opts: HelperOpts, method: string, args: string[]): Promise<{ output: string }> {
+  const res = await runHelper(opts, method, args);
+  if (!res) return { output: 'helper not ready' };
+  if (!res.ok) return { output: res.error };
+  return { output: typeof res.value === 'string' ? res.value : '' };
+}
+
+export async function gitStatusViaHelper(opts: HelperOpts, cwd: string): Promise<GitStatus> {
+  if (opts.kind === 'ssh' && (!opts.sessionId || !opts.helperPath)) {
     console.info('[git] helper ssh not ready; sessionId or helperPath missing', { cwd, sessionId: opts.sessionId, helperPath: opts.helperPath });
-    return { branch: '-', ahead: 0, behind: 0, staged: 0, unstaged: 0 };
+  } else {
+    console.info(`[git] helper ${opts.kind === 'ssh' ? 'ssh' : 'local'} git-status cwd=`, cwd);
   }
-  const esc = (s: string) => s.replace(/'/g, "'\\''");
-  try {
-    console.info('[git] helper ssh git-status cwd=', cwd);
-    const cmd = `'${esc(opts.helperPath)}' git-status '${esc(cwd)}'`;
-    const res = await sshExec(opts.sessionId, cmd);
-    if (res.exit_code === 0 && res.stdout) {
-      const j = JSON.parse(res.stdout);
-      return normalize(j);
-    }
-  } catch {}
+  const res = await runHelper(opts, 'git-status', [cwd]);
+  if (res?.ok) return normalize(res.value);
+  // Fallback: conservative defaults
   return { branch: '-', ahead: 0, behind: 0, staged: 0, unstaged: 0 };
 }
 
 export type GitChange = { path: string; x: string; y: string; staged: boolean };
 
-export async function gitListChanges(opts: { kind?: 'local' | 'ssh'; sessionId?: string; helperPath?: string | null }, cwd: string): Promise<GitChange[]> {
-  if (opts.kind !== 'ssh') {
-    try {
-      const res = await helperLocalExec('git-changes', [cwd]);
-      if (res.exit_code === 0 && res.stdout) return JSON.parse(res.stdout);
-    } catch {}
-    return [];
-  }
-  if (!opts.sessionId || !opts.helperPath) return [];
-  const esc = (s: string) => s.replace(/'/g, "'\\''");
-  try {
-    const cmd = `'${esc(opts.helperPath)}' git-changes '${esc(cwd)}'`;
-    const res = await sshExec(opts.sessionId, cmd);
-    if (res.exit_code === 0 && res.stdout) return JSON.parse(res.stdout);
-  } catch {}
-  return [];
+export async function gitListChanges(opts: HelperOpts, cwd: string): Promise<GitChange[]> {
+  const res = await runHelper(opts, 'git-changes', [cwd]);
+  return res?.ok && Array.isArray(res.value) ? (res.value as GitChange[]) : [];
 }
 
-export async function gitDiffFile(opts: { kind?: 'local' | 'ssh'; sessionId?: string; helperPath?: string | null }, cwd: string, file: string, staged?: boolean): Promise<string> {
+export async function gitDiffFile(opts: HelperOpts, cwd: string, file: string, staged?: boolean): Promise<string> {
   const mode = staged ? 'staged' : 'work';
-  if (opts.kind !== 'ssh') {
-    try {
-      const res = await helperLocalExec('git-diff', [cwd, file, mode]);
-      if (res.exit_code === 0) return res.stdout;
-    } catch {}
-    return '';
-  }
-  if (!opts.sessionId || !opts.helperPath) return '';
-  const esc = (s: string) => s.replace(/'/g, "'\\''");
-  try {
-    const cmd = `'${esc(opts.helperPath)}' git-diff '${esc(cwd)}' '${esc(file)}' '${esc(mode)}'`;
-    const res = await sshExec(opts.sessionId, cmd);
-    if (res.exit_code === 0) return res.stdout;
-  } catch {}
-  return '';
+  const res = await runHelper(opts, 'git-diff', [cwd, file, mode]);
+  return res?.ok && typeof res.value === 'string' ? res.value : '';
 }
 
-export async function gitCommit(opts: { kind?: 'local' | 'ssh'; sessionId?: string; helperPath?: string | null }, cwd: string, message: string): Promise<{ output: string }> {
-  if (opts.kind !== 'ssh') {
-    const res = await helperLocalExec('git-commit', [cwd, message]);
-    return { output: (res.stdout || '') + (res.stderr || '') };
-  }
-  if (!opts.sessionId || !opts.helperPath) return { output: 'helper not ready' };
-  const esc = (s: string) => s.replace(/'/g, "'\\''");
-  const cmd = `'${esc(opts.helperPath)}' git-commit '${esc(cwd)}' '${esc(message)}'`;
-  const res = await sshExec(opts.sessionId, cmd);
-  return { output: (res.stdout || '') + (res.stderr || '') };
+// One page of history; pass `skip: commits.length` for the next one
+export async function gitLog(opts: HelperOpts, cwd: string, options?: GitLogOptions): Promise<GitLogPage> {
+  if (opts.kind === 'ssh' && !opts.sessionId) return { commits: [], has_more: false, head: null };
+  return gitLogIpc(cwd, options, opts.kind === 'ssh' ? opts.sessionId : undefined);
 }
 
-export async function gitSync(opts: { kind?: 'local' | 'ssh'; sessionId?: string; helperPath?: string | null }, cwd: string): Promise<{ output: string }> {
-  if (opts.kind !== 'ssh') {
-    const res = await helperLocalExec('git-sync', [cwd]);
-    return { output: (res.stdout || '') + (res.stderr || '') };
-  }
-  if (!opts.sessionId || !opts.helperPath) return { output: 'helper not ready' };
-  const esc = (s: string) => s.replace(/'/g, "'\\''");
-  const cmd = `'${esc(opts.helperPath)}' git-sync '${esc(cwd)}'`;
-  const res = await sshExec(opts.sessionId, cmd);
-  return { output: (res.stdout || '') + (res.stderr || '') };
+export async function gitCommit(opts: HelperOpts, cwd: string, message: string): Promise<{ output: string }> {
+  return runOutput(opts, 'git-commit', [cwd, message]);
 }
 
-export async function gitStageFile(opts: { kind?: 'local' | 'ssh'; sessionId?: string; helperPath?: string | null }, cwd: string, file: string): Promise<{ output: string }> {
-  if (opts.kind !== 'ssh') {
-    const res = await helperLocalExec('git-stage', [cwd, file]);
-    return { output: (res.stdout || '') + (res.stderr || '') };
-  }
-  if (!opts.sessionId || !opts.helperPath) return { output: 'helper not ready' };
-  const esc = (s: string) => s.replace(/'/g, "'\\''");
-  const cmd = `'${esc(opts.helperPath)}' git-stage '${esc(cwd)}' '${esc(file)}'`;
-  const res = await sshExec(opts.sessionId, cmd);
-  return { output: (res.stdout || '') + (res.stderr || '') };
+export async function gitSync(opts: HelperOpts, cwd: string): Promise<{ output: string }> {
+  return runOutput(opts, 'git-sync', [cwd]);
 }
 
-export async function gitUnstageFile(opts: { kind?: 'local' | 'ssh'; sessionId?: string; helperPath?: string | null }, cwd: string, file: string): Promise<{ output: string }> {
-  if (opts.kind !== 'ssh') {
-    const res = await helperLocalExec('git-unstage', [cwd, file]);
-    return { output: (res.stdout || '') + (res.stderr || '') };
-  }
-  if (!opts.sessionId || !opts.helperPath) return { output: 'helper not ready' };
-  const esc = (s: string) => s.replace(/'/g, "'\\''");
-  const cmd = `'${esc(opts.helperPath)}' git-unstage '${esc(cwd)}' '${esc(file)}'`;
-  const res = await sshExec(opts.sessionId, cmd);
-  return { output: (res.stdout || '') + (res.stderr || '') };
+export async function gitStageFile(opts: HelperOpts, cwd: string, file: string): Promise<{ output: string }> {
+  return runOutput(opts, 'git-stage', [cwd, file]);
 }
 
-export async function gitDiscardFile(opts: { kind?: 'local' | 'ssh'; sessionId?: string; helperPath?: string | null }, cwd: string, file: string): Promise<{ output: string }> {
-  if (opts.kind !== 'ssh') {
-    const res = await helperLocalExec('git-discard', [cwd, file]);
-    return { output: (res.stdout || '') + (res.stderr || '') };
-  }
-  if (!opts.sessionId || !opts.helperPath) return { output: 'helper not ready' };
-  const esc = (s: string) => s.replace(/'/g, "'\\''");
-  const cmd = `'${esc(opts.helperPath)}' git-discard '${esc(cwd)}' '${esc(file)}'`;
-  const res = await sshExec(opts.sessionId, cmd);
-  return { output: (res.stdout || '') + (res.stderr || '') };
+export async function gitUnstageFile(opts: HelperOpts, cwd: string, file: string): Promise<{ output: string }> {
+  return runOutput(opts, 'git-unstage', [cwd, file]);
 }
 
-export async function gitStageAll(opts: { kind?: 'local' | 'ssh'; sessionId?: string; helperPath?: string | null }, cwd: string): Promise<{ output: string }> {
-  if (opts.kind !== 'ssh') {
-    const res = await helperLocalExec('git-stage-all', [cwd]);
-    return { output: (res.stdout || '') + (res.stderr || '') };
-  }
-  if (!opts.sessionId || !opts.helperPath) return { output: 'helper not ready' };
-  const esc = (s: string) => s.replace(/'/g, "'\\''");
-  const cmd = `'${esc(opts.helperPath)}' git-stage-all '${esc(cwd)}'`;
-  const res = await sshExec(opts.sessionId, cmd);
-  return { output: (res.stdout || '') + (res.stderr || '') };
+export async function gitDiscardFile(opts: HelperOpts, cwd: string, file: string): Promise<{ output: string }> {
+  return runOutput(opts, 'git-discard', [cwd, file]);
 }
 
-export async function gitUnstageAll(opts: { kind?: 'local' | 'ssh'; sessionId?: string; helperPath?: string | null }, cwd: string): Promise<{ output: string }> {
-  if (opts.kind !== 'ssh') {
-    const res = await helperLocalExec('git-unstage-all', [cwd]);
-    return { output: (res.stdout || '') + (res.stderr || '') };
-  }
-  if (!opts.sessionId || !opts.helperPath) return { output: 'helper not ready' };
-  const esc = (s: string) => s.replace(/'/g, "'\\''");
-  const cmd = `'${esc(opts.helperPath)}' git-unstage-all '${esc(cwd)}'`;
-  const res = await sshExec(opts.sessionId, cmd);
-  return { output: (res.stdout || '') + (res.stderr || '') };
+export async function gitStageAll(opts: HelperOpts, cwd: string): Promise<{ output: string }> {
+  return runOutput(opts, 'git-stage-all', [cwd]);
 }
 
-export async function gitPull(opts: { kind?: 'local' | 'ssh'; sessionId?: string; helperPath?: string | null }, cwd: string): Promise<{ output: string }> {
-  if (opts.kind !== 'ssh') {
-    const res = await helperLocalExec('git-pull', [cwd]);
-    return { output: (res.stdout || '') + (res.stderr || '') };
-  }
-  if (!opts.sessionId || !opts.helperPath) return { output: 'helper not ready' };
-  const esc = (s: string) => s.replace(/'/g, "'\\''");
-  const cmd = `'${esc(opts.helperPath)}' git-pull '${esc(cwd)}'`;
-  const res = await sshExec(opts.sessionId, cmd);
-  return { output: (res.stdout || '') + (res.stderr || '') };
+export async function gitUnstageAll(opts: HelperOpts, cwd: string): Promise<{ output: string }> {
+  return runOutput(opts, 'git-unstage-all', [cwd]);
 }
 
-export async function gitPush(opts: { kind?: 'local' | 'ssh'; sessionId?: string; helperPath?: string | null }, cwd: string): Promise<{ output: string }> {
-  if (opts.kind !== 'ssh') {
-    const res = await helperLocalExec('git-push', [cwd]);
-    return { output: (res.stdout || '') + (res.stderr || '') };
-  }
-  if (!opts.sessionId || !opts.helperPath) return { output: 'helper not ready' };
-  const esc = (s: string) => s.replace(/'/g, "'\\''");
-  const cmd = `'${esc(opts.helperPath)}' git-push '${esc(cwd)}'`;
-  const res = await sshExec(opts.sessionId, cmd);
-  return { output: (res.stdout || '') + (res.stderr || '') };
+export async function gitPull(opts: HelperOpts, cwd: string): Promise<{ output: string }> {
+  return runOutput(opts, 'git-pull', [cwd]);
+}
+
+export async function gitPush(opts: HelperOpts, cwd: string): Promise<{ output: string }> {
+  return runOutput(opts, 'git-push', [cwd]);
 }
 
 function normalize(j: any): GitStatus {
@@ -188,5 +111,10 @@ function normalize(j: any): GitStatus {
     behind: Number(j?.behind) || 0,
     staged: Number(j?.staged) || 0,
     unstaged: Number(j?.unstaged) || 0,
+    operation: typeof j?.operation === 'string' ? j.operation : null,
+    step: typeof j?.step === 'number' ? j.step : null,
+    total: typeof j?.total === 'number' ? j.total : null,
+    conflicted: Number(j?.conflicted) || 0,
+    conflicted_files: Array.isArray(j?.conflicted_files) ? j.conflicted_files : [],
   };
 }
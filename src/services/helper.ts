@@ -1,4 +1,4 @@
-import { onSshUploadProgress, sshExec, sshHomeDir, sshSftpMkdirs, sshDeployHelper, helperLocalEnsure, helperGetVersion } from '@/types/ipc';
+import { onSshUploadProgress, sshHomeDir, sshSftpMkdirs, sshDeployHelper, sshInstallShellIntegration, helperLocalEnsure } from '@/types/ipc';
 
 export type HelperStatus = { ok: boolean; version?: string; path?: string; os?: string };
 
@@ -8,78 +8,42 @@ export async function ensureHelper(
   opts: { show: (t: any) => string; update: (id: string, patch: any) => void; dismiss: (id: string) => void }
 ): Promise<HelperStatus> {
   const { show, update, dismiss } = opts;
+  let toastId: string | null = null;
   try {
     console.info('[helper] ensureHelper: start', { sessionId });
-    
-    // Get the helper version from backend
-    const requiredVersion = await helperGetVersion();
-    console.info('[helper] required version:', requiredVersion);
-    
+
     const home = await sshHomeDir(sessionId);
     console.info('[helper] home', home);
     const helperDir = home.replace(/\/+$/, '') + '/.jaterm-helper';
     const helperPath = helperDir + '/jaterm-agent';
-
-    // Check existing helper
-    try {
-      const res = await sshExec(sessionId, `'${helperPath.replace(/'/g, "'\\''")}'  health`);
-      console.info('[helper] health check (existing)', { exit: res.exit_code, stdout: res.stdout, stderr: res.stderr });
-      if (res.exit_code === 0) {
-        try {
-          const j = JSON.parse(res.stdout);
-          if (j && j.ok && j.version === requiredVersion) {
-            console.info('[helper] up to date', j.version, 'os:', j.os);
-            return { ok: true, version: j.version, path: helperPath, os: j.os };
-          }
-        } catch {}
-      }
-    } catch {}
-
-    // Need to (re)install - get binary from backend
-    console.info('[helper] mkdirs', helperDir);
     await sshSftpMkdirs(sessionId, helperDir);
-    
-    // Get the helper binary from the backend
-    const toastId = show({ title: 'Installing helper', message: helperPath, progress: { current: 0, total: 100 }, kind: 'info' });
-    
+
+    // The backend skips the upload when the installed helper is current, so
+    // only show the install toast once bytes start moving
     const unlisten = await onSshUploadProgress((p) => {
-      if (p.path === helperPath) {
-        const percent = Math.round((p.written / p.total) * 100);
-        update(toastId, { progress: { current: percent, total: 100 } });
-      }
+      if (p.path !== helperPath) return;
+      const percent = Math.round((p.written / p.total) * 100);
+      if (!toastId) toastId = show({ title: 'Installing helper', message: helperPath, progress: { current: 0, total: 100 }, kind: 'info' });
+      update(toastId, { progress: { current: percent, total: 100 } });
     });
-    
+
     try {
-      console.info('[helper] upload start', helperPath);
-      // Deploy the binary directly from the backend
-      await sshDeployHelper(sessionId, helperPath);
-      
-      console.info('[helper] chmod +x', helperPath);
-      const ch = await sshExec(sessionId, `chmod +x '${helperPath.replace(/'/g, "'\\''")}'`);
-      console.info('[helper] chmod result', { exit: ch.exit_code, stdout: ch.stdout, stderr: ch.stderr });
-      
-      const res = await sshExec(sessionId, `'${helperPath.replace(/'/g, "'\\''")}'  health`);
-      console.info('[helper] health after install', { exit: res.exit_code, stdout: res.stdout, stderr: res.stderr });
-      
-      if (res.exit_code !== 0) throw new Error(res.stderr || 'health failed');
-      
-      update(toastId, { title: 'Helper ready', kind: 'success' });
-      setTimeout(() => dismiss(toastId), 1500);
-      
-      try {
-        const j = JSON.parse(res.stdout);
-        return { ok: !!j?.ok, version: j?.version, path: helperPath, os: j?.os };
-      } catch {
-        return { ok: true, version: requiredVersion, path: helperPath };
+      const res = await sshDeployHelper(sessionId, helperPath);
+      console.info('[helper] deploy result', res);
+      if (toastId) {
+        const id = toastId;
+        update(id, { title: 'Helper ready', kind: 'success' });
+        setTimeout(() => dismiss(id), 1500);
       }
+      if (res.offer_shell_integration) offerShellIntegration(sessionId, show);
+      return { ok: true, version: res.version, path: helperPath, os: res.os };
     } finally {
       unlisten();
     }
   } catch (e) {
     console.error('[helper] ensure error', e);
-    // Try to close any existing install toast if present by updating/dismissing
     try {
-      // We don't hold the id here reliably; rely on a new error toast and let any stale progress toast be dismissed by timeout
+      if (toastId) dismiss(toastId);
       const id = opts.show({ title: 'Helper install failed', message: String(e), kind: 'error' });
       setTimeout(() => opts.dismiss(id), 3000);
     } catch {}
@@ -87,6 +51,30 @@ export async function ensureHelper(
   }
 }
 
+// Remote shells only report their cwd (OSC 7) once the snippet is in their rc file
+function offerShellIntegration(sessionId: string, show: (t: any) => string) {
+  show({
+    title: 'Track remote working directory?',
+    message: 'Adds a snippet to your shell rc file on this host so the file and git panels follow cd.',
+    kind: 'info',
+    actions: [
+      {
+        label: 'Enable',
+        onClick: async () => {
+          try {
+            const results = await sshInstallShellIntegration(sessionId);
+            const files = results.map((r) => r.path).join(', ');
+            show({ title: 'Cwd tracking enabled', message: `New shells pick it up (${files})`, kind: 'success' });
+          } catch (e) {
+            show({ title: 'Cwd tracking failed', message: String(e), kind: 'error' });
+          }
+        },
+      },
+      { label: 'Not now', onClick: () => {} },
+    ],
+  });
+}
+
 // Ensure local helper for local sessions
 export async function ensureLocalHelper(): Promise<HelperStatus> {
   try {
@@ -46,7 +46,12 @@ export type GitStatus = {
   behind: number;
   staged: number;
   unstaged: number;
+  // Operation in progress, with step/total while rebasing
+  operation?: 'merging' | 'rebasing' | 'cherry-picking' | 'reverting' | 'bisecting' | 'applying' | null;
+  step?: number | null;
+  total?: number | null;
   conflicted?: number;
+  conflicted_files?: string[];
 };
 
 // Shell types
@@ -188,6 +193,159 @@ export function gitStatus(cwd: string): Promise<GitStatus> {
   return invoke('git_status', { path: cwd } as any);
 }
 
+// ----- Git history -----
+export type GitLogOptions = {
+  skip?: number;
+  limit?: number;
+  rev?: string;
+  all?: boolean;
+  // Relative to the repository root
+  path?: string;
+  author?: string;
+  message?: string;
+};
+export type GitRef = { name: string; kind: 'branch' | 'remote' | 'tag' | 'head'; is_head: boolean };
+// Lines run from `from` in this row to `to` in the next one
+export type GitGraphRow = { column: number; edges: { from: number; to: number }[] };
+export type GitCommit = {
+  id: string;
+  short_id: string;
+  parents: string[];
+  author_name: string;
+  author_email: string;
+  author_time: number;
+  committer_name: string;
+  committer_time: number;
+  summary: string;
+  message: string;
+  refs: GitRef[];
+  // Null when path/author/message filters are set
+  graph: GitGraphRow | null;
+};
+export type GitLogPage = { commits: GitCommit[]; has_more: boolean; head: string | null };
+
+// Local through git2, or through the helper of an SSH session
+export function gitLog(cwd: string, options?: GitLogOptions, sessionId?: string): Promise<GitLogPage> {
+  return invoke('git_log', { path: cwd, sessionId, options } as any);
+}
+
+// ----- Git branches (local through git2, or through the helper of an SSH session) -----
+export type GitBranch = {
+  name: string;
+  kind: 'local' | 'remote';
+  is_head: boolean;
+  upstream: string | null;
+  // Upstream configured but its remote branch is gone
+  upstream_gone: boolean;
+  ahead: number;
+  behind: number;
+  last_commit: { id: string; short_id: string; summary: string; author_name: string; time: number } | null;
+};
+// checked_out is false when local changes are in the way; conflicts lists them
+export type GitCheckoutResult = { branch: string; checked_out: boolean; conflicts: string[] };
+
+export function gitBranches(cwd: string, sessionId?: string, remotes?: boolean): Promise<GitBranch[]> {
+  return invoke('git_branches', { path: cwd, sessionId, remotes } as any);
+}
+export function gitBranchCreate(cwd: string, name: string, start?: string, sessionId?: string): Promise<void> {
+  return invoke('git_branch_create', { path: cwd, sessionId, name, start } as any);
+}
+export function gitCheckout(cwd: string, branch: string, sessionId?: string): Promise<GitCheckoutResult> {
+  return invoke('git_checkout', { path: cwd, sessionId, branch } as any);
+}
+export function gitBranchDelete(cwd: string, name: string, force?: boolean, sessionId?: string): Promise<void> {
+  return invoke('git_branch_delete', { path: cwd, sessionId, name, force } as any);
+}
+export function gitBranchRename(cwd: string, name: string, newName: string, force?: boolean, sessionId?: string): Promise<void> {
+  return invoke('git_branch_rename', { path: cwd, sessionId, name, newName, force } as any);
+}
+// Omit upstream to stop tracking
+export function gitBranchSetUpstream(cwd: string, name: string, upstream?: string, sessionId?: string): Promise<void> {
+  return invoke('git_branch_set_upstream', { path: cwd, sessionId, name, upstream } as any);
+}
+
+// ----- Structured git diffs and hunk/line staging -----
+export type GitDiffOptions = {
+  // HEAD vs index instead of index vs working tree
+  staged?: boolean;
+  paths?: string[];
+  context?: number;
+};
+export type GitDiffLine = { origin: '+' | '-' | ' '; old_lineno: number | null; new_lineno: number | null; content: string };
+export type GitDiffHunk = {
+  header: string;
+  old_start: number;
+  old_lines: number;
+  new_start: number;
+  new_lines: number;
+  lines: GitDiffLine[];
+};
+export type GitDiffFile = {
+  old_path: string | null;
+  new_path: string | null;
+  status: 'added' | 'deleted' | 'modified' | 'renamed' | 'copied' | 'typechange' | 'untracked' | 'conflicted';
+  binary: boolean;
+  additions: number;
+  deletions: number;
+  hunks: GitDiffHunk[];
+};
+export type GitLineAction = 'stage' | 'unstage' | 'discard';
+
+export function gitDiff(cwd: string, options?: GitDiffOptions, sessionId?: string): Promise<GitDiffFile[]> {
+  return invoke('git_diff', { path: cwd, sessionId, options } as any);
+}
+// Applies a whole hunk, or only `lines` (indexes into hunk.lines); pass the context the diff was listed with
+export function gitApplyLines(
+  cwd: string,
+  args: { action: GitLineAction; file: string; hunk: number; lines?: number[]; context?: number },
+  sessionId?: string
+): Promise<void> {
+  return invoke('git_apply_lines', { path: cwd, sessionId, ...args } as any);
+}
+
+// ----- Git stash (index 0 is the newest entry) -----
+export type GitStash = {
+  index: number;
+  id: string;
+  branch: string | null;
+  message: string;
+  time: number;
+  files: number;
+  untracked: number;
+};
+
+export function gitStashList(cwd: string, sessionId?: string): Promise<GitStash[]> {
+  return invoke('git_stash_list', { path: cwd, sessionId } as any);
+}
+export function gitStashPush(
+  cwd: string,
+  opts?: { message?: string; includeUntracked?: boolean; keepIndex?: boolean },
+  sessionId?: string
+): Promise<void> {
+  return invoke('git_stash_push', { path: cwd, sessionId, ...opts } as any);
+}
+export function gitStashApply(cwd: string, index?: number, sessionId?: string): Promise<void> {
+  return invoke('git_stash_apply', { path: cwd, sessionId, index } as any);
+}
+export function gitStashPop(cwd: string, index?: number, sessionId?: string): Promise<void> {
+  return invoke('git_stash_pop', { path: cwd, sessionId, index } as any);
+}
+export function gitStashDrop(cwd: string, index?: number, sessionId?: string): Promise<void> {
+  return invoke('git_stash_drop', { path: cwd, sessionId, index } as any);
+}
+export function gitStashShow(cwd: string, index?: number, sessionId?: string): Promise<GitDiffFile[]> {
+  return invoke('git_stash_show', { path: cwd, sessionId, index } as any);
+}
+
+// ----- Merge/rebase/cherry-pick in progress (see GitStatus.operation) -----
+export function gitOperation(cwd: string, action: 'continue' | 'abort' | 'skip', sessionId?: string): Promise<string> {
+  return invoke('git_operation', { path: cwd, sessionId, action } as any);
+}
+// Take one side of a conflicted file and mark it resolved; during a rebase "ours" is the upstream, as in git
+export function gitResolve(cwd: string, file: string, side: 'ours' | 'theirs', sessionId?: string): Promise<void> {
+  return invoke('git_resolve', { path: cwd, sessionId, file, side } as any);
+}
+
 // ----- SSH Key Generation -----
 export interface GeneratedKey {
   private_key_path: string;
@@ -205,7 +363,7 @@ export const deployPublicKey = (sessionId: string, publicKey: string): Promise<v
 export const testKeyAuth = (host: string, port: number, user: string, keyPath: string, passphrase: string | null): Promise<boolean> =>
   invoke('test_key_auth', { host, port, user, keyPath, passphrase });
 
-export function watchSubscribe(paths: string[]): Promise<{ subscriptionId: string } | void> {
+export function watchSubscribe(paths: string[]): Promise<{ subscriptionId: string }> {
   return invoke('watch_subscribe', { paths } as any);
 }
 
@@ -213,10 +371,196 @@ export function watchUnsubscribe(subscriptionId: string) {
   return invoke('watch_unsubscribe', { subscriptionId } as any);
 }
 
+export type WatchEvent = { subscriptionId: string; kind: 'create' | 'modify' | 'remove' | 'rename' | 'other'; paths: string[] };
+
+// Watch a local folder and push changes to a remote path over the session's SFTP
+export type WatchUploadOptions = { debounceMs?: number; exclude?: string[]; ignoreFile?: string; delete?: boolean };
+export function watchUploadStart(sessionId: string, localDir: string, remoteDir: string, opts?: WatchUploadOptions): Promise<{ subscriptionId: string }> {
+  return invoke('watch_upload_start', { sessionId, localDir, remoteDir, opts } as any);
+}
+
+export type SshWatchUploadEvent = {
+  subscriptionId: string;
+  path: string;
+  remotePath: string;
+  status: 'uploaded' | 'mkdir' | 'deleted' | 'error';
+  error: string | null;
+};
+export function onSshWatchUpload(handler: (e: SshWatchUploadEvent) => void): Promise<UnlistenFn> {
+  return listen<SshWatchUploadEvent>('SSH_WATCH_UPLOAD', (ev) => handler(ev.payload));
+}
+
+// Remote file search (helper agent when deployed, SFTP walk otherwise)
+export type SearchOptions = { regex?: boolean; ignoreCase?: boolean; hidden?: boolean; maxResults?: number };
+export type SearchMatch = { path: string; type: 'file' | 'dir' | 'symlink'; size: number; line: number | null; text: string | null };
+export function sshSearchStart(sessionId: string, root: string, query: { name?: string; content?: string }, opts?: SearchOptions): Promise<string> {
+  return invoke('ssh_search_start', { sessionId, root, name: query.name, content: query.content, opts } as any);
+}
+export function sshSearchCancel(searchId: string): Promise<void> {
+  return invoke('ssh_search_cancel', { searchId } as any);
+}
+export type SshSearchResultsEvent = { searchId: string; results: SearchMatch[] };
+export function onSshSearchResults(handler: (e: SshSearchResultsEvent) => void): Promise<UnlistenFn> {
+  return listen<SshSearchResultsEvent>('SSH_SEARCH_RESULTS', (ev) => handler(ev.payload));
+}
+export type SshSearchDoneEvent = {
+  searchId: string;
+  via?: 'agent' | 'sftp';
+  found: number;
+  truncated?: boolean;
+  scanned?: number;
+  cancelled: boolean;
+  error: string | null;
+};
+export function onSshSearchDone(handler: (e: SshSearchDoneEvent) => void): Promise<UnlistenFn> {
+  return listen<SshSearchDoneEvent>('SSH_SEARCH_DONE', (ev) => handler(ev.payload));
+}
+
+// Session watchdog: the helper agent streams git status (GIT_STATUS), listening
+// ports and cwd changes. Started on connect for ~; restart it to follow another
+// directory, or the cwd of a remote process with `pid`.
+export function sshWatchdogStart(sessionId: string, dir?: string, pid?: number): Promise<void> {
+  return invoke('ssh_watchdog_start', { sessionId, dir, pid } as any);
+}
+export function sshWatchdogStop(sessionId: string): Promise<void> {
+  return invoke('ssh_watchdog_stop', { sessionId } as any);
+}
+export type ListeningSocket = {
+  port: number;
+  proto: 'tcp' | 'tcp6' | 'udp' | 'udp6';
+  address: string;
+  pid?: number | null;
+  process?: string | null;
+  cmdline?: string | null;
+};
+// `sockets` is missing when the remote agent predates process-aware detection
+export type SshDetectedPortsEvent = { sessionId: string; ports: number[]; sockets?: ListeningSocket[] };
+export function onSshDetectedPorts(handler: (e: SshDetectedPortsEvent) => void): Promise<UnlistenFn> {
+  return listen<SshDetectedPortsEvent>('ssh_detected_ports', (ev) => handler(ev.payload));
+}
+// Host dashboard: the helper agent samples /proc on the remote and emits SSH_METRICS
+export type HostMetrics = {
+  timestamp: number;
+  uptime_secs: number;
+  load: [number, number, number];
+  cpu: { usage: number; cores: number[] };
+  memory: { total: number; available: number; used: number; swap_total: number; swap_used: number };
+  disks: { mount: string; device: string; fs_type: string; total: number; used: number; available: number }[];
+  network: { interface: string; rx_bytes: number; tx_bytes: number; rx_rate: number; tx_rate: number }[];
+  processes: { pid: number; name: string; cpu: number; rss: number }[];
+};
+export function sshMetricsStart(sessionId: string, intervalMs?: number, top?: number): Promise<void> {
+  return invoke('ssh_metrics_start', { sessionId, intervalMs, top } as any);
+}
+export function sshMetricsStop(sessionId: string): Promise<void> {
+  return invoke('ssh_metrics_stop', { sessionId } as any);
+}
+// `metrics` is null with an `error` when the stream ends on its own
+export type SshMetricsEvent = { sessionId: string; metrics: HostMetrics | null; error?: string };
+export function onSshMetrics(handler: (e: SshMetricsEvent) => void): Promise<UnlistenFn> {
+  return listen<SshMetricsEvent>('SSH_METRICS', (ev) => handler(ev.payload));
+}
+// Processes via the helper agent: local when sessionId is omitted, else on the SSH host
+export type ProcessInfo = {
+  pid: number;
+  ppid: number;
+  user: string;
+  name: string;
+  state: string;
+  cpu: number;
+  mem: number;
+  rss: number;
+  start_time: number;
+  cmdline: string;
+  children: ProcessInfo[];
+};
+export type KillResult = { pid: number; ok: boolean; error?: string | null };
+export function processList(opts: { sessionId?: string; filter?: string; flat?: boolean } = {}): Promise<ProcessInfo[]> {
+  return invoke('process_list', { sessionId: opts.sessionId, filter: opts.filter, flat: opts.flat } as any);
+}
+export function processKill(pids: number[], signal?: string, sessionId?: string): Promise<KillResult[]> {
+  return invoke('process_kill', { sessionId, pids, signal } as any);
+}
+// Containers (Docker/Podman) via the helper agent: local when sessionId is omitted, else on the SSH host
+export type ContainerPort = { container_port: number; host_port: number | null; host_ip: string | null; protocol: string };
+export type ContainerInfo = {
+  id: string;
+  name: string;
+  image: string;
+  state: string;
+  status: string;
+  runtime: 'docker' | 'podman';
+  ports: ContainerPort[];
+};
+export function containerList(sessionId?: string, all?: boolean): Promise<ContainerInfo[]> {
+  return invoke('container_list', { sessionId, all } as any);
+}
+// Opens `docker exec -it` on a new SSH channel; resolves like sshOpenShell
+export async function containerShell(args: { sessionId: string; container: string; runtime?: string; shell?: string; cols?: number; rows?: number }): Promise<{ channelId: string; sessionId: string }> {
+  const result = await invoke<string>('container_shell', { sessionId: args.sessionId, container: args.container, runtime: args.runtime, shell: args.shell, cols: args.cols, rows: args.rows } as any);
+  return JSON.parse(result);
+}
+// Host environment report via the helper agent; cached per SSH session unless refresh is set
+export type EnvReport = {
+  os: string;
+  distro: { id: string; name: string; version: string | null } | null;
+  kernel: string | null;
+  arch: string;
+  hostname: string | null;
+  user: string | null;
+  home: string | null;
+  shell: { path: string; name: string; version: string | null } | null;
+  package_managers: string[];
+  tools: Record<string, string>;
+  osc7_shells: string[];
+  osc7_installed: boolean;
+};
+export function envReport(sessionId?: string, refresh?: boolean): Promise<EnvReport> {
+  return invoke('env_report', { sessionId, refresh } as any);
+}
+// Remote cwd tracking (OSC 7) snippets in shell rc files; shells default to the login shell
+export type ShellIntegrationResult = { shell: string; path: string; status: 'installed' | 'updated' | 'unchanged' | 'removed' | 'absent' };
+export function sshInstallShellIntegration(sessionId: string, shells?: string[]): Promise<ShellIntegrationResult[]> {
+  return invoke('ssh_install_shell_integration', { sessionId, shells } as any);
+}
+export function sshUninstallShellIntegration(sessionId: string, shells?: string[]): Promise<ShellIntegrationResult[]> {
+  return invoke('ssh_uninstall_shell_integration', { sessionId, shells } as any);
+}
+// Log viewer: follow files / journal units / containers via the helper agent (local when sessionId is omitted)
+export type LogStreamOptions = {
+  files?: string[];
+  units?: string[];
+  containers?: string[];
+  lines?: number;
+  follow?: boolean;
+  grep?: string;
+  ignoreCase?: boolean;
+  level?: 'error' | 'warning' | 'info' | 'debug';
+};
+export type LogLine = { source: string; ts: number; level: 'error' | 'warning' | 'info' | 'debug' | null; line: string };
+export function logStreamStart(opts: LogStreamOptions, sessionId?: string): Promise<string> {
+  return invoke('log_stream_start', { sessionId, opts } as any);
+}
+export function logStreamStop(streamId: string): Promise<void> {
+  return invoke('log_stream_stop', { streamId } as any);
+}
+export type LogViewerLinesEvent = { streamId: string; lines: LogLine[] };
+export type LogViewerEndEvent = { streamId: string; cancelled: boolean; error: string | null };
+export function onLogViewerLines(handler: (e: LogViewerLinesEvent) => void): Promise<UnlistenFn> {
+  return listen<LogViewerLinesEvent>('LOG_VIEWER_LINES', (ev) => handler(ev.payload));
+}
+export function onLogViewerEnd(handler: (e: LogViewerEndEvent) => void): Promise<UnlistenFn> {
+  return listen<LogViewerEndEvent>('LOG_VIEWER_END', (ev) => handler(ev.payload));
+}
+export type SshRemoteCwdEvent = { sessionId: string; cwd: string };
+export function onSshRemoteCwd(handler: (e: SshRemoteCwdEvent) => void): Promise<UnlistenFn> {
+  return listen<SshRemoteCwdEvent>('SSH_REMOTE_CWD', (ev) => handler(ev.payload));
+}
+
 // Events
 export type PtyOutputEvent = { ptyId: string; data: string };
 export type PtyExitEvent = { ptyId: string; code?: number; signal?: number };
-export type GitStatusEvent = { cwd: string; status: GitStatus };
+export type GitStatusEvent = { cwd: string; status: GitStatus; sessionId?: string };
 export type WatchEvent = { path: string; kind: string };
 export type TunnelStateEvent = { forwardId: string; status: NonNullable<PortForward['status']> };
 
@@ -260,7 +604,25 @@ export function sshHomeDir(sessionId: string): Promise<string> {
   return invoke('ssh_home_dir', { sessionId } as any);
 }
 
-export type SftpEntry = { name: string; path: string; is_dir: boolean };
+export type SftpFileType = 'file' | 'dir' | 'symlink' | 'fifo' | 'socket' | 'char' | 'block' | 'unknown';
+export type SftpEntry = {
+  name: string;
+  path: string;
+  is_dir: boolean;
+  size?: number;
+  mtime?: number;
+  atime?: number;
+  mode?: number;
+  permissions?: string; // ls-style, e.g. "drwxr-xr-x"
+  uid?: number;
+  gid?: number;
+  owner?: string;
+  group?: string;
+  file_type?: SftpFileType;
+  is_symlink?: boolean;
+  link_target?: string;
+  link_is_dir?: boolean;
+};
 export function sshSftpList(sessionId: string, path: string): Promise<SftpEntry[]> {
   return invoke('ssh_sftp_list', { sessionId, path } as any);
 }
@@ -269,32 +631,221 @@ export function sshSftpMkdirs(sessionId: string, path: string): Promise<void> {
   return invoke('ssh_sftp_mkdirs', { sessionId, path } as any);
 }
 
-export function sshSftpWrite(sessionId: string, remotePath: string, dataBase64: string): Promise<void> {
+export function sshSftpRename(sessionId: string, from: string, to: string, overwrite?: boolean): Promise<void> {
+  return invoke('ssh_sftp_rename', { sessionId, from, to, overwrite } as any);
+}
+
+export type SftpDeleteResult = { paths: string[]; dry_run: boolean };
+export function sshSftpDelete(sessionId: string, path: string, opts?: { recursive?: boolean; dryRun?: boolean }): Promise<SftpDeleteResult> {
+  return invoke('ssh_sftp_delete', { sessionId, path, recursive: opts?.recursive, dryRun: opts?.dryRun } as any);
+}
+
+export function sshSftpChmod(sessionId: string, path: string, mode: number): Promise<void> {
+  return invoke('ssh_sftp_chmod', { sessionId, path, mode } as any);
+}
+
+export function sshSftpChown(sessionId: string, path: string, uid?: number, gid?: number): Promise<void> {
+  return invoke('ssh_sftp_chown', { sessionId, path, uid, gid } as any);
+}
+
+export function sshSftpSymlink(sessionId: string, target: string, linkPath: string): Promise<void> {
+  return invoke('ssh_sftp_symlink', { sessionId, target, linkPath } as any);
+}
+
+export function sshSftpStat(sessionId: string, path: string): Promise<SftpEntry> {
+  return invoke('ssh_sftp_stat', { sessionId, path } as any);
+}
+
+export function sshSftpLstat(sessionId: string, path: string): Promise<SftpEntry> {
+  return invoke('ssh_sftp_lstat', { sessionId, path } as any);
+}
+
+export function sshSftpCopy(sessionId: string, from: string, to: string): Promise<void> {
+  return invoke('ssh_sftp_copy', { sessionId, from, to } as any);
+}
+
+// Single-file transfers fall back to SCP when the server has no SFTP subsystem
+export type SftpTransport = 'sftp' | 'scp';
+export type SftpTransferResult = { transport: SftpTransport; bytes: number };
+export type SftpReadResult = { data_b64: string; transport: SftpTransport };
+
+export function sshSftpWrite(sessionId: string, remotePath: string, dataBase64: string): Promise<SftpTransferResult> {
   // Tauri maps snake_case param `data_b64` to camelCase `dataB64` in JS
   return invoke('ssh_sftp_write', { sessionId, remotePath, dataB64: dataBase64 } as any);
 }
 
-export function sshDeployHelper(sessionId: string, remotePath: string): Promise<void> {
+// Edit sessions: remember the remote state on open so a save can detect concurrent changes
+export type SftpEditOpen = { edit_id: string; remote_path: string; data_b64: string; size: number; mtime?: number; hash: string };
+export type SftpEditConflict = {
+  deleted: boolean;
+  remote_size?: number;
+  remote_mtime?: number;
+  remote_hash?: string;
+  diff?: string;
+};
+export type SftpEditSaveResult = { saved: boolean; size: number; mtime?: number; hash: string; conflict?: SftpEditConflict };
+export function sshSftpEditOpen(sessionId: string, remotePath: string): Promise<SftpEditOpen> {
+  return invoke('ssh_sftp_edit_open', { sessionId, remotePath } as any);
+}
+
+export function sshSftpEditSave(editId: string, dataBase64: string, force?: boolean): Promise<SftpEditSaveResult> {
+  return invoke('ssh_sftp_edit_save', { editId, dataB64: dataBase64, force } as any);
+}
+
+export function sshSftpEditClose(editId: string): Promise<void> {
+  return invoke('ssh_sftp_edit_close', { editId } as any);
+}
+
+export type HelperDeployResult = {
+  deployed: boolean;
+  version: string;
+  os: string;
+  arch: string;
+  // Fresh install on a host whose login shell lacks cwd tracking
+  offer_shell_integration: boolean;
+};
+
+// Installs the helper build for the remote OS/arch unless health already reports this version
+export function sshDeployHelper(sessionId: string, remotePath: string): Promise<HelperDeployResult> {
   return invoke('ssh_deploy_helper', { sessionId, remotePath } as any);
 }
 
-export function sshSftpRead(sessionId: string, remotePath: string): Promise<string> {
+export function sshSftpRead(sessionId: string, remotePath: string): Promise<SftpReadResult> {
   return invoke('ssh_sftp_read', { sessionId, remotePath } as any);
 }
 
-export function sshSftpDownload(sessionId: string, remotePath: string, localPath: string): Promise<void> {
+export function sshSftpDownload(sessionId: string, remotePath: string, localPath: string): Promise<SftpTransferResult> {
   return invoke('ssh_sftp_download', { sessionId, remotePath, localPath } as any);
 }
 
-export function sshSftpDownloadDir(sessionId: string, remoteDir: string, localDir: string): Promise<void> {
-  return invoke('ssh_sftp_download_dir', { sessionId, remoteDir, localDir } as any);
+export type DownloadDirResult = {
+  method: 'tar+zstd' | 'tar+gzip' | 'tar' | 'sftp';
+  files: number;
+  dirs: number;
+  bytes: number;
+  skipped: string[];
+};
+// compress packs the directory on the server first; falls back to per-file SFTP without tar
+export function sshSftpDownloadDir(sessionId: string, remoteDir: string, localDir: string, compress?: 'gzip' | 'zstd' | 'none'): Promise<DownloadDirResult> {
+  return invoke('ssh_sftp_download_dir', { sessionId, remoteDir, localDir, compress } as any);
+}
+
+export type UploadDirResult = { files: number; dirs: number; bytes: number; skipped: number };
+export function sshSftpUploadDir(
+  sessionId: string,
+  localDir: string,
+  remoteDir: string,
+  opts?: { include?: string[]; exclude?: string[] },
+): Promise<UploadDirResult> {
+  return invoke('ssh_sftp_upload_dir', { sessionId, localDir, remoteDir, include: opts?.include, exclude: opts?.exclude } as any);
 }
 
-export type SshUploadProgress = { path: string; written: number; total: number };
+export type SshUploadProgress = {
+  path: string;
+  written: number;
+  total: number;
+  transferId?: string;
+  // Set for directory uploads, where written/total are aggregated over all files
+  file?: string;
+  filesDone?: number;
+  filesTotal?: number;
+};
 export function onSshUploadProgress(handler: (e: SshUploadProgress) => void): Promise<UnlistenFn> {
   return listen<SshUploadProgress>('SSH_UPLOAD_PROGRESS', (ev) => handler(ev.payload));
 }
 
+// Transfer manager: queued, resumable file transfers with progress
+export type TransferDirection = 'upload' | 'download';
+export type TransferStatus = 'queued' | 'running' | 'paused' | 'cancelled' | 'completed' | 'failed';
+export type TransferVerify = 'size' | 'sha256';
+export type TransferInfo = {
+  id: string;
+  session_id: string;
+  direction: TransferDirection;
+  remote_path: string;
+  local_path: string;
+  total: number;
+  transferred: number;
+  status: TransferStatus;
+  error?: string;
+  verified?: TransferVerify;
+  created_at: number;
+};
+export function sshTransferDownload(sessionId: string, remotePath: string, localPath: string, opts?: { resume?: boolean; verify?: TransferVerify }): Promise<string> {
+  return invoke('ssh_transfer_download', { sessionId, remotePath, localPath, resume: opts?.resume, verify: opts?.verify } as any);
+}
+
+export function sshTransferUpload(sessionId: string, localPath: string, remotePath: string, opts?: { resume?: boolean; verify?: TransferVerify }): Promise<string> {
+  return invoke('ssh_transfer_upload', { sessionId, localPath, remotePath, resume: opts?.resume, verify: opts?.verify } as any);
+}
+
+export function sshTransferList(sessionId?: string): Promise<TransferInfo[]> {
+  return invoke('ssh_transfer_list', { sessionId } as any);
+}
+
+export function sshTransferPause(transferId: string): Promise<void> {
+  return invoke('ssh_transfer_pause', { transferId } as any);
+}
+
+export function sshTransferResume(transferId: string): Promise<void> {
+  return invoke('ssh_transfer_resume', { transferId } as any);
+}
+
+export function sshTransferCancel(transferId: string): Promise<void> {
+  return invoke('ssh_transfer_cancel', { transferId } as any);
+}
+
+export function sshTransferClear(): Promise<void> {
+  return invoke('ssh_transfer_clear');
+}
+
+// transferId is absent and total is 0 for archive directory downloads
+export type SshDownloadProgress = { transferId?: string; path: string; written: number; total: number };
+export function onSshDownloadProgress(handler: (e: SshDownloadProgress) => void): Promise<UnlistenFn> {
+  return listen<SshDownloadProgress>('SSH_DOWNLOAD_PROGRESS', (ev) => handler(ev.payload));
+}
+
+export type SshTransferStateEvent = { transferId: string; status: TransferStatus; error?: string; verified?: TransferVerify };
+export function onSshTransferState(handler: (e: SshTransferStateEvent) => void): Promise<UnlistenFn> {
+  return listen<SshTransferStateEvent>('SSH_TRANSFER_STATE', (ev) => handler(ev.payload));
+}
+
+// Directory sync (push/pull mirror or two-way)
+export type SyncMode = 'push' | 'pull' | 'two_way';
+export type SyncActionKind = 'mkdir_local' | 'mkdir_remote' | 'upload' | 'download' | 'delete_local' | 'delete_remote';
+export type SyncAction = { kind: SyncActionKind; path: string; is_dir: boolean; size: number; reason: string; conflict: boolean };
+export type SyncPlan = {
+  plan_id: string;
+  session_id: string;
+  local_dir: string;
+  remote_dir: string;
+  mode: SyncMode;
+  actions: SyncAction[];
+  upload_bytes: number;
+  download_bytes: number;
+  conflicts: number;
+  unchanged: number;
+  skipped: number;
+};
+export type SyncOptions = { checksum?: boolean; exclude?: string[]; ignoreFile?: string };
+export type SyncResult = { applied: number; failed: { path: string; kind: SyncActionKind; error: string }[]; cancelled: boolean; bytes: number };
+export function sshSyncPlan(sessionId: string, localDir: string, remoteDir: string, mode: SyncMode, opts?: SyncOptions): Promise<SyncPlan> {
+  return invoke('ssh_sync_plan', { sessionId, localDir, remoteDir, mode, opts } as any);
+}
+export function sshSyncApply(planId: string): Promise<SyncResult> {
+  return invoke('ssh_sync_apply', { planId } as any);
+}
+export function sshSyncCancel(planId: string): Promise<void> {
+  return invoke('ssh_sync_cancel', { planId } as any);
+}
+export function sshSyncDiscard(planId: string): Promise<void> {
+  return invoke('ssh_sync_discard', { planId } as any);
+}
+export type SshSyncProgressEvent = { planId: string; path: string; action: SyncActionKind | null; done: number; total: number; bytesDone: number; bytesTotal: number };
+export function onSshSyncProgress(handler: (e: SshSyncProgressEvent) => void): Promise<UnlistenFn> {
+  return listen<SshSyncProgressEvent>('SSH_SYNC_PROGRESS', (ev) => handler(ev.payload));
+}
+
 export type ExecResult = { stdout: string; stderr: string; exit_code: number };
 export function sshExec(sessionId: string, command: string): Promise<ExecResult> {
   return invoke('ssh_exec', { sessionId, command } as any);
@@ -425,3 +976,8 @@ export type HelperExecResult = { stdout: string; stderr: string; exit_code: numb
 export function helperLocalExec(command: string, args?: string[]): Promise<HelperExecResult> {
   return invoke('helper_local_exec', { command, args } as any);
 }
+// Call a helper subcommand through the long-running agent of an SSH session
+// (or the local one). JSON output comes back parsed, anything else as text.
+export function helperCall<T = unknown>(method: string, args?: string[], sessionId?: string): Promise<T> {
+  return invoke('helper_call', { sessionId, method, args } as any);
+}